@@ -0,0 +1,363 @@
+//! Build time, memory footprint, lookup latency and batch throughput on
+//! synthetic layouts shaped like real formats.
+//!
+//! Run with `cargo bench`; `cargo bench -- elf` only runs the shapes whose
+//! name contains `elf`. Timings come from `std::time::Instant` (best of a few
+//! runs), memory from a global allocator counting live bytes.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::env;
+use std::hint::black_box;
+use std::ops::Range;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use janus_array::builder::LayoutBuilder;
+use janus_array::{CommandElements, Commands, Coordinates, DiskOffsets, File, LookupPolicy, NodeLevel, Slices};
+
+const LOOKUPS: usize = 200_000;
+const RUNS: usize = 5;
+
+struct Counting;
+
+static LIVE: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        LIVE.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        LIVE.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        LIVE.fetch_add(new_size, Ordering::Relaxed);
+        LIVE.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+// xorshift64*, so runs are repeatable.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    fn below(&mut self, n: u64) -> u64 {
+        self.next() % n.max(1)
+    }
+}
+
+// A layout as records in document order: each slice followed by its
+// commands, each command by its elements.
+struct Shape {
+    name: &'static str,
+    size: u64,
+    records: Vec<(NodeLevel, Range<u64>)>,
+    /// Nodes with a file mounted on them.
+    images: Vec<Coordinates>,
+}
+
+/// A fat Mach-O: two architectures, each with its load commands and a
+/// `__TEXT` segment split into one element per function.
+fn macho() -> Shape {
+    let mut rng = Rng(0x4d41_4348);
+    let mut records = Vec::new();
+    let arch_size = 0x80_0000;
+    for arch in 0..2 {
+        let base = 0x4000 + arch * arch_size;
+        records.push((NodeLevel::Slice, base..base + arch_size));
+        // Header and load commands
+        let mut offset = base;
+        for _ in 0..40 {
+            let size = 0x18 + rng.below(0x60) / 8 * 8;
+            records.push((NodeLevel::Command, offset..offset + size));
+            offset += size;
+        }
+        // __TEXT: functions
+        let text = base + 0x4000..base + 0x40_0000;
+        records.push((NodeLevel::Command, text.clone()));
+        let mut function = text.start;
+        while function < text.end {
+            let size = (0x10 + rng.below(0x400)).min(text.end - function);
+            records.push((NodeLevel::Element, function..function + size));
+            function += size;
+        }
+        // __DATA and __LINKEDIT with a few sections each
+        for segment in [base + 0x40_0000..base + 0x60_0000, base + 0x60_0000..base + arch_size] {
+            records.push((NodeLevel::Command, segment.clone()));
+            let step = (segment.end - segment.start) / 8;
+            for s in 0..8 {
+                let start = segment.start + s * step;
+                records.push((NodeLevel::Element, start..start + step - 0x100));
+            }
+        }
+    }
+    Shape { name: "macho", size: 0x4000 + 2 * arch_size, records, images: Vec::new() }
+}
+
+/// An ELF with many symbols: one slice, a few dozen sections, `.text` holding
+/// a quarter of a million function symbols.
+fn elf() -> Shape {
+    let mut rng = Rng(0x0045_4c46);
+    let size = 0x400_0000;
+    let mut records = vec![(NodeLevel::Slice, 0..size)];
+    let mut offset = 0x1000;
+    for section in 0..32 {
+        let text = section == 12;
+        let len = if text { 0x300_0000 } else { 0x1000 + rng.below(0x8_0000) / 0x10 * 0x10 };
+        let range = offset..(offset + len).min(size);
+        records.push((NodeLevel::Command, range.clone()));
+        if text {
+            let mut symbol = range.start;
+            for _ in 0..250_000 {
+                let size = 0x10 + rng.below(0x100) / 0x10 * 0x10;
+                if symbol + size > range.end {
+                    break;
+                }
+                records.push((NodeLevel::Element, symbol..symbol + size));
+                // Padding between functions is unclaimed
+                symbol += size + rng.below(2) * 0x10;
+            }
+        }
+        offset = range.end;
+    }
+    Shape { name: "elf", size, records, images: Vec::new() }
+}
+
+/// A partitioned disk: partitions, files, and the extents of each file.
+/// Every 16th file is a disk image of its own, left without extents for a
+/// nested layout to be mounted on it.
+fn disk(seed: u64, files: u64, file_size: u64) -> Shape {
+    let mut rng = Rng(seed);
+    let partition_size = files * file_size;
+    let mut records = Vec::new();
+    let mut images = Vec::new();
+    for partition in 0..4 {
+        let base = file_size + partition * partition_size;
+        records.push((NodeLevel::Slice, base..base + partition_size));
+        for file in 0..files {
+            let start = base + file * file_size;
+            records.push((NodeLevel::Command, start..start + file_size));
+            if file % 16 == 5 {
+                images.push(Coordinates::from_ordinals(&[partition as usize, file as usize]));
+                continue;
+            }
+            let extents = 1 + rng.below(8);
+            let extent_size = file_size / extents;
+            for extent in 0..extents {
+                let extent_start = start + extent * extent_size;
+                records.push((NodeLevel::Element, extent_start..extent_start + extent_size / 2));
+            }
+        }
+    }
+    Shape { name: "fs", size: file_size + 4 * partition_size, records, images }
+}
+
+// Mount a disk image on every image file, nested `depth` levels deep. Each
+// image fills the file holding it.
+fn mount_images(file: &mut File, images: &[Coordinates], file_size: u64, depth: usize) {
+    const FILES: u64 = 8;
+    let image_file_size = file_size / (1 + 4 * FILES) / 0x10 * 0x10;
+    if depth == 0 || image_file_size == 0 {
+        return;
+    }
+    let shape = disk(0xd15c + depth as u64, FILES, image_file_size);
+    let mut image = build_in_order(&shape);
+    mount_images(&mut image, &shape.images, image_file_size, depth - 1);
+    // One layout, shared by every mount point
+    let image = Arc::new(image);
+    for coords in images {
+        file.mount_at(coords, Arc::clone(&image));
+    }
+}
+
+// Files in order, through the containers' own insertion.
+fn build_in_order(shape: &Shape) -> File {
+    let mut file = File::with_size(shape.size);
+    let mut slice: Option<Slices> = None;
+    let mut command: Option<Commands> = None;
+    let finish_command = |slice: &mut Option<Slices>, command: &mut Option<Commands>| {
+        if let (Some(s), Some(c)) = (slice.as_mut(), command.take()) {
+            s.add_command(c);
+        }
+    };
+    for (level, range) in &shape.records {
+        match level {
+            NodeLevel::Slice => {
+                finish_command(&mut slice, &mut command);
+                if let Some(s) = slice.take() {
+                    file.add_slice(s);
+                }
+                let mut s = Slices::default();
+                s.populate_values(range.start, range.end, range.start, range.end, file.slices().len());
+                slice = Some(s);
+            }
+            NodeLevel::Command => {
+                finish_command(&mut slice, &mut command);
+                let s = slice.as_ref().expect("commands follow a slice");
+                let base = s.get_absolute_span().start();
+                let mut c = Commands::default();
+                c.populate_values(range.start, range.end, range.start - base, range.end - base, s.commands().len());
+                command = Some(c);
+            }
+            NodeLevel::Element => {
+                let c = command.as_mut().expect("elements follow a command");
+                let base = c.get_absolute_span().start();
+                let mut e = CommandElements::default();
+                e.populate_values(range.start, range.end, range.start - base, range.end - base, c.elements().len());
+                c.add_element(e);
+            }
+            NodeLevel::File => unreachable!("shapes have no file records"),
+        }
+    }
+    finish_command(&mut slice, &mut command);
+    if let Some(s) = slice {
+        file.add_slice(s);
+    }
+    file
+}
+
+// Records in a scrambled order, as a parser may find them.
+fn build_with_builder(shape: &Shape, order: &[usize]) -> File {
+    let mut builder = LayoutBuilder::new(shape.size);
+    for &i in order {
+        let (level, range) = &shape.records[i];
+        builder.push(*level, range.clone(), ()).unwrap();
+    }
+    builder.finish().file
+}
+
+fn best_of<R>(mut f: impl FnMut() -> R) -> Duration {
+    (0..RUNS)
+        .map(|_| {
+            let start = Instant::now();
+            black_box(f());
+            start.elapsed()
+        })
+        .min()
+        .unwrap()
+}
+
+fn per_lookup(elapsed: Duration, lookups: usize) -> String {
+    let ns = elapsed.as_nanos() as f64 / lookups as f64;
+    format!("{:8.1} ns/lookup  {:8.1} M lookups/s", ns, 1e3 / ns)
+}
+
+fn mib(bytes: usize) -> String {
+    format!("{:.2} MiB", bytes as f64 / (1 << 20) as f64)
+}
+
+fn report(label: &str, value: String) {
+    println!("  {:<36} {}", label, value);
+}
+
+fn bench(shape: &Shape, mounts: impl Fn(&mut File)) {
+    let count = |level| shape.records.iter().filter(|(l, _)| *l == level).count();
+    println!(
+        "{}: {} slices, {} commands, {} elements over {}",
+        shape.name,
+        count(NodeLevel::Slice),
+        count(NodeLevel::Command),
+        count(NodeLevel::Element),
+        mib(shape.size as usize)
+    );
+
+    // Build time
+    let mut rng = Rng(0x5eed);
+    let mut order: Vec<usize> = (0..shape.records.len()).collect();
+    for i in (1..order.len()).rev() {
+        order.swap(i, rng.below(i as u64 + 1) as usize);
+    }
+    report("build, in order", format!("{:8.2} ms", best_of(|| build_in_order(shape)).as_secs_f64() * 1e3));
+    report("build, LayoutBuilder shuffled", format!("{:8.2} ms", best_of(|| build_with_builder(shape, &order)).as_secs_f64() * 1e3));
+
+    // Memory: live bytes held by the built layout
+    let before = LIVE.load(Ordering::Relaxed);
+    let mut file = build_in_order(shape);
+    mounts(&mut file);
+    let held = LIVE.load(Ordering::Relaxed).saturating_sub(before);
+    let nodes = file.stats().node_count();
+    report("memory", format!("{:>11}  {:6.1} B/node", mib(held), held as f64 / nodes as f64));
+    let before = LIVE.load(Ordering::Relaxed);
+    let mut packed = build_in_order(shape);
+    mounts(&mut packed);
+    let commands = packed.pack_elements();
+    let held = LIVE.load(Ordering::Relaxed).saturating_sub(before);
+    report(
+        &format!("memory, {} commands packed", commands),
+        format!("{:>11}  {:6.1} B/node", mib(held), held as f64 / nodes as f64),
+    );
+
+    // Lookups
+    let span = file.get_absolute_span();
+    let random: Vec<u64> = (0..LOOKUPS).map(|_| span.start() + rng.below(span.len() as u64)).collect();
+    let step = (span.len() as u64 / LOOKUPS as u64).max(1);
+    let sequential: Vec<u64> = (0..LOOKUPS as u64).map(|i| span.start() + i * step).collect();
+    let slice_hits: Vec<(usize, u64)> = random
+        .iter()
+        .filter_map(|&a| {
+            let slice = file.find_address(a).ok()?.slice?;
+            Some((slice, file.get_slice(slice)?.absolute_to_relative(a).ok()?))
+        })
+        .collect();
+
+    let time_each = |addresses: &[u64], lookup: &dyn Fn(u64) -> bool| {
+        best_of(|| addresses.iter().filter(|&&a| lookup(black_box(a))).count())
+    };
+    report("find_address, random", per_lookup(time_each(&random, &|a| file.find_address(a).is_ok()), LOOKUPS));
+    report("find_address, sequential", per_lookup(time_each(&sequential, &|a| file.find_address(a).is_ok()), LOOKUPS));
+    report("find_address_with, strict", per_lookup(
+        time_each(&random, &|a| file.find_address_with(a, LookupPolicy::Strict).is_ok()),
+        LOOKUPS,
+    ));
+    report("locate", per_lookup(time_each(&random, &|a| file.locate(a).is_ok()), LOOKUPS));
+    let relative = best_of(|| slice_hits.iter().filter(|&&(s, r)| file.find_in_slice(s, black_box(r)).is_ok()).count());
+    report("find_in_slice", per_lookup(relative, slice_hits.len()));
+    // Fewer of these: finding a child by ordinal is a linear scan
+    let resolved: Vec<Coordinates> = random.iter().take(4096).filter_map(|&a| file.find_address(a).ok()).collect();
+    let resolve = best_of(|| resolved.iter().filter(|c| file.resolve(black_box(c)).is_ok()).count());
+    report("resolve (coordinates to node)", per_lookup(resolve, resolved.len()));
+    report("find_address, packed", per_lookup(time_each(&random, &|a| packed.find_address(a).is_ok()), LOOKUPS));
+    let resolve = best_of(|| resolved.iter().filter(|c| packed.resolve(black_box(c)).is_ok()).count());
+    report("resolve, packed", per_lookup(resolve, resolved.len()));
+
+    // Batch throughput
+    report("find_addresses", per_lookup(best_of(|| file.find_addresses(&random)), LOOKUPS));
+    let threads = thread::available_parallelism().map_or(4, |n| n.get());
+    report(
+        &format!("find_addresses_parallel ({} threads)", threads),
+        per_lookup(best_of(|| file.find_addresses_parallel(&random, threads)), LOOKUPS),
+    );
+    println!();
+}
+
+fn main() {
+    // Arguments that aren't flags filter shapes by name, as with the default harness
+    let filters: Vec<String> = env::args().skip(1).filter(|a| !a.starts_with('-')).collect();
+    let selected = |name: &str| filters.is_empty() || filters.iter().any(|f| name.contains(f.as_str()));
+
+    if selected("macho") {
+        bench(&macho(), |_| {});
+    }
+    if selected("elf") {
+        bench(&elf(), |_| {});
+    }
+    if selected("fs") {
+        let shape = disk(0xd15c, 4096, 0x10_0000);
+        bench(&shape, |file| mount_images(file, &shape.images, 0x10_0000, 3));
+    }
+}
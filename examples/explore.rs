@@ -0,0 +1,39 @@
+//! Interactive exploration of a small synthetic layout.
+//!
+//! Run with `cargo run --example explore` and type `help`.
+
+use std::io;
+
+use janus_array::explorer::Explorer;
+use janus_array::{CommandElements, Commands, DiskOffsets, File, Slices};
+
+fn main() -> io::Result<()> {
+    let data: Vec<u8> = (0..0x2000u32).map(|i| (i % 251) as u8).collect();
+    let mut file = File::with_size(data.len() as u64);
+
+    for s in 0..2u64 {
+        let slice_start = s * 0x1000;
+        let mut slice = Slices::default();
+        slice.populate_values(slice_start, slice_start + 0x1000, slice_start, slice_start + 0x1000, s as usize);
+
+        for c in 0..4u64 {
+            let cmd_start = slice_start + c * 0x400;
+            let mut command = Commands::default();
+            command.populate_values(cmd_start, cmd_start + 0x400, c * 0x400, (c + 1) * 0x400, c as usize);
+
+            for e in 0..8u64 {
+                let el_start = cmd_start + e * 0x80;
+                let mut element = CommandElements::default();
+                element.populate_values(el_start, el_start + 0x80, e * 0x80, (e + 1) * 0x80, e as usize);
+                command.add_element(element);
+            }
+            slice.add_command(command);
+        }
+        file.add_slice(slice);
+    }
+
+    let stdin = io::stdin();
+    Explorer::new(&file)
+        .with_data(&data)
+        .run(stdin.lock(), io::stdout())
+}
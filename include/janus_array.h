@@ -0,0 +1,99 @@
+/* Generated from src/ffi.rs by tests/ffi.rs; do not edit. */
+#ifndef JANUS_ARRAY_H
+#define JANUS_ARRAY_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+// Status code returned by every function of the C API.
+typedef int32_t JanusStatus;
+
+// Success.
+#define JANUS_OK 0
+// The address falls in a gap between slices.
+#define JANUS_NOT_FOUND 1
+// The address is outside the file.
+#define JANUS_OUT_OF_RANGE 2
+// The layout is inconsistent, or the library panicked.
+#define JANUS_BROKEN 3
+// A required pointer argument was null.
+#define JANUS_NULL_POINTER 4
+// `offset + size` runs past the end of the address space.
+#define JANUS_OVERFLOW 5
+// Records can't be added once the layout is finalized.
+#define JANUS_FINALIZED 6
+// Queries need a finalized layout.
+#define JANUS_NOT_FINALIZED 7
+// Some records could not be placed; the layout was built without them.
+#define JANUS_UNPLACED 8
+// An iteration callback asked to stop.
+#define JANUS_STOPPED 9
+
+// Opaque layout handle.
+typedef struct JanusFile JanusFile;
+
+// Result of `janus_find_address`.
+// `depth` is the number of valid ordinals: 1 for a slice, 2 for a command,
+// 3 for an element, 0 when `status` is not `JANUS_OK`. `tag` is the tag of
+// the deepest node found.
+typedef struct JanusCoordinates {
+    JanusStatus status;
+    uint32_t depth;
+    size_t slice;
+    size_t command;
+    size_t element;
+    uint64_t tag;
+} JanusCoordinates;
+
+// A node as seen by iteration callbacks.
+// `end` is exclusive and saturates at `UINT64_MAX` for a node holding the
+// last byte of the address space.
+typedef struct JanusNode {
+    uint32_t depth;
+    size_t slice;
+    size_t command;
+    size_t element;
+    uint64_t start;
+    uint64_t end;
+    uint64_t tag;
+} JanusNode;
+
+// Iteration callback; return non-zero to stop the iteration.
+typedef int32_t (*JanusVisitor)(const JanusNode *node, void *user_data);
+
+// Create an empty layout for a file of `size` bytes starting at address 0.
+// Release it with `janus_file_free`. Returns null if the handle can't be created.
+JanusFile *janus_file_new(uint64_t size);
+
+// Release a layout. Null is accepted and ignored.
+void janus_file_free(JanusFile *file);
+
+// Record a slice of `size` bytes at absolute address `offset`.
+JanusStatus janus_add_slice(JanusFile *file, uint64_t offset, uint64_t size, uint64_t tag);
+
+// Record a command; it goes under the slice containing it.
+JanusStatus janus_add_command(JanusFile *file, uint64_t offset, uint64_t size, uint64_t tag);
+
+// Record an element; it goes under the command containing it.
+JanusStatus janus_add_element(JanusFile *file, uint64_t offset, uint64_t size, uint64_t tag);
+
+// Place every record and freeze the layout. Returns `JANUS_UNPLACED` if some
+// records fit nowhere; their number is stored in `unplaced` when it is not null.
+JanusStatus janus_finalize(JanusFile *file, size_t *unplaced);
+
+// Locate the deepest node containing `address`.
+JanusCoordinates janus_find_address(const JanusFile *file, uint64_t address);
+
+// Call `visit` on every node below the file, depth first in address order.
+// Returns `JANUS_STOPPED` if the callback stopped the walk early.
+JanusStatus janus_for_each_node(const JanusFile *file, JanusVisitor visit, void *user_data);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* JANUS_ARRAY_H */
@@ -0,0 +1,90 @@
+use core::fmt;
+use core::hash::Hash;
+
+/// Address type a layout is expressed in.
+///
+/// Addresses are totally ordered and map one to one onto positions
+/// `0..=MAX.to_wide()`. All range arithmetic goes through those positions, so
+/// an implementation only has to provide the mapping. `u64` is the default
+/// everywhere; `u32` halves the memory used by large layouts of 32-bit
+/// targets, and [`Segmented`] covers `(segment, offset)` addressing.
+pub trait Address: Copy + Ord + Hash + Default + fmt::Debug + fmt::LowerHex + Send + Sync + 'static {
+    /// First address of the space.
+    const ZERO: Self;
+    /// Last address of the space.
+    const MAX: Self;
+
+    /// Position of the address in the space.
+    fn to_wide(self) -> u128;
+
+    /// Address at a position, or `None` past [`Address::MAX`].
+    fn from_wide(wide: u128) -> Option<Self>;
+}
+
+macro_rules! impl_address {
+    ($($t:ty),*) => {$(
+        impl Address for $t {
+            const ZERO: Self = 0;
+            const MAX: Self = <$t>::MAX;
+
+            fn to_wide(self) -> u128 {
+                self as u128
+            }
+
+            fn from_wide(wide: u128) -> Option<Self> {
+                <$t>::try_from(wide).ok()
+            }
+        }
+    )*};
+}
+
+impl_address!(u16, u32, u64);
+
+/// A `(segment, offset)` address.
+///
+/// Segments follow each other in the address space: the address after the
+/// last offset of a segment is offset 0 of the next one.
+///
+/// ```rust
+/// use janus_array::{Address, AddressRange, Segmented};
+///
+/// let code = AddressRange::new(Segmented::new(1, 0x100), Segmented::new(1, 0x200)).unwrap();
+/// assert!(code.contains(Segmented::new(1, 0x180)));
+/// assert!(!code.contains(Segmented::new(2, 0x180)));
+/// assert_eq!(format!("{:#x}", Segmented::new(1, 0x180)), "0x1:0x180");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Segmented {
+    pub segment: u32,
+    pub offset: u32,
+}
+
+impl Segmented {
+    pub fn new(segment: u32, offset: u32) -> Self {
+        Self { segment, offset }
+    }
+}
+
+impl Address for Segmented {
+    const ZERO: Self = Segmented { segment: 0, offset: 0 };
+    const MAX: Self = Segmented { segment: u32::MAX, offset: u32::MAX };
+
+    fn to_wide(self) -> u128 {
+        ((self.segment as u128) << 32) | self.offset as u128
+    }
+
+    fn from_wide(wide: u128) -> Option<Self> {
+        let segment = u32::try_from(wide >> 32).ok()?;
+        Some(Segmented { segment, offset: wide as u32 })
+    }
+}
+
+impl fmt::LowerHex for Segmented {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if f.alternate() {
+            write!(f, "{:#x}:{:#x}", self.segment, self.offset)
+        } else {
+            write!(f, "{:x}:{:x}", self.segment, self.offset)
+        }
+    }
+}
@@ -0,0 +1,170 @@
+use core::fmt;
+use core::ops::Range;
+
+use crate::address::Address;
+use crate::disk_offsets::OffsetLayoutsError;
+
+/// A half-open range of addresses that can reach the end of the address space.
+///
+/// `Range<u64>` can't describe a node whose last byte is at `u64::MAX`, since
+/// its exclusive end would be 2^64. Here such an end is stored as `None`.
+/// Empty ranges keep their start, which is where zero-length nodes live.
+///
+/// ```rust
+/// use janus_array::AddressRange;
+///
+/// let top = AddressRange::from_offset_size(u64::MAX - 0xf, 0x10).unwrap();
+/// assert!(top.contains(u64::MAX));
+/// assert_eq!(top.end(), None);
+/// assert_eq!(top.last(), Some(u64::MAX));
+/// assert!(AddressRange::from_offset_size(u64::MAX, 2).is_err());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AddressRange<A = u64> {
+    start: A,
+    end: Option<A>,
+}
+
+// One past the last address, as a wide integer.
+fn end_of_space<A: Address>() -> u128 {
+    A::MAX.to_wide() + 1
+}
+
+impl<A: Address> AddressRange<A> {
+    /// Half-open range `start..end`. An `end` below `start` is an error.
+    pub fn new(start: A, end: A) -> Result<Self, OffsetLayoutsError<A>> {
+        if end < start {
+            return Err(OffsetLayoutsError::InconsistentStructure(start, start..end));
+        }
+        Ok(Self { start, end: Some(end) })
+    }
+
+    // Empty range at `start` when `end` is below it.
+    pub(crate) fn clamped(start: A, end: A) -> Self {
+        Self { start, end: Some(end.max(start)) }
+    }
+
+    /// Range from `start` up to and including the last address.
+    pub fn to_end_of_space(start: A) -> Self {
+        Self { start, end: None }
+    }
+
+    /// Range from `start` up to and including `last`.
+    pub fn from_inclusive(start: A, last: A) -> Result<Self, OffsetLayoutsError<A>> {
+        if last < start {
+            return Err(OffsetLayoutsError::InconsistentStructure(start, start..last));
+        }
+        Ok(match A::from_wide(last.to_wide() + 1) {
+            Some(end) => Self { start, end: Some(end) },
+            None => Self::to_end_of_space(start),
+        })
+    }
+
+    /// Range of `size` bytes at `offset`. Reaching exactly the end of the
+    /// address space is fine, going past it is an error.
+    pub fn from_offset_size(offset: A, size: u64) -> Result<Self, OffsetLayoutsError<A>> {
+        Self::from_bounds(offset.to_wide(), offset.to_wide() + size as u128)
+            .ok_or(OffsetLayoutsError::AddressOverflow(offset, offset..A::MAX))
+    }
+
+    /// Range between two wide bounds, if it fits in the address space and
+    /// `end` isn't below `start`.
+    pub fn from_bounds(start: u128, end: u128) -> Option<Self> {
+        let start = A::from_wide(start)?;
+        if end == end_of_space::<A>() {
+            return Some(Self::to_end_of_space(start));
+        }
+        Self::new(start, A::from_wide(end)?).ok()
+    }
+
+    pub fn start(&self) -> A {
+        self.start
+    }
+
+    /// Exclusive end, or `None` when the range runs to the end of the address space.
+    pub fn end(&self) -> Option<A> {
+        self.end
+    }
+
+    /// Exclusive end as a wide integer, always defined.
+    pub fn end_wide(&self) -> u128 {
+        self.end.map_or(end_of_space::<A>(), A::to_wide)
+    }
+
+    /// Last address inside the range, or `None` when it is empty.
+    pub fn last(&self) -> Option<A> {
+        match self.end {
+            Some(end) if end == self.start => None,
+            Some(end) => A::from_wide(end.to_wide() - 1),
+            None => Some(A::MAX),
+        }
+    }
+
+    /// Number of addresses covered.
+    pub fn len(&self) -> u128 {
+        self.end_wide() - self.start.to_wide()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.end == Some(self.start)
+    }
+
+    pub fn contains(&self, address: A) -> bool {
+        self.start <= address && self.end.map_or(true, |end| address < end)
+    }
+
+    /// Whether every address of `other` is in `self`.
+    pub fn contains_range(&self, other: &AddressRange<A>) -> bool {
+        self.start <= other.start && other.end_wide() <= self.end_wide()
+    }
+
+    /// Whether the two ranges share at least one address.
+    pub fn overlaps(&self, other: &AddressRange<A>) -> bool {
+        self.start.to_wide() < other.end_wide() && other.start.to_wide() < self.end_wide()
+    }
+
+    /// The same range with a different start; the end follows if it would
+    /// end up before the start.
+    pub fn with_start(&self, start: A) -> Self {
+        match self.end {
+            Some(end) => Self::clamped(start, end),
+            None => Self::to_end_of_space(start),
+        }
+    }
+
+    /// The same range with a different exclusive end; the start follows if
+    /// it would end up after the end.
+    pub fn with_end(&self, end: A) -> Self {
+        Self::clamped(self.start.min(end), end)
+    }
+
+    /// Convert to a `Range`. A range running to the end of the address
+    /// space is cut short by one byte, since `Range` can't express it.
+    pub fn to_range(&self) -> Range<A> {
+        self.start..self.end.unwrap_or(A::MAX)
+    }
+}
+
+/// The empty range at address zero, so that a default node covers nothing.
+impl<A: Address> Default for AddressRange<A> {
+    fn default() -> Self {
+        Self { start: A::ZERO, end: Some(A::ZERO) }
+    }
+}
+
+impl<A: Address> TryFrom<Range<A>> for AddressRange<A> {
+    type Error = OffsetLayoutsError<A>;
+
+    fn try_from(range: Range<A>) -> Result<Self, Self::Error> {
+        Self::new(range.start, range.end)
+    }
+}
+
+impl<A: Address> fmt::Display for AddressRange<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.end {
+            Some(end) => write!(f, "{:#x}..{:#x}", self.start, end),
+            None => write!(f, "{:#x}..={:#x}", self.start, A::MAX),
+        }
+    }
+}
@@ -0,0 +1,73 @@
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::thread;
+
+use crate::address::Address;
+use crate::cmd_elements::CommandElements;
+use crate::commands::Commands;
+use crate::coordinates::Coordinates;
+use crate::disk_offsets::{DiskOffsets, OffsetLayoutsError};
+use crate::file::File;
+use crate::slices::Slices;
+
+// Lookups only need `&self`, so a built layout can be shared between threads
+// as-is (behind an `Arc` or a plain reference). Keep it that way.
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<File>();
+    assert_send_sync::<Slices>();
+    assert_send_sync::<Commands>();
+    assert_send_sync::<CommandElements>();
+    assert_send_sync::<File<u32>>();
+};
+
+impl<A: Address> File<A> {
+    /// Look up every address in `addresses`, returning results in the same order.
+    pub fn find_addresses(&self, addresses: &[A]) -> Vec<Result<Coordinates, OffsetLayoutsError<A>>> {
+        addresses.iter().map(|&addr| self.find_address(addr)).collect()
+    }
+
+    /// Same as [`File::find_addresses`], spread over `threads` scoped worker threads.
+    ///
+    /// Only available with the `std` feature.
+    ///
+    /// ```rust
+    /// use janus_array::{File, Slices, Commands, DiskOffsets};
+    ///
+    /// let mut file = File::with_size(0x1000);
+    /// let mut slice = Slices::default();
+    /// slice.populate_values(0, 0x1000, 0, 0x1000, 0);
+    /// let mut command = Commands::default();
+    /// command.populate_values(0, 0x1000, 0, 0x1000, 0);
+    /// slice.add_command(command);
+    /// file.add_slice(slice);
+    ///
+    /// let addresses: Vec<u64> = (0..0x1000).step_by(0x10).collect();
+    /// let results = file.find_addresses_parallel(&addresses, 4);
+    /// assert_eq!(results.len(), addresses.len());
+    /// assert!(results.iter().all(|r| r.is_ok()));
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn find_addresses_parallel(
+        &self,
+        addresses: &[A],
+        threads: usize,
+    ) -> Vec<Result<Coordinates, OffsetLayoutsError<A>>> {
+        let threads = threads.max(1);
+        if threads == 1 || addresses.len() < 2 {
+            return self.find_addresses(addresses);
+        }
+
+        let chunk = (addresses.len() + threads - 1) / threads;
+        thread::scope(|scope| {
+            let workers: Vec<_> = addresses
+                .chunks(chunk)
+                .map(|part| scope.spawn(move || self.find_addresses(part)))
+                .collect();
+            workers
+                .into_iter()
+                .flat_map(|w| w.join().expect("lookup worker panicked"))
+                .collect()
+        })
+    }
+}
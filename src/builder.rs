@@ -0,0 +1,388 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use crate::address_range::AddressRange;
+use crate::cmd_elements::CommandElements;
+use crate::commands::Commands;
+use crate::coordinates::Coordinates;
+use crate::disk_offsets::{DiskOffsets, OffsetLayoutsError};
+use crate::file::File;
+use crate::slices::Slices;
+use crate::types::NodeLevel;
+
+/// Builds a [`File`] from records discovered in any order.
+///
+/// Parsers often find structures out of order (symbols before the sections
+/// holding them, say). The builder collects `(level, absolute range, payload)`
+/// records as they come and, on [`LayoutBuilder::finish`], places every
+/// record under the node of the level above that contains it, assigns
+/// ordinals in address order and computes parent-relative ranges.
+///
+/// ```rust
+/// use janus_array::{DiskOffsets, NodeLevel};
+/// use janus_array::builder::LayoutBuilder;
+///
+/// let mut builder = LayoutBuilder::new(0x1000);
+/// builder.push(NodeLevel::Element, 0x120..0x130, "symbol").unwrap();
+/// builder.push(NodeLevel::Command, 0x100..0x200, "section").unwrap();
+/// builder.push(NodeLevel::Slice, 0x0..0x800, "segment").unwrap();
+/// builder.push(NodeLevel::Element, 0x900..0x910, "stray").unwrap();
+///
+/// let built = builder.finish();
+/// assert_eq!(built.file.find_address(0x125).unwrap().element, Some(0));
+/// assert_eq!(built.payloads.len(), 3);
+/// assert_eq!(built.orphans[0].payload, "stray");
+/// ```
+#[derive(Debug, Clone)]
+pub struct LayoutBuilder<P> {
+    file_range: AddressRange,
+    records: Vec<Record<P>>,
+    options: BuildOptions,
+}
+
+/// What to do with a record that overlaps a node of the level above without
+/// fitting inside it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StraddlePolicy {
+    /// Leave it out and report it as an orphan.
+    #[default]
+    Orphan,
+    /// Keep the part inside the parent it overlaps first.
+    Clip,
+}
+
+/// What to do with records of the same level that overlap each other, making
+/// it ambiguous which one an address (or a child) belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AmbiguityPolicy {
+    /// Keep the one starting first, orphan the others.
+    #[default]
+    KeepFirst,
+    /// Keep the larger one, orphan the others.
+    KeepLarger,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BuildOptions {
+    pub straddling: StraddlePolicy,
+    pub ambiguous: AmbiguityPolicy,
+}
+
+#[derive(Debug, Clone)]
+struct Record<P> {
+    level: NodeLevel,
+    range: AddressRange,
+    payload: P,
+}
+
+/// Why a record could not be placed in the tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrphanReason {
+    /// No node of the level above overlaps the record at all.
+    NoParent,
+    /// The record overlaps a node of the level above without fitting inside it,
+    /// and the builder was not asked to clip it.
+    Straddles,
+    /// The record overlaps a sibling that was kept instead of it.
+    OverlapsSibling,
+    /// Records at file level can't be placed: the root is given to the builder.
+    RootLevel,
+}
+
+#[derive(Debug, Clone)]
+pub struct Orphan<P> {
+    pub level: NodeLevel,
+    pub range: AddressRange,
+    pub payload: P,
+    pub reason: OrphanReason,
+}
+
+/// Result of [`LayoutBuilder::finish`].
+#[derive(Debug, Clone)]
+pub struct BuiltLayout<P> {
+    pub file: File,
+    /// Payload of every placed record, in document order.
+    pub payloads: Vec<(Coordinates, P)>,
+    /// Records that fit nowhere, in the order they were pushed.
+    pub orphans: Vec<Orphan<P>>,
+}
+
+// A record accepted at some level, waiting for its children.
+struct Placed {
+    record: usize,
+    range: AddressRange,
+    parent: usize,
+}
+
+impl<P> LayoutBuilder<P> {
+    pub fn new(file_size: u64) -> Self {
+        Self::with_span(AddressRange::default().with_end(file_size))
+    }
+
+    /// Builder for a root that does not start at address 0. Fails if the
+    /// range is inverted.
+    pub fn with_range(file_range: Range<u64>) -> Result<Self, OffsetLayoutsError> {
+        Ok(Self::with_span(file_range.try_into()?))
+    }
+
+    /// Builder for a root that may reach the last byte of the address space.
+    pub fn with_span(file_range: AddressRange) -> Self {
+        Self { file_range, records: Vec::new(), options: BuildOptions::default() }
+    }
+
+    /// Change how straddling and overlapping records are handled.
+    pub fn with_options(mut self, options: BuildOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Number of records pushed so far.
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    /// Record a node. Nothing is placed until [`LayoutBuilder::finish`].
+    /// Fails if the range is inverted.
+    pub fn push(&mut self, level: NodeLevel, range: Range<u64>, payload: P) -> Result<(), OffsetLayoutsError> {
+        self.push_span(level, range.try_into()?, payload);
+        Ok(())
+    }
+
+    /// Like [`LayoutBuilder::push`], for a range that may reach the last
+    /// byte of the address space.
+    pub fn push_span(&mut self, level: NodeLevel, range: AddressRange, payload: P) {
+        self.records.push(Record { level, range, payload });
+    }
+
+    /// Record a node given as `(offset, size)`; `size` may be 0. Fails if the
+    /// node would run past the end of the address space.
+    pub fn push_sized(&mut self, level: NodeLevel, offset: u64, size: u64, payload: P) -> Result<(), OffsetLayoutsError> {
+        self.push_span(level, AddressRange::from_offset_size(offset, size)?, payload);
+        Ok(())
+    }
+
+    pub fn finish(self) -> BuiltLayout<P> {
+        let mut orphaned: Vec<Option<OrphanReason>> = vec![None; self.records.len()];
+        for (i, record) in self.records.iter().enumerate() {
+            if record.level == NodeLevel::File {
+                orphaned[i] = Some(OrphanReason::RootLevel);
+            }
+        }
+
+        let root = [Placed { record: usize::MAX, range: self.file_range, parent: 0 }];
+        let slices = self.place(NodeLevel::Slice, &root, &mut orphaned);
+        let commands = self.place(NodeLevel::Command, &slices, &mut orphaned);
+        let elements = self.place(NodeLevel::Element, &commands, &mut orphaned);
+
+        // Hand payloads out by record index
+        let mut payloads: Vec<Option<P>> = Vec::with_capacity(self.records.len());
+        let mut orphans = Vec::new();
+        for (record, reason) in self.records.into_iter().zip(orphaned) {
+            match reason {
+                Some(reason) => {
+                    orphans.push(Orphan { level: record.level, range: record.range, payload: record.payload, reason });
+                    payloads.push(None);
+                }
+                None => payloads.push(Some(record.payload)),
+            }
+        }
+
+        let mut file = File::with_span(self.file_range);
+        let mut placed_payloads = Vec::new();
+
+        let mut next_command = 0;
+        let mut next_element = 0;
+        for (s, slice_rec) in slices.iter().enumerate() {
+            let mut slice = Slices::default();
+            set_ranges(&mut slice, slice_rec.range, self.file_range.start(), s);
+            push_payload(&mut placed_payloads, &mut payloads, slice_rec, &[s]);
+
+            let mut c = 0;
+            while next_command < commands.len() && commands[next_command].parent == s {
+                let command_rec = &commands[next_command];
+                let mut command = Commands::default();
+                set_ranges(&mut command, command_rec.range, slice_rec.range.start(), c);
+                push_payload(&mut placed_payloads, &mut payloads, command_rec, &[s, c]);
+
+                let mut e = 0;
+                while next_element < elements.len() && elements[next_element].parent == next_command {
+                    let element_rec = &elements[next_element];
+                    let mut element = CommandElements::default();
+                    set_ranges(&mut element, element_rec.range, command_rec.range.start(), e);
+                    push_payload(&mut placed_payloads, &mut payloads, element_rec, &[s, c, e]);
+                    command.add_element(element);
+                    next_element += 1;
+                    e += 1;
+                }
+                slice.add_command(command);
+                next_command += 1;
+                c += 1;
+            }
+            file.add_slice(slice);
+        }
+
+        BuiltLayout { file, payloads: placed_payloads, orphans }
+    }
+
+    // Accept the records of `level` that fit inside one of `parents`, which
+    // must be sorted and disjoint. The result is sorted and disjoint as well.
+    fn place(&self, level: NodeLevel, parents: &[Placed], orphaned: &mut [Option<OrphanReason>]) -> Vec<Placed> {
+        let mut candidates: Vec<Placed> = Vec::new();
+        for (i, record) in self.records.iter().enumerate() {
+            if record.level != level || orphaned[i].is_some() {
+                continue;
+            }
+            match (find_parent(parents, &record.range), self.options.straddling) {
+                (Fit::Inside(parent), _) => {
+                    candidates.push(Placed { record: i, range: record.range, parent })
+                }
+                (Fit::Straddles(parent), StraddlePolicy::Clip) => {
+                    let bounds = parents[parent].range;
+                    let start = record.range.start().max(bounds.start());
+                    let end = record.range.end_wide().min(bounds.end_wide());
+                    let range = AddressRange::from_bounds(start as u128, end).unwrap_or(bounds);
+                    candidates.push(Placed { record: i, range, parent })
+                }
+                (Fit::Straddles(_), StraddlePolicy::Orphan) => orphaned[i] = Some(OrphanReason::Straddles),
+                (Fit::Outside, _) => orphaned[i] = Some(OrphanReason::NoParent),
+            }
+        }
+        candidates.sort_by_key(|p| (p.range.start(), p.range.end_wide(), p.record));
+
+        let mut accepted: Vec<Placed> = Vec::with_capacity(candidates.len());
+        // Zero-length records own no bytes and never overlap anything, so
+        // only the last non-empty record kept matters
+        let mut last_owner: Option<usize> = None;
+        for candidate in candidates {
+            if candidate.range.is_empty() {
+                accepted.push(candidate);
+                continue;
+            }
+            let previous = match last_owner {
+                Some(index) if candidate.range.overlaps(&accepted[index].range) => index,
+                _ => {
+                    last_owner = Some(accepted.len());
+                    accepted.push(candidate);
+                    continue;
+                }
+            };
+            let keep_candidate = self.options.ambiguous == AmbiguityPolicy::KeepLarger
+                && candidate.range.len() > accepted[previous].range.len();
+            if keep_candidate {
+                orphaned[accepted[previous].record] = Some(OrphanReason::OverlapsSibling);
+                accepted.remove(previous);
+                last_owner = Some(accepted.len());
+                accepted.push(candidate);
+            } else {
+                orphaned[candidate.record] = Some(OrphanReason::OverlapsSibling);
+            }
+        }
+        accepted
+    }
+}
+
+enum Fit {
+    Inside(usize),
+    Straddles(usize),
+    Outside,
+}
+
+fn find_parent(parents: &[Placed], range: &AddressRange) -> Fit {
+    let after = parents.partition_point(|p| p.range.start() <= range.start());
+    let mut exact_empty = None;
+    // Same preference as lookups: a parent owning bytes wins over a
+    // zero-length parent, which can only hold zero-length children
+    for index in (0..after).rev() {
+        let parent = &parents[index].range;
+        if parent.is_empty() {
+            if range.is_empty() && parent.start() == range.start() {
+                exact_empty = Some(index);
+            }
+            continue;
+        }
+        if parent.contains(range.start()) {
+            return if parent.contains_range(range) { Fit::Inside(index) } else { Fit::Straddles(index) };
+        }
+        break;
+    }
+    if let Some(index) = exact_empty {
+        return Fit::Inside(index);
+    }
+    match parents[after..].iter().position(|p| !p.range.is_empty()) {
+        Some(offset) if (parents[after + offset].range.start() as u128) < range.end_wide() => {
+            Fit::Straddles(after + offset)
+        }
+        _ => Fit::Outside,
+    }
+}
+
+fn set_ranges<T: DiskOffsets>(node: &mut T, range: AddressRange, parent_start: u64, ordinal: usize) {
+    let start = range.start() - parent_start;
+    let relative = AddressRange::from_bounds(start as u128, range.end_wide() - parent_start as u128);
+    node.set_absolute_span(range);
+    node.set_relative_span(relative.unwrap_or(range));
+    node.set_ordinal(ordinal);
+}
+
+fn push_payload<P>(out: &mut Vec<(Coordinates, P)>, payloads: &mut [Option<P>], placed: &Placed, path: &[usize]) {
+    if let Some(payload) = payloads[placed.record].take() {
+        out.push((Coordinates::from_ordinals(path), payload));
+    }
+}
+
+impl File {
+    /// Derive a hierarchy from flat lists of ranges, one list per level.
+    ///
+    /// `levels[0]` holds the slices, `levels[1]` the commands and `levels[2]`
+    /// the elements; each range goes under the range of the previous level
+    /// containing it. The file spans from 0 to the highest end. Any range that
+    /// can't be placed (outside every parent, straddling one, or overlapping a
+    /// sibling) is an error, and so is an inverted range; use
+    /// [`File::from_ranges_with`] to get the orphans back instead.
+    ///
+    /// ```rust
+    /// use janus_array::{File, DiskOffsets};
+    ///
+    /// let file = File::from_ranges(vec![
+    ///     vec![0..0x1000],
+    ///     vec![0x100..0x200, 0x0..0x100],
+    ///     vec![0x180..0x190],
+    /// ]).unwrap();
+    /// let coords = file.find_address(0x184).unwrap();
+    /// assert_eq!((coords.slice, coords.command, coords.element), (Some(0), Some(1), Some(0)));
+    /// ```
+    pub fn from_ranges(levels: Vec<Vec<Range<u64>>>) -> Result<File, OffsetLayoutsError> {
+        let built = File::from_ranges_with(levels, BuildOptions::default())?;
+        match built.orphans.into_iter().next() {
+            Some(orphan) => Err(OffsetLayoutsError::UnplacedRange(orphan.range.to_range())),
+            None => Ok(built.file),
+        }
+    }
+
+    /// Like [`File::from_ranges`], with explicit handling of straddling and
+    /// overlapping ranges. Ranges that still can't be placed are returned as
+    /// orphans whose payload is their index in their level's list; inverted
+    /// ranges are still an error.
+    pub fn from_ranges_with(
+        levels: Vec<Vec<Range<u64>>>,
+        options: BuildOptions,
+    ) -> Result<BuiltLayout<usize>, OffsetLayoutsError> {
+        if levels.len() > 3 {
+            return Err(OffsetLayoutsError::TooManyLevels(levels.len()));
+        }
+        let end = levels.iter().flatten().map(|r| r.end).max().unwrap_or(0);
+        let mut builder = LayoutBuilder::new(end).with_options(options);
+        let kinds = [NodeLevel::Slice, NodeLevel::Command, NodeLevel::Element];
+        for (level, ranges) in kinds.into_iter().zip(levels) {
+            for (index, range) in ranges.into_iter().enumerate() {
+                builder.push(level, range, index)?;
+            }
+        }
+        Ok(builder.finish())
+    }
+}
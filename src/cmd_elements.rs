@@ -1,162 +1,131 @@
-use std::ops::Range;
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+
+use crate::address::Address;
+use crate::address_range::AddressRange;
 
 use crate::coordinates::Coordinates;
-use crate::types::ChildrenArray;
+use crate::lookup::{Lookup, LookupPolicy, Termination};
+use crate::file::File;
+use crate::mount::{locate_mounted, Mountable};
+use crate::labels::{Labeled, NodeTags};
+use crate::types::{ChildrenArray, NodeLevel};
 use crate::disk_offsets::DiskOffsets;
 use crate::disk_offsets::OffsetLayoutsError;
 
-use crate::cmd_elements;
-
 // Assumptions and Conventions:
 // we adopt natural Rust's indexing method (first object index is 0)
 
 #[derive(Clone,Debug)]
-pub struct CommandElements{
-    pub start_abs_address: u64,
-    pub end_abs_address: u64,
-    pub start_rel_address: u64,
-    pub end_rel_address: u64,
+pub struct CommandElements<A = u64>{
+    absolute_range:AddressRange<A>,
+    relative_range:AddressRange<A>,
 
-    pub absolute_range:Range<u64>,
-    pub relative_range:Range<u64>,
+    my_ordinal:usize,
 
-    pub my_ordinal:usize,
-}
+    tags:Option<Box<NodeTags>>,
 
-impl Default for CommandElements{
-    fn default () -> Self {
-        let empty_range: Range<u64> = 0..0;
-        Self { 
-            start_abs_address: 0, 
-            end_abs_address: 0, 
-            start_rel_address: 0, 
-            end_rel_address: 0, 
-            absolute_range: empty_range.clone(), 
-            relative_range: empty_range, 
-            my_ordinal: 0 }
-    }
+    mount:Option<Arc<File<A>>>,
 }
 
 impl CommandElements {
-    fn new () -> Self {
-        let empty_range: Range<u64> = 0..0;
-        Self { 
-            start_abs_address: 0, 
-            end_abs_address: 0, 
-            start_rel_address: 0, 
-            end_rel_address: 0, 
-            absolute_range: empty_range.clone(), 
-            relative_range: empty_range, 
-            my_ordinal: 0 
-        }
+    pub fn new () -> Self {
+        Self::default()
     }
+}
 
-    fn populate_values(&mut self, start_abs:u64, end_abs:u64, start_rel:u64, end_rel:u64, ordinal:usize){
-        self.set_absolutes(start_abs, end_abs); 
-        self.set_relatives(start_rel, end_rel);
-        self.set_ordinal(ordinal);
-    }
-
-    fn set_absolutes(&mut self, start_abs:u64, end_abs:u64){
-        self.start_abs_address  =   start_abs; 
-        self.end_abs_address    =   end_abs; 
-
-        self.absolute_range = self.start_abs_address..self.end_abs_address;
+impl Default for CommandElements {
+    fn default() -> Self {
+        Self::with_spans(AddressRange::default(), AddressRange::default(), 0)
     }
+}
 
-    fn set_relatives(&mut self, start_rel:u64, end_rel:u64){
-        self.start_rel_address  =   start_rel; 
-        self.end_rel_address    =   end_rel;
-
-        self.relative_range = self.start_rel_address..self.end_rel_address;
+impl<A: Address> CommandElements<A> {
+    /// Element with the given ranges, for any address type
+    pub fn with_spans(absolute: AddressRange<A>, relative: AddressRange<A>, ordinal: usize) -> Self {
+        Self { absolute_range: absolute, relative_range: relative, my_ordinal: ordinal, tags: None, mount: None }
     }
 
-    fn set_ordinal(&mut self, ordinal:usize){
-        self.my_ordinal=ordinal;
+    /// Like [`DiskOffsets::find_address`], also telling where the search
+    /// stopped. Elements are always leaves, unless a file is mounted on them.
+    pub fn locate(&self, address: A) -> Result<Lookup, OffsetLayoutsError<A>> {
+        self.locate_with(address, LookupPolicy::default())
     }
 
-    fn contains_address(self,an_address:u64)->bool{
-        self.absolute_range.contains(&an_address)
+    /// [`CommandElements::locate`] under an explicit [`LookupPolicy`], which
+    /// only matters inside a mounted file.
+    pub fn locate_with(&self, address: A, policy: LookupPolicy) -> Result<Lookup, OffsetLayoutsError<A>> {
+        if !self.contains_absolute_address(address) {
+            return Err(OffsetLayoutsError::AddressOutsideCurrentScope(address, self.get_absolute_range()));
+        }
+        let mut lookup = match locate_mounted(self, address, policy) {
+            Some(lookup) => lookup?,
+            None => Lookup { coordinates: Coordinates::new(), level: NodeLevel::Element, termination: Termination::Leaf },
+        };
+        lookup.coordinates.set_element(self.my_ordinal);
+        Ok(lookup)
     }
-
 }
 
-
-
-impl DiskOffsets for CommandElements {
-    fn set_start_abs_address(&mut self, start_abs:u64){
-        self.start_abs_address = start_abs; 
-    }
-
-    fn set_start_rel_address(&mut self, start_rel:u64){
-        self.start_rel_address = start_rel; 
-    }
-
-    fn set_end_abs_address(&mut self, end_abs:u64){
-        self.end_abs_address = end_abs; 
+impl<A: Address> DiskOffsets<A> for CommandElements<A> {
+    fn set_absolute_span(&mut self, span:AddressRange<A>){
+        self.absolute_range = span;
     }
 
-    fn set_end_rel_address(&mut self, end_rel:u64){
-        self.end_rel_address = end_rel; 
+    fn set_relative_span(&mut self, span:AddressRange<A>){
+        self.relative_range = span;
     }
 
-    fn set_absolute_range_explicit(&mut self, range:Range<u64>){
-        self.absolute_range=range;
+    fn set_ordinal(&mut self, ordinal:usize){
+        self.my_ordinal=ordinal;
     }
 
-    fn set_absolute_range_implicit(&mut self){
-        self.absolute_range = self.start_abs_address..self.end_abs_address;
+    fn get_ordinal(&self)->usize {
+        self.my_ordinal
     }
 
-    fn set_relative_range_explicit(&mut self, range:Range<u64>){
-        self.relative_range=range;
+    fn get_absolute_span(&self)->AddressRange<A> {
+        self.absolute_range
     }
 
-    fn set_relative_range_implicit(&mut self){
-        self.relative_range = self.start_rel_address..self.end_rel_address;
+    fn get_relative_span(&self)->AddressRange<A> {
+        self.relative_range
     }
 
-    fn set_ordinal(&mut self, ordinal:usize){
-        self.my_ordinal=ordinal;
+    fn has_children(&self)->bool{
+        false
     }
 
-    fn get_absolute_range(&self)->Range<u64> {
-        self.absolute_range.clone()
+    fn get_children(&self)->Option<ChildrenArray<A>> {
+        None
     }
 
-    fn get_relative_range(&self)->Range<u64> {
-        self.relative_range.clone()
+    fn sort_children(&mut self){
+        // by definition, an element has no children.
     }
 
-    fn get_max_abs_address(&self)->u64 {
-        self.end_abs_address
+    fn find_address(&self, absolute_address:A) -> Result<Coordinates, OffsetLayoutsError<A>>{
+        self.locate(absolute_address).map(|lookup| lookup.coordinates)
     }
 
-    fn get_min_abs_address(&self)->u64 {
-        self.start_abs_address
-    }
+}
 
-    fn has_children(&self)->bool{
-        false
+impl<A> Labeled for CommandElements<A> {
+    fn tags(&self) -> Option<&NodeTags> {
+        self.tags.as_deref()
     }
 
-    fn get_children(&mut self)->Option<ChildrenArray> {
-        None
+    fn tags_mut(&mut self) -> &mut Option<Box<NodeTags>> {
+        &mut self.tags
     }
+}
 
-    fn sort_children(&mut self){
-        // by definition, an element has no children.
+impl<A: Address> Mountable<A> for CommandElements<A> {
+    fn mount_point(&self) -> Option<&Arc<File<A>>> {
+        self.mount.as_ref()
     }
 
-    fn find_address(&mut self, absolute_address:u64) -> Result<Coordinates, OffsetLayoutsError>{
-        let absolute_range = self.get_absolute_range();
-        if absolute_range.contains(&absolute_address){
-            let mut retval = Coordinates::new();
-            retval.set_element(self.my_ordinal);
-            return Ok(retval);
-        } else {
-            Err(OffsetLayoutsError::AddressOutsideCurrentScope(absolute_address,absolute_range))
-        }
+    fn mount_point_mut(&mut self) -> &mut Option<Arc<File<A>>> {
+        &mut self.mount
     }
-
 }
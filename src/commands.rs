@@ -1,124 +1,271 @@
-use std::ops::Range;
-use crate::disk_offsets::{DiskOffsets, OffsetLayoutsError};
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use crate::address::Address;
+use crate::address_range::AddressRange;
+use alloc::sync::Arc;
+use crate::disk_offsets::{child_error, insert_sorted, locate_child, order_key, termination, update_sorted, DiskOffsets, OffsetLayoutsError};
 use crate::cmd_elements::CommandElements;
-use crate::types::ChildrenArray;
+use crate::types::{ChildrenArray, NodeLevel};
 use crate::coordinates::Coordinates;
+use crate::lookup::{Lookup, LookupPolicy, Termination};
+use crate::file::File;
+use crate::mount::{locate_mounted, Mountable};
+use crate::labels::{Labeled, NodeTags};
+use crate::packed::{PackedElement, PackedElements};
 
 #[derive(Clone,Debug)]
-pub struct Commands{
-    pub start_abs_address: u64,
-    pub end_abs_address: u64,
-    pub start_rel_address: u64,
-    pub end_rel_address: u64,
+pub struct Commands<A = u64>{
+    absolute_range:AddressRange<A>,
+    relative_range:AddressRange<A>,
 
-    pub absolute_range:Range<u64>,
-    pub relative_range:Range<u64>,
+    my_ordinal:usize,
 
-    pub my_ordinal:usize,
+    // Kept sorted by start address; shared copy-on-write between clones
+    elements:Option<Elements<A>>,
 
-    pub elements:Option<Vec<CommandElements>>
+    tags:Option<Box<NodeTags>>,
+
+    mount:Option<Arc<File<A>>>,
 }
 
-impl Default for Commands{
-    fn default () -> Self {
-        let empty_range: Range<u64> = 0..0;
-        Self { 
-            start_abs_address: 0, 
-            end_abs_address: 0, 
-            start_rel_address: 0, 
-            end_rel_address: 0, 
-            absolute_range: empty_range.clone(), 
-            relative_range: empty_range, 
-            my_ordinal: 0,
-            elements: None,
-        }
-    }
+// Elements as nodes, or as columns once `pack_elements` found them plain enough
+#[derive(Clone,Debug)]
+enum Elements<A> {
+    Nodes(Arc<Vec<CommandElements<A>>>),
+    Packed(Arc<PackedElements>),
 }
 
 impl Commands{
-    fn new() -> Self {
-        let empty_range: Range<u64> = 0..0;
-        Self { 
-            start_abs_address: 0, 
-            end_abs_address: 0, 
-            start_rel_address: 0, 
-            end_rel_address: 0, 
-            absolute_range: empty_range.clone(), 
-            relative_range: empty_range, 
-            my_ordinal: 0,
-            elements: None,
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Default for Commands {
+    fn default() -> Self {
+        Self::with_spans(AddressRange::default(), AddressRange::default(), 0)
+    }
+}
+
+impl<A: Address> Commands<A>{
+    /// Command with the given ranges and no elements, for any address type
+    pub fn with_spans(absolute: AddressRange<A>, relative: AddressRange<A>, ordinal: usize) -> Self {
+        Self { absolute_range: absolute, relative_range: relative, my_ordinal: ordinal, elements: None, tags: None, mount: None }
+    }
+
+    /// Elements of the command, sorted by start address; empty while they
+    /// are packed, see [`Commands::packed_elements`]
+    pub fn elements(&self) -> &[CommandElements<A>] {
+        match &self.elements {
+            Some(Elements::Nodes(elements)) => elements,
+            _ => &[],
         }
     }
 
+    /// Number of elements, packed or not
+    pub fn element_count(&self) -> usize {
+        match &self.elements {
+            Some(Elements::Nodes(elements)) => elements.len(),
+            Some(Elements::Packed(packed)) => packed.len(),
+            None => 0,
+        }
+    }
 
-}
+    /// Add an element to the command, keeping elements sorted by start address.
+    /// Packed elements are unpacked first.
+    pub fn add_element(&mut self, element: CommandElements<A>) {
+        if self.elements.is_none() {
+            self.elements = Some(Elements::Nodes(Default::default()));
+        }
+        if let Some(elements) = self.elements_mut() {
+            insert_sorted(elements, element);
+        }
+    }
 
-impl DiskOffsets for Commands {
-    fn set_start_abs_address(&mut self, start_abs:u64){
-        self.start_abs_address = start_abs; 
+    /// Get the element whose ordinal is `ordinal`; `None` while the elements
+    /// are packed, see [`Commands::packed_element`]
+    pub fn get_element(&self, ordinal: usize) -> Option<&CommandElements<A>> {
+        self.elements().iter().find(|e| e.get_ordinal() == ordinal)
     }
 
-    fn set_start_rel_address(&mut self, start_rel:u64){
-        self.start_rel_address = start_rel; 
+    /// Modify the element whose ordinal is `ordinal` in place, then restore the sort order.
+    /// Packed elements are unpacked first.
+    pub fn update_element<R>(&mut self, ordinal: usize, f: impl FnOnce(&mut CommandElements<A>) -> R) -> Option<R> {
+        update_sorted(self.elements_mut()?, ordinal, f)
     }
 
-    fn set_end_abs_address(&mut self, end_abs:u64){
-        self.end_abs_address = end_abs; 
+    /// Remove and return the element whose ordinal is `ordinal`.
+    /// Packed elements are unpacked first.
+    pub fn remove_element(&mut self, ordinal: usize) -> Option<CommandElements<A>> {
+        let elements = self.elements_mut()?;
+        let index = elements.iter().position(|e| e.get_ordinal() == ordinal)?;
+        Some(elements.remove(index))
     }
 
-    fn set_end_rel_address(&mut self, end_rel:u64){
-        self.end_rel_address = end_rel; 
+    /// Store the elements as two columns of 32-bit offsets from the command
+    /// start, with ordinals implied by their position: 8 bytes an element
+    /// instead of a whole [`CommandElements`]. Queries answer the same, and
+    /// any change to an element unpacks them again.
+    ///
+    /// Only plain elements qualify: numbered `0..n` in address order, with
+    /// relative ranges measured from the command start, no label, kind or
+    /// mount, and ending less than 4 GiB past the command start. Returns
+    /// whether the elements are packed.
+    pub fn pack_elements(&mut self) -> bool {
+        if let Some(Elements::Nodes(elements)) = &self.elements {
+            if let Some(packed) = PackedElements::pack(self.get_min_abs_address(), elements) {
+                self.elements = Some(Elements::Packed(Arc::new(packed)));
+            }
+        }
+        self.is_packed()
     }
 
-    fn set_absolute_range_explicit(&mut self, range:Range<u64>){
-        self.absolute_range=range;
+    /// Turn packed elements back into [`CommandElements`].
+    pub fn unpack_elements(&mut self) {
+        self.elements_mut();
     }
 
-    fn set_absolute_range_implicit(&mut self){
-        self.absolute_range = self.start_abs_address..self.end_abs_address;
+    pub fn is_packed(&self) -> bool {
+        matches!(self.elements, Some(Elements::Packed(_)))
     }
 
-    fn set_relative_range_explicit(&mut self, range:Range<u64>){
-        self.relative_range=range;
+    /// Packed elements in address order; empty unless the command is packed
+    pub fn packed_elements(&self) -> impl Iterator<Item = PackedElement<A>> + '_ {
+        let count = if self.is_packed() { self.element_count() } else { 0 };
+        (0..count).filter_map(move |index| self.packed_element(index))
     }
 
-    fn set_relative_range_implicit(&mut self){
-        self.relative_range = self.start_rel_address..self.end_rel_address;
+    /// Get the packed element whose ordinal is `ordinal`, in constant time
+    pub fn packed_element(&self, ordinal: usize) -> Option<PackedElement<A>> {
+        match &self.elements {
+            Some(Elements::Packed(packed)) => PackedElement::new(packed, ordinal),
+            _ => None,
+        }
     }
 
-    fn set_ordinal(&mut self, ordinal:usize){
-        self.my_ordinal=ordinal;
+    /// Elements as nodes, unpacking them if needed
+    pub(crate) fn elements_mut(&mut self) -> Option<&mut Vec<CommandElements<A>>> {
+        let elements = self.elements.as_mut()?;
+        if let Elements::Packed(packed) = elements {
+            *elements = Elements::Nodes(Arc::new(packed.unpack()?));
+        }
+        match elements {
+            Elements::Nodes(nodes) => Some(Arc::make_mut(nodes)),
+            Elements::Packed(_) => None,
+        }
+    }
+
+    /// Like [`DiskOffsets::find_address`], also telling where the search
+    /// stopped: in an element, or in the command itself and why.
+    pub fn locate(&self, address: A) -> Result<Lookup, OffsetLayoutsError<A>> {
+        self.locate_with(address, LookupPolicy::default())
+    }
+
+    /// [`Commands::locate`] under an explicit [`LookupPolicy`].
+    pub fn locate_with(&self, address: A, policy: LookupPolicy) -> Result<Lookup, OffsetLayoutsError<A>> {
+        let absolute_range = self.get_absolute_range();
+        if !self.contains_absolute_address(address) {
+            return Err(OffsetLayoutsError::AddressOutsideCurrentScope(address, absolute_range));
+        }
+        if policy == LookupPolicy::Strict && !self.has_children() && self.mounted().is_none() {
+            return Err(OffsetLayoutsError::InconsistentStructure(address, absolute_range));
+        }
+        let mut lookup = match self.locate_element(address, policy) {
+            Some(lookup) => lookup?,
+            None => match locate_mounted(self, address, policy) {
+                Some(lookup) => lookup?,
+                None => Lookup {
+                    coordinates: Coordinates::new(),
+                    level: NodeLevel::Command,
+                    termination: self.element_termination(address),
+                },
+            },
+        };
+        lookup.coordinates.set_command(self.my_ordinal);
+        Ok(lookup)
     }
 
-    fn get_absolute_range(&self)->Range<u64> {
-        self.absolute_range.clone()
+    /// Move packed elements by `delta` bytes, returning how many there are,
+    /// or `None` if the elements aren't packed
+    pub(crate) fn shift_packed(&mut self, delta: i128) -> Result<Option<usize>, OffsetLayoutsError<A>> {
+        match &mut self.elements {
+            Some(Elements::Packed(packed)) => {
+                Arc::make_mut(packed).shift(delta)?;
+                Ok(Some(packed.len()))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn locate_element(&self, address: A, policy: LookupPolicy) -> Option<Result<Lookup, OffsetLayoutsError<A>>> {
+        match self.elements.as_ref()? {
+            Elements::Nodes(elements) => {
+                let index = locate_child(elements, address)?;
+                Some(elements[index].locate_with(address, policy).map_err(|e| child_error(e, OffsetLayoutsError::CommandIsBroken)))
+            }
+            Elements::Packed(packed) => {
+                let index = packed.locate(address.to_wide())?;
+                let mut coordinates = Coordinates::new();
+                coordinates.set_element(index);
+                Some(Ok(Lookup { coordinates, level: NodeLevel::Element, termination: Termination::Leaf }))
+            }
+        }
+    }
+
+    fn element_termination(&self, address: A) -> Termination {
+        match &self.elements {
+            Some(Elements::Nodes(elements)) => termination(elements, address),
+            Some(Elements::Packed(packed)) => packed.termination(address.to_wide()),
+            None => Termination::Leaf,
+        }
     }
 
-    fn get_relative_range(&self)->Range<u64> {
-        self.relative_range.clone()
+    /// [`DiskOffsets::find_address`] under an explicit [`LookupPolicy`].
+    pub fn find_address_with(&self, address: A, policy: LookupPolicy) -> Result<Coordinates, OffsetLayoutsError<A>> {
+        self.locate_with(address, policy).map(|lookup| lookup.coordinates)
     }
+}
 
-    fn get_max_abs_address(&self)->u64 {
-        self.end_abs_address
+impl<A: Address> DiskOffsets<A> for Commands<A> {
+    fn set_absolute_span(&mut self, span:AddressRange<A>){
+        self.absolute_range = span;
     }
 
-    fn get_min_abs_address(&self)->u64 {
-        self.start_abs_address
+    fn set_relative_span(&mut self, span:AddressRange<A>){
+        self.relative_range = span;
     }
 
+    fn set_ordinal(&mut self, ordinal:usize){
+        self.my_ordinal=ordinal;
+    }
+
+    fn get_ordinal(&self)->usize {
+        self.my_ordinal
+    }
+
+    fn get_absolute_span(&self)->AddressRange<A> {
+        self.absolute_range
+    }
+
+    fn get_relative_span(&self)->AddressRange<A> {
+        self.relative_range
+    }
 
     fn has_children(&self)->bool{
         match &self.elements {
-            Some(cmd_elements) => cmd_elements.len()>0,
+            Some(Elements::Nodes(cmd_elements)) => !cmd_elements.is_empty(),
+            Some(Elements::Packed(packed)) => !packed.is_empty(),
             None =>false
         }
     }
 
-    fn get_children(&mut self)->Option<ChildrenArray> {
+    fn get_children(&self)->Option<ChildrenArray<A>> {
         
         if self.has_children() {
-            self.sort_children();
-            let children = self.elements.clone()?;
+            let children = match self.elements.as_ref()? {
+                Elements::Nodes(elements) => elements.to_vec(),
+                Elements::Packed(packed) => packed.unpack()?,
+            };
             Some(ChildrenArray::Commands(children))
         } else {
             None
@@ -127,60 +274,34 @@ impl DiskOffsets for Commands {
     }
 
     fn sort_children(&mut self) {
-        if let Some(children) = &mut self.elements {
-            children.sort_by_key(|c| c.start_abs_address);
+        // Packed elements are in order by construction
+        if let Some(Elements::Nodes(children)) = self.elements.as_mut() {
+            Arc::make_mut(children).sort_by_key(order_key);
         }
     }
 
-    fn find_address(&mut self, absolute_address:u64) -> Result<Coordinates, OffsetLayoutsError>{
-        let absolute_range = self.get_absolute_range();
-        if absolute_range.contains(&absolute_address){
-            let mut retval = Coordinates::new();
-            retval.set_command(self.my_ordinal);
-            if self.has_children(){
-                let mut children = if let Some(ChildrenArray::Commands(v)) = self.get_children() {
-                    v
-                } else {
-                    return Err(OffsetLayoutsError::InconsistentSearch);
-                };
-
-                let mut start:usize = 0;
-                let mut end: usize = children.len() - 1 ;
-
-                while start <= end {
-                    let mid = (start + end) / 2;
-                    let range = &children[mid].get_absolute_range();
-                    if range.contains(&absolute_address) {
-                        match children[mid].find_address(absolute_address) {
-                            Ok(coords) => {
-                                if let Some(e) = coords.element {
-                                    retval.set_element(e);
-                                }
-                            }
-                            Err(_) => return Err(OffsetLayoutsError::CommandIsBroken),
-                        }
-                        return Ok(retval);
-                    } else {
-                        let min = &children[mid].get_min_abs_address();
-                        let max = &children[mid].get_max_abs_address();
-                        let mid_range = &children[mid].get_absolute_range();
-                        if absolute_address < *min {
-                            if mid == 0 {
-                                break;
-                            }
-                            end = mid - 1;
-                        } else if absolute_address > *max {
-                            start = mid + 1;
-                        } else {
-                            return Err(OffsetLayoutsError::InconsistentStructure(absolute_address,mid_range.clone()));
-                        }
-                    }
-                }
-            }
-            Ok(retval)
-        } else {
-            Err(OffsetLayoutsError::AddressOutsideCurrentScope(absolute_address,absolute_range))
-        }
+    fn find_address(&self, absolute_address:A) -> Result<Coordinates, OffsetLayoutsError<A>>{
+        self.locate(absolute_address).map(|lookup| lookup.coordinates)
     }
     
 }
+
+impl<A> Labeled for Commands<A> {
+    fn tags(&self) -> Option<&NodeTags> {
+        self.tags.as_deref()
+    }
+
+    fn tags_mut(&mut self) -> &mut Option<Box<NodeTags>> {
+        &mut self.tags
+    }
+}
+
+impl<A: Address> Mountable<A> for Commands<A> {
+    fn mount_point(&self) -> Option<&Arc<File<A>>> {
+        self.mount.as_ref()
+    }
+
+    fn mount_point_mut(&mut self) -> &mut Option<Arc<File<A>>> {
+        &mut self.mount
+    }
+}
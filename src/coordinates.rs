@@ -1,19 +1,117 @@
-#[derive(Debug,Clone)]
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+use core::str::FromStr;
+
+/// Path of ordinals from the file down to a node.
+///
+/// Coordinates order like a depth-first walk: a node comes right before its
+/// descendants, and siblings follow their ordinals. For layouts whose
+/// ordinals follow addresses, as [`crate::builder::LayoutBuilder`] assigns
+/// them, that is document order.
+///
+/// They print and parse as ordinals joined by `/`; the file itself is the
+/// empty path. Inside a file mounted on a node (see [`crate::Mountable`]),
+/// [`Coordinates::inner`] continues the path in the mounted file, printed
+/// after a `!`:
+/// `0/3!1/0` is command 0 of slice 1 of the file mounted on `0/3`.
+///
+/// ```rust
+/// use janus_array::Coordinates;
+///
+/// let coords: Coordinates = "0/12/3".parse().unwrap();
+/// assert_eq!(coords.to_string(), "0/12/3");
+/// assert_eq!(coords.depth(), 3);
+/// assert!(coords.parent().unwrap().is_ancestor_of(&coords));
+/// assert!(Coordinates::from_ordinals(&[0, 2]) < coords);
+/// ```
+#[derive(Debug,Clone,Default,PartialEq,Eq,Hash,PartialOrd,Ord)]
 pub struct Coordinates{
     pub slice:Option<usize>,
     pub command:Option<usize>,
     pub element:Option<usize>,
-}
-
-impl Default for Coordinates {
-    fn default() -> Self {
-        Self { slice: None, command: None, element: None }
-    }
+    // Path inside the file mounted on the node designated so far. Private so
+    // that the three ordinals can still be matched and set on their own.
+    inner:Option<Box<Coordinates>>,
 }
 
 impl Coordinates {
     pub fn new() -> Self {
-        Self { slice: None, command: None, element: None }
+        Self { slice: None, command: None, element: None, inner: None }
+    }
+
+    /// Build coordinates from a path of ordinals, outermost first.
+    ///
+    /// Ordinals beyond the third are ignored.
+    pub fn from_ordinals(path: &[usize]) -> Self {
+        Self {
+            slice: path.first().copied(),
+            command: path.get(1).copied(),
+            element: path.get(2).copied(),
+            inner: None,
+        }
+    }
+
+    /// The ordinals set in the outermost file, stopping at the first unset
+    /// level. Mounted files are not included, see [`Coordinates::inner`].
+    pub fn ordinals(&self) -> Vec<usize> {
+        [self.slice, self.command, self.element]
+            .into_iter()
+            .map_while(|o| o)
+            .collect()
+    }
+
+    /// Number of ordinals set in the outermost file: 0 for the file, 3 for
+    /// an element.
+    pub fn depth(&self) -> usize {
+        self.ordinals().len()
+    }
+
+    /// Same coordinates, continued by `inner` in the file mounted on the node
+    /// they designate.
+    pub fn with_inner(mut self, inner: Coordinates) -> Self {
+        self.inner = Some(Box::new(inner));
+        self
+    }
+
+    /// Path inside the file mounted on the node these coordinates designate,
+    /// if they continue into one.
+    pub fn inner(&self) -> Option<&Coordinates> {
+        self.inner.as_deref()
+    }
+
+    /// Coordinates of the enclosing node, or `None` for the file itself. The
+    /// parent of a mounted file's root is the node it is mounted on.
+    pub fn parent(&self) -> Option<Coordinates> {
+        let steps = self.steps();
+        let (_, parent) = steps.split_last()?;
+        Some(Self::from_steps(parent))
+    }
+
+    /// Whether `other` lies strictly below `self`, mounted files included.
+    pub fn is_ancestor_of(&self, other: &Coordinates) -> bool {
+        let (ours, theirs) = (self.steps(), other.steps());
+        ours.len() < theirs.len() && theirs.starts_with(&ours)
+    }
+
+    // Flat path across mounts: an ordinal per level, `None` entering a mounted file.
+    fn steps(&self) -> Vec<Option<usize>> {
+        let mut steps: Vec<Option<usize>> = self.ordinals().into_iter().map(Some).collect();
+        if let Some(inner) = &self.inner {
+            steps.push(None);
+            steps.extend(inner.steps());
+        }
+        steps
+    }
+
+    fn from_steps(steps: &[Option<usize>]) -> Self {
+        let outer: Vec<usize> = steps.iter().map_while(|s| *s).collect();
+        let coords = Self::from_ordinals(&outer);
+        match steps.get(outer.len()) {
+            Some(None) => coords.with_inner(Self::from_steps(&steps[outer.len() + 1..])),
+            _ => coords,
+        }
     }
 
     pub fn set_slice(&mut self, a_slice:usize){
@@ -28,4 +126,61 @@ impl Coordinates {
         self.element=Some(an_element)
     }
 
-}
\ No newline at end of file
+}
+
+impl fmt::Display for Coordinates {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, ordinal) in self.ordinals().into_iter().enumerate() {
+            if i > 0 {
+                f.write_str("/")?;
+            }
+            write!(f, "{}", ordinal)?;
+        }
+        match &self.inner {
+            Some(inner) => write!(f, "!{}", inner),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Error returned when parsing [`Coordinates`] from a string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseCoordinatesError {
+    /// A segment is not an ordinal.
+    InvalidOrdinal(String),
+    /// More segments than there are levels below the file.
+    TooDeep(usize),
+}
+
+impl fmt::Display for ParseCoordinatesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseCoordinatesError::InvalidOrdinal(s) => write!(f, "not an ordinal: `{}`", s),
+            ParseCoordinatesError::TooDeep(n) => write!(f, "{} levels, at most 3 are supported", n),
+        }
+    }
+}
+
+impl FromStr for Coordinates {
+    type Err = ParseCoordinatesError;
+
+    /// Parse `0/12/3` or `0/3!1/0`. A leading `/` is accepted, and an empty
+    /// string is the file.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some((outer, inner)) = s.split_once('!') {
+            return Ok(outer.parse::<Self>()?.with_inner(inner.parse()?));
+        }
+        let s = s.strip_prefix('/').unwrap_or(s);
+        if s.is_empty() {
+            return Ok(Self::new());
+        }
+        let ordinals = s
+            .split('/')
+            .map(|part| part.parse::<usize>().map_err(|_| ParseCoordinatesError::InvalidOrdinal(part.to_string())))
+            .collect::<Result<Vec<_>, _>>()?;
+        if ordinals.len() > 3 {
+            return Err(ParseCoordinatesError::TooDeep(ordinals.len()));
+        }
+        Ok(Self::from_ordinals(&ordinals))
+    }
+}
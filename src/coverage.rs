@@ -0,0 +1,107 @@
+use alloc::vec::Vec;
+
+use crate::address::Address;
+use crate::address_range::AddressRange;
+use crate::disk_offsets::DiskOffsets;
+use crate::file::File;
+use crate::types::NodeRef;
+
+/// How much of a file its leaves account for.
+///
+/// A byte is covered when it belongs to a node without children below the
+/// file; everything else is a gap, wherever it sits in the hierarchy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Coverage {
+    /// Bytes spanned by the file(s).
+    pub total: u128,
+    /// Bytes owned by a leaf node.
+    pub covered: u128,
+    /// Number of maximal runs of unclaimed bytes.
+    pub gaps: usize,
+    /// Length of the longest run of unclaimed bytes.
+    pub largest_gap: u128,
+}
+
+impl Coverage {
+    pub fn unclaimed(&self) -> u128 {
+        self.total - self.covered
+    }
+
+    /// Covered share of the total, from 0.0 to 1.0. An empty file is fully covered.
+    pub fn ratio(&self) -> f64 {
+        if self.total == 0 {
+            1.0
+        } else {
+            self.covered as f64 / self.total as f64
+        }
+    }
+
+    /// Add the figures of another file, e.g. to aggregate a collection.
+    pub fn merge(&mut self, other: &Coverage) {
+        self.total += other.total;
+        self.covered += other.covered;
+        self.gaps += other.gaps;
+        self.largest_gap = self.largest_gap.max(other.largest_gap);
+    }
+}
+
+impl<A: Address> File<A> {
+    /// Maximal runs of bytes not owned by any leaf node, in address order.
+    ///
+    /// ```rust
+    /// use janus_array::{DiskOffsets, File, Slices, Commands};
+    ///
+    /// let mut command = Commands::default();
+    /// command.populate_values(0x10, 0x20, 0x10, 0x20, 0);
+    /// let mut slice = Slices::default();
+    /// slice.populate_values(0, 0x40, 0, 0x40, 0);
+    /// slice.add_command(command);
+    /// let mut file = File::with_size(0x80);
+    /// file.add_slice(slice);
+    ///
+    /// let gaps: Vec<_> = file.gaps().iter().map(|g| g.to_range()).collect();
+    /// assert_eq!(gaps, vec![0x0..0x10, 0x20..0x80]);
+    /// assert_eq!(file.coverage().covered, 0x10);
+    /// ```
+    pub fn gaps(&self) -> Vec<AddressRange<A>> {
+        let span = self.get_absolute_span();
+        let mut gaps = Vec::new();
+        let mut cursor = span.start().to_wide();
+        for_each_leaf(&NodeRef::File(self), &mut |leaf| {
+            let start = leaf.start().to_wide();
+            if start > cursor {
+                gaps.extend(AddressRange::from_bounds(cursor, start));
+            }
+            cursor = cursor.max(leaf.end_wide());
+        });
+        if cursor < span.end_wide() {
+            gaps.extend(AddressRange::from_bounds(cursor, span.end_wide()));
+        }
+        gaps
+    }
+
+    /// Covered bytes, gap count and largest gap of this file.
+    pub fn coverage(&self) -> Coverage {
+        let gaps = self.gaps();
+        let total = self.get_absolute_span().len();
+        let unclaimed: u128 = gaps.iter().map(AddressRange::len).sum();
+        Coverage {
+            total,
+            covered: total - unclaimed,
+            gaps: gaps.len(),
+            largest_gap: gaps.iter().map(AddressRange::len).max().unwrap_or(0),
+        }
+    }
+}
+
+// Spans of the non-empty leaves below `node`, in address order. Siblings are
+// sorted and nested in their parent, so a pre-order walk yields them sorted.
+fn for_each_leaf<A: Address>(node: &NodeRef<'_, A>, visit: &mut impl FnMut(AddressRange<A>)) {
+    for child in node.children() {
+        if child.has_children() {
+            for_each_leaf(&child, visit);
+        } else if !child.absolute_span().is_empty() {
+            visit(child.absolute_span());
+        }
+    }
+}
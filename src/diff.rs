@@ -0,0 +1,156 @@
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use alloc::collections::BTreeMap;
+
+use crate::address_range::AddressRange;
+use crate::coordinates::Coordinates;
+use crate::file::File;
+use crate::types::{NodeLevel, NodeRef};
+
+/// How nodes of the two layouts are paired up.
+///
+/// Matching only ever happens between children of already matched parents,
+/// so a command can't be matched across two different slices.
+pub enum MatchBy<'k> {
+    /// Pair nodes carrying the same `my_ordinal`.
+    Ordinal,
+    /// Pair nodes covering the same absolute range.
+    Range,
+    /// Pair nodes for which the closure returns the same key.
+    Key(&'k dyn Fn(&NodeRef<'_>) -> String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    /// Only present in the new layout. Descendants are not reported separately.
+    Added,
+    /// Only present in the old layout. Descendants are not reported separately.
+    Removed,
+    /// Same length, different start address.
+    Moved,
+    /// Different length, whether or not the start moved as well.
+    Resized,
+}
+
+#[derive(Debug, Clone)]
+pub struct LayoutChange {
+    pub kind: ChangeKind,
+    pub level: NodeLevel,
+    pub old: Option<Coordinates>,
+    pub new: Option<Coordinates>,
+    pub old_range: Option<AddressRange>,
+    pub new_range: Option<AddressRange>,
+}
+
+/// Compare two layouts, pairing nodes by ordinal.
+///
+/// ```rust
+/// use janus_array::{File, Slices, DiskOffsets};
+/// use janus_array::diff::{diff, ChangeKind};
+///
+/// let old = File::with_size(1024);
+/// let mut new = File::with_size(1024);
+/// let mut slice = Slices::default();
+/// slice.populate_values(0, 512, 0, 512, 0);
+/// new.add_slice(slice);
+///
+/// let changes = diff(&old, &new);
+/// assert_eq!(changes.len(), 1);
+/// assert_eq!(changes[0].kind, ChangeKind::Added);
+/// ```
+pub fn diff(old: &File, new: &File) -> Vec<LayoutChange> {
+    diff_by(old, new, &MatchBy::Ordinal)
+}
+
+/// Compare two layouts, pairing nodes with the given strategy.
+///
+/// Changes are reported in document order of the old layout; additions come
+/// after the other changes found under the same parent.
+pub fn diff_by(old: &File, new: &File, by: &MatchBy<'_>) -> Vec<LayoutChange> {
+    let mut changes = Vec::new();
+    compare(&NodeRef::File(old), &NodeRef::File(new), &[], &[], by, &mut changes);
+    changes
+}
+
+fn compare(
+    old: &NodeRef<'_>,
+    new: &NodeRef<'_>,
+    old_path: &[usize],
+    new_path: &[usize],
+    by: &MatchBy<'_>,
+    changes: &mut Vec<LayoutChange>,
+) {
+    let old_range = old.absolute_span();
+    let new_range = new.absolute_span();
+    let kind = if old_range.len() != new_range.len() {
+        Some(ChangeKind::Resized)
+    } else if old_range.start() != new_range.start() {
+        Some(ChangeKind::Moved)
+    } else {
+        None
+    };
+    if let Some(kind) = kind {
+        changes.push(LayoutChange {
+            kind,
+            level: old.level(),
+            old: Some(Coordinates::from_ordinals(old_path)),
+            new: Some(Coordinates::from_ordinals(new_path)),
+            old_range: Some(old_range),
+            new_range: Some(new_range),
+        });
+    }
+
+    let old_children = old.children();
+    let new_children = new.children();
+
+    // Keys can repeat; pair repeated keys in storage order
+    let mut unmatched: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+    for (i, child) in new_children.iter().enumerate().rev() {
+        unmatched.entry(key_of(child, by)).or_default().push(i);
+    }
+
+    let mut paired = vec![false; new_children.len()];
+    for child in &old_children {
+        let mut child_old_path = old_path.to_vec();
+        child_old_path.push(child.ordinal());
+
+        match unmatched.get_mut(&key_of(child, by)).and_then(|v| v.pop()) {
+            Some(j) => {
+                paired[j] = true;
+                let mut child_new_path = new_path.to_vec();
+                child_new_path.push(new_children[j].ordinal());
+                compare(child, &new_children[j], &child_old_path, &child_new_path, by, changes);
+            }
+            None => changes.push(LayoutChange {
+                kind: ChangeKind::Removed,
+                level: child.level(),
+                old: Some(Coordinates::from_ordinals(&child_old_path)),
+                new: None,
+                old_range: Some(child.absolute_span()),
+                new_range: None,
+            }),
+        }
+    }
+
+    for (child, _) in new_children.iter().zip(paired).filter(|(_, p)| !p) {
+        let mut child_new_path = new_path.to_vec();
+        child_new_path.push(child.ordinal());
+        changes.push(LayoutChange {
+            kind: ChangeKind::Added,
+            level: child.level(),
+            old: None,
+            new: Some(Coordinates::from_ordinals(&child_new_path)),
+            old_range: None,
+            new_range: Some(child.absolute_span()),
+        });
+    }
+}
+
+fn key_of(node: &NodeRef<'_>, by: &MatchBy<'_>) -> String {
+    match by {
+        MatchBy::Ordinal => node.ordinal().to_string(),
+        MatchBy::Range => node.absolute_span().to_string(),
+        MatchBy::Key(f) => f(node),
+    }
+}
@@ -1,71 +1,268 @@
-use std::ops::Range;
+use alloc::vec::Vec;
+use core::ops::Range;
 
+use crate::address::Address;
+use crate::address_range::AddressRange;
 use crate::{coordinates::Coordinates, types::ChildrenArray};
+use crate::lookup::Termination;
 
-pub trait DiskOffsets{
+/// Common interface of every level of the hierarchy.
+///
+/// Each node stores its absolute and relative ranges and nothing else: start
+/// and end addresses are always read from, and written through, the ranges,
+/// so they can never disagree. Setting a start past the current end (or an
+/// end before the current start) drags the other bound along, leaving an
+/// empty range rather than an inverted one. The setters taking both bounds
+/// at once do the same with an inverted pair, leaving an empty range at the
+/// start; build the range with [`AddressRange::new`] to get an error instead.
+///
+/// Ranges are stored as [`AddressRange`]s so that a node can hold the last
+/// byte of the address space. The `Range` accessors are kept for
+/// convenience; for such a node they report an end of `A::MAX`, one byte
+/// short, and the `*_span` accessors give the exact range.
+///
+/// `A` is the [`Address`] type of the layout, `u64` unless stated otherwise.
+/// Sizes are always given as `u64`.
+pub trait DiskOffsets<A: Address = u64>{
 
     // Functions to implement:
-    fn set_start_abs_address(&mut self, start_abs:u64);
-    fn set_start_rel_address(&mut self, start_rel:u64);
-    fn set_end_abs_address(&mut self, end_abs:u64);
-    fn set_end_rel_address(&mut self, end_rel:u64);
-    fn set_absolute_range_explicit(&mut self, range:Range<u64>);
-    fn set_relative_range_explicit(&mut self, range:Range<u64>);
-    fn set_absolute_range_implicit(&mut self);
-    fn set_relative_range_implicit(&mut self);
+    fn set_absolute_span(&mut self, span:AddressRange<A>);
+    fn set_relative_span(&mut self, span:AddressRange<A>);
     fn set_ordinal(&mut self, ordinal:usize);
-    fn get_absolute_range(&self)->Range<u64>;
-    fn get_relative_range(&self)->Range<u64>;
+    fn get_ordinal(&self)->usize;
+    fn get_absolute_span(&self)->AddressRange<A>;
+    fn get_relative_span(&self)->AddressRange<A>;
     fn has_children(&self)->bool;
-    fn get_children(&mut self)->Option<ChildrenArray>;
+    fn get_children(&self)->Option<ChildrenArray<A>>;
     fn sort_children(&mut self);
-    fn get_max_abs_address(&self)->u64;
-    fn get_min_abs_address(&self)->u64;
-    fn find_address(&mut self, absolute_address:u64) -> Result<Coordinates, OffsetLayoutsError>;
+    fn find_address(&self, absolute_address:A) -> Result<Coordinates, OffsetLayoutsError<A>>;
     //
 
-    fn populate_values(&mut self, start_abs:u64, end_abs:u64, start_rel:u64, end_rel:u64, ordinal:usize){
+    fn set_absolute_range_explicit(&mut self, range:Range<A>){
+        self.set_absolutes(range.start, range.end);
+    }
+
+    fn set_relative_range_explicit(&mut self, range:Range<A>){
+        self.set_relatives(range.start, range.end);
+    }
+
+    fn get_absolute_range(&self)->Range<A>{
+        self.get_absolute_span().to_range()
+    }
+
+    fn get_relative_range(&self)->Range<A>{
+        self.get_relative_span().to_range()
+    }
+
+    fn populate_values(&mut self, start_abs:A, end_abs:A, start_rel:A, end_rel:A, ordinal:usize){
         self.set_absolutes(start_abs, end_abs); 
         self.set_relatives(start_rel, end_rel);
         self.set_ordinal(ordinal);
     }
 
-    fn set_absolutes(&mut self, start_abs:u64, end_abs:u64){
-        self.set_start_abs_address(start_abs);
-        self.set_end_abs_address(end_abs);
+    fn set_absolutes(&mut self, start_abs:A, end_abs:A){
+        self.set_absolute_span(AddressRange::clamped(start_abs, end_abs));
+    }
+
+    fn set_relatives(&mut self,start_rel:A, end_rel:A){
+        self.set_relative_span(AddressRange::clamped(start_rel, end_rel));
+    }
+
+    fn set_start_abs_address(&mut self, start_abs:A){
+        let span = self.get_absolute_span();
+        self.set_absolute_span(span.with_start(start_abs));
+    }
+
+    fn set_end_abs_address(&mut self, end_abs:A){
+        let span = self.get_absolute_span();
+        self.set_absolute_span(span.with_end(end_abs));
+    }
 
-        self.set_absolute_range_implicit();
+    fn set_start_rel_address(&mut self, start_rel:A){
+        let span = self.get_relative_span();
+        self.set_relative_span(span.with_start(start_rel));
     }
 
-    fn set_relatives(&mut self,start_rel:u64, end_rel:u64){
-        self.set_start_rel_address(start_rel);
-        self.set_end_rel_address(end_rel);
+    fn set_end_rel_address(&mut self, end_rel:A){
+        let span = self.get_relative_span();
+        self.set_relative_span(span.with_end(end_rel));
+    }
 
-        self.set_relative_range_implicit();
+    fn get_min_abs_address(&self)->A{
+        self.get_absolute_span().start()
     }
 
-    fn contains_absolute_address(&self,an_address:u64)->bool{
-        let range=self.get_absolute_range();
-        range.contains(&an_address)
+    /// Exclusive end; saturates at `A::MAX` for a node reaching the last byte.
+    fn get_max_abs_address(&self)->A{
+        self.get_absolute_range().end
     }
 
-    
+    fn get_min_rel_address(&self)->A{
+        self.get_relative_span().start()
+    }
+
+    /// Exclusive end; saturates at `A::MAX` for a node reaching the last byte.
+    fn get_max_rel_address(&self)->A{
+        self.get_relative_range().end
+    }
+
+    /// Whether lookups for `an_address` can end up in this node.
+    ///
+    /// A zero-length node owns no bytes but still answers to its exact start
+    /// address, so that empty sections or marker commands remain findable.
+    fn contains_absolute_address(&self,an_address:A)->bool{
+        let span=self.get_absolute_span();
+        span.contains(an_address) || (span.is_empty() && span.start() == an_address)
+    }
+
+    /// Absolute address of the byte `relative` bytes into this node.
+    ///
+    /// Offsets relative to a node are what its children store as relative
+    /// ranges, e.g. load command offsets relative to a fat slice. The result
+    /// must fall inside the node; otherwise the error carries the node's
+    /// local range `0..len`.
+    ///
+    /// ```rust
+    /// use janus_array::{DiskOffsets, Slices};
+    ///
+    /// let mut slice = Slices::default();
+    /// slice.populate_values(0x4000, 0x8000, 0x4000, 0x8000, 1);
+    /// assert_eq!(slice.relative_to_absolute(0x10).unwrap(), 0x4010);
+    /// assert_eq!(slice.absolute_to_relative(0x4010).unwrap(), 0x10);
+    /// assert!(slice.relative_to_absolute(0x4000).is_err());
+    /// ```
+    fn relative_to_absolute(&self, relative:A) -> Result<A, OffsetLayoutsError<A>>{
+        let span = self.get_absolute_span();
+        let absolute = A::from_wide(span.start().to_wide() + relative.to_wide());
+        match absolute {
+            Some(address) if self.contains_absolute_address(address) => Ok(address),
+            _ => {
+                let len = A::from_wide(span.len()).unwrap_or(A::MAX);
+                Err(OffsetLayoutsError::AddressOutsideCurrentScope(relative, A::ZERO..len))
+            }
+        }
+    }
+
+    /// Offset of `absolute` from the start of this node, the inverse of
+    /// [`DiskOffsets::relative_to_absolute`].
+    fn absolute_to_relative(&self, absolute:A) -> Result<A, OffsetLayoutsError<A>>{
+        if !self.contains_absolute_address(absolute) {
+            return Err(OffsetLayoutsError::AddressOutsideCurrentScope(absolute, self.get_absolute_range()));
+        }
+        // Never below zero, and never wider than the address itself
+        A::from_wide(absolute.to_wide() - self.get_min_abs_address().to_wide()).ok_or(OffsetLayoutsError::InconsistentSearch)
+    }
+
+    /// Same as [`DiskOffsets::find_address`] for an address relative to the
+    /// start of this node, so a lookup stays scoped to its subtree.
+    fn find_relative_address(&self, relative:A) -> Result<Coordinates, OffsetLayoutsError<A>>{
+        self.find_address(self.relative_to_absolute(relative)?)
+    }
+
+    /// Set the absolute and relative ranges from `(offset, size)` pairs, as
+    /// most formats describe them. A range may end exactly at the end of the
+    /// address space; going past it fails instead of wrapping.
+    fn populate_offset_size(&mut self, abs_offset:A, rel_offset:A, size:u64, ordinal:usize) -> Result<(), OffsetLayoutsError<A>>{
+        let absolute = AddressRange::from_offset_size(abs_offset, size)?;
+        let relative = AddressRange::from_offset_size(rel_offset, size)?;
+        self.set_absolute_span(absolute);
+        self.set_relative_span(relative);
+        self.set_ordinal(ordinal);
+        Ok(())
+    }
 
 }
 
 
 #[derive(Clone,Debug)]
-pub enum OffsetLayoutsError{
-    AddressOutsideCurrentScope(u64,Range<u64>),
-    InconsistentStructure(u64,Range<u64>),
+pub enum OffsetLayoutsError<A = u64>{
+    AddressOutsideCurrentScope(A,Range<A>),
+    InconsistentStructure(A,Range<A>),
     InconsistentSearch,
-    NotFound(u64),
+    NotFound(A),
     SliceIsBroken,
     CommandIsBroken,
+    AddressOverflow(A,Range<A>),
+    UnplacedRange(Range<A>),
+    TooManyLevels(usize),
+    /// The coordinates no longer designate a node; the `usize` is how many
+    /// of their ordinals still resolve.
+    StaleCoordinates(Coordinates, usize),
 }
 
+/// Insert `node` before the first sibling starting after it.
+pub(crate) fn insert_sorted<A: Address, T: DiskOffsets<A>>(children: &mut Vec<T>, node: T) {
+    let key = order_key(&node);
+    let index = children.partition_point(|c| order_key(c) <= key);
+    children.insert(index, node);
+}
 
+/// Sibling order: by start address, then end address (zero-length nodes
+/// first), then ordinal.
+pub(crate) fn order_key<A: Address, T: DiskOffsets<A>>(node: &T) -> (A, u128, usize) {
+    let span = node.get_absolute_span();
+    (span.start(), span.end_wide(), node.get_ordinal())
+}
 
+/// Run `f` on the child with the given ordinal and restore the sort order.
+pub(crate) fn update_sorted<A: Address, T: DiskOffsets<A>, R>(
+    children: &mut Vec<T>,
+    ordinal: usize,
+    f: impl FnOnce(&mut T) -> R,
+) -> Option<R> {
+    let index = children.iter().position(|c| c.get_ordinal() == ordinal)?;
+    let result = f(&mut children[index]);
+    let node = children.remove(index);
+    insert_sorted(children, node);
+    Some(result)
+}
 
+/// Index of the child containing `address`.
+///
+/// Children must be sorted with [`order_key`] and non-empty children must not
+/// overlap, which every level guarantees for its own children. A child owning
+/// the byte at `address` always wins; otherwise the first zero-length child
+/// starting exactly at `address` is returned.
+pub(crate) fn locate_child<A: Address, T: DiskOffsets<A>>(children: &[T], address: A) -> Option<usize> {
+    let after = children.partition_point(|c| c.get_min_abs_address() <= address);
+    let mut exact_empty = None;
+    // Zero-length children may sit inside the span of a non-empty sibling;
+    // step back over them to reach it
+    for index in (0..after).rev() {
+        let span = children[index].get_absolute_span();
+        if span.is_empty() {
+            if span.start() == address {
+                exact_empty = Some(index);
+            }
+            continue;
+        }
+        if span.contains(address) {
+            return Some(index);
+        }
+        break;
+    }
+    exact_empty
+}
 
+/// Error from a child lookup as seen from its parent: a childless node
+/// rejected by a strict lookup is reported as is, anything else means the
+/// parent is `broken`.
+pub(crate) fn child_error<A>(error: OffsetLayoutsError<A>, broken: OffsetLayoutsError<A>) -> OffsetLayoutsError<A> {
+    match error {
+        OffsetLayoutsError::InconsistentStructure(..) => error,
+        _ => broken,
+    }
+}
 
+/// Why no child contains `address`, when [`locate_child`] found none.
+pub(crate) fn termination<A: Address, T: DiskOffsets<A>>(children: &[T], address: A) -> Termination {
+    let after = children.partition_point(|c| c.get_min_abs_address() <= address);
+    let previous = after.checked_sub(1).map(|i| children[i].get_ordinal());
+    let next = children.get(after).map(|c| c.get_ordinal());
+    match (previous, next) {
+        (None, None) => Termination::Leaf,
+        (None, Some(next)) => Termination::BeforeFirst { next },
+        (Some(previous), None) => Termination::AfterLast { previous },
+        (Some(previous), Some(next)) => Termination::Gap { previous, next },
+    }
+}
@@ -0,0 +1,260 @@
+use alloc::vec::Vec;
+use core::ops::Range;
+use alloc::sync::Arc;
+
+use crate::address_range::AddressRange;
+use crate::cmd_elements::CommandElements;
+use crate::commands::Commands;
+use crate::coordinates::Coordinates;
+use crate::disk_offsets::{DiskOffsets, OffsetLayoutsError};
+use crate::file::File;
+use crate::slices::Slices;
+use crate::types::NodeRef;
+
+/// What an edit did to the tree.
+///
+/// Every list holds the coordinates the nodes had *before* the edit.
+#[derive(Debug, Clone, Default)]
+pub struct EditReport {
+    /// Number of nodes whose start address moved.
+    pub shifted: usize,
+    /// Nodes that grew because the edit happened inside them.
+    pub grown: Vec<Coordinates>,
+    /// Nodes that lost bytes without disappearing.
+    pub shrunk: Vec<Coordinates>,
+    /// Childless nodes that had bytes inserted strictly inside them. They are
+    /// grown like any other node, but the caller most likely wants to split them.
+    pub split: Vec<Coordinates>,
+    /// Nodes that were entirely inside a removed range and have been dropped.
+    pub removed: Vec<Coordinates>,
+}
+
+#[derive(Clone, Copy)]
+enum Edit {
+    Insert { at: u64, len: u64 },
+    // Wide bounds so that the last byte of the address space can be removed
+    Remove { start: u128, end: u128 },
+    Rebase(i64),
+}
+
+impl Edit {
+    /// New absolute span of a node, or `None` if it would leave the address space.
+    fn remap(&self, span: AddressRange, is_root: bool) -> Option<AddressRange> {
+        let (start, end) = (span.start() as u128, span.end_wide());
+        let (start, end) = match *self {
+            Edit::Insert { at, len } => {
+                let (at, len) = (at as u128, len as u128);
+                // Bytes inserted exactly at a node's start go before it,
+                // except for the file which always keeps its start.
+                if start > at || (start == at && !is_root) {
+                    (start + len, end + len)
+                } else if end > at {
+                    (start, end + len)
+                } else {
+                    (start, end)
+                }
+            }
+            Edit::Remove { start: a, end: b } => {
+                let map = |x: u128| if x <= a { x } else if x <= b { a } else { x - (b - a) };
+                (map(start), map(end))
+            }
+            Edit::Rebase(delta) => {
+                let shift = |x: u128| u128::try_from(x as i128 + delta as i128).ok();
+                (shift(start)?, shift(end)?)
+            }
+        };
+        AddressRange::from_bounds(start, end)
+    }
+
+    fn removes(&self, span: AddressRange) -> bool {
+        match *self {
+            Edit::Remove { start: a, end: b } => {
+                !span.is_empty() && a <= span.start() as u128 && span.end_wide() <= b
+            }
+            _ => false,
+        }
+    }
+}
+
+// Uniform mutable access to the children of every level.
+trait Editable: DiskOffsets {
+    type Child: Editable;
+    fn children_mut(&mut self) -> Option<&mut Vec<Self::Child>>;
+
+    /// Move children stored in a form `children_mut` would have to expand,
+    /// returning how many there are.
+    fn shift_packed_children(&mut self, _delta: i128) -> Result<Option<usize>, OffsetLayoutsError> {
+        Ok(None)
+    }
+}
+
+impl Editable for File {
+    type Child = Slices;
+    fn children_mut(&mut self) -> Option<&mut Vec<Slices>> {
+        self.slices.as_mut().map(Arc::make_mut)
+    }
+}
+
+impl Editable for Slices {
+    type Child = Commands;
+    fn children_mut(&mut self) -> Option<&mut Vec<Commands>> {
+        self.commands.as_mut().map(Arc::make_mut)
+    }
+}
+
+impl Editable for Commands {
+    type Child = CommandElements;
+    fn children_mut(&mut self) -> Option<&mut Vec<CommandElements>> {
+        self.elements_mut()
+    }
+
+    fn shift_packed_children(&mut self, delta: i128) -> Result<Option<usize>, OffsetLayoutsError> {
+        self.shift_packed(delta)
+    }
+}
+
+impl Editable for CommandElements {
+    type Child = CommandElements;
+    fn children_mut(&mut self) -> Option<&mut Vec<CommandElements>> {
+        None
+    }
+}
+
+impl File {
+    /// Insert `len` bytes at absolute address `at`.
+    ///
+    /// Nodes starting at or after `at` move up by `len`, nodes containing `at`
+    /// grow by `len`. Relative ranges follow so that children stay at the same
+    /// offset from their parent. Childless nodes that get bytes inserted in
+    /// their middle are listed in [`EditReport::split`].
+    ///
+    /// ```rust
+    /// use janus_array::{File, Slices, Commands, DiskOffsets};
+    ///
+    /// let mut file = File::with_size(0x1000);
+    /// let mut slice = Slices::default();
+    /// slice.populate_values(0, 0x1000, 0, 0x1000, 0);
+    /// let mut header = Commands::default();
+    /// header.populate_values(0, 0x100, 0, 0x100, 0);
+    /// let mut body = Commands::default();
+    /// body.populate_values(0x100, 0x800, 0x100, 0x800, 1);
+    /// slice.add_command(header);
+    /// slice.add_command(body);
+    /// file.add_slice(slice);
+    ///
+    /// let report = file.insert_bytes(0x80, 0x20).unwrap();
+    /// assert_eq!(report.split.len(), 1); // the header command
+    /// assert_eq!(file.get_absolute_range(), 0..0x1020);
+    /// assert_eq!(file.find_address(0x810).unwrap().command, Some(1));
+    /// ```
+    pub fn insert_bytes(&mut self, at: u64, len: u64) -> Result<EditReport, OffsetLayoutsError> {
+        self.apply_edit(Edit::Insert { at, len }, at)
+    }
+
+    /// Remove the bytes in `range`.
+    ///
+    /// Nodes entirely inside the range are dropped, nodes overlapping it
+    /// shrink, and everything after it moves down.
+    pub fn remove_bytes(&mut self, range: Range<u64>) -> Result<EditReport, OffsetLayoutsError> {
+        self.remove_span(range.try_into()?)
+    }
+
+    /// Same as [`File::remove_bytes`], for a span that may reach the last
+    /// byte of the address space.
+    pub fn remove_span(&mut self, span: AddressRange) -> Result<EditReport, OffsetLayoutsError> {
+        let edit = Edit::Remove { start: span.start() as u128, end: span.end_wide() };
+        self.apply_edit(edit, span.start())
+    }
+
+    /// Move the whole layout, file included, by `delta` bytes.
+    pub fn rebase(&mut self, delta: i64) -> Result<EditReport, OffsetLayoutsError> {
+        self.apply_edit(Edit::Rebase(delta), self.get_min_abs_address())
+    }
+
+    fn apply_edit(&mut self, edit: Edit, at: u64) -> Result<EditReport, OffsetLayoutsError> {
+        // Check everything first so that a failing edit leaves the tree untouched
+        if !fits(&NodeRef::File(self), &edit, true) {
+            return Err(OffsetLayoutsError::AddressOverflow(at, self.get_absolute_range()));
+        }
+        let mut report = EditReport::default();
+        let mut path = Vec::new();
+        apply(self, &edit, true, 0, &mut path, &mut report)?;
+        Ok(report)
+    }
+}
+
+fn fits(node: &NodeRef<'_>, edit: &Edit, is_root: bool) -> bool {
+    edit.remap(node.absolute_span(), is_root).is_some()
+        && node.children().iter().all(|c| fits(c, edit, false))
+}
+
+fn apply<T: Editable>(
+    node: &mut T,
+    edit: &Edit,
+    is_root: bool,
+    parent_start_delta: i128,
+    path: &mut Vec<usize>,
+    report: &mut EditReport,
+) -> Result<(), OffsetLayoutsError> {
+    let old = node.get_absolute_span();
+    // Validated by `fits` beforehand
+    let new = edit.remap(old, is_root).unwrap_or(old);
+    let start_delta = new.start() as i128 - old.start() as i128;
+    let end_delta = new.end_wide() as i128 - old.end_wide() as i128;
+
+    if start_delta != 0 {
+        report.shifted += 1;
+    } else if end_delta > 0 {
+        report.grown.push(Coordinates::from_ordinals(path));
+        if let Edit::Insert { at, .. } = *edit {
+            if !node.has_children() && old.start() < at {
+                report.split.push(Coordinates::from_ordinals(path));
+            }
+        }
+    }
+    if new.len() < old.len() {
+        report.shrunk.push(Coordinates::from_ordinals(path));
+    }
+
+    let rel = node.get_relative_span();
+    let rel_start = offset(rel.start() as u128, start_delta - parent_start_delta).min(u64::MAX as u128);
+    let rel_end = offset(rel.end_wide(), end_delta - parent_start_delta);
+    node.set_absolute_span(new);
+    node.set_relative_span(AddressRange::from_bounds(rel_start, rel_end).unwrap_or(rel));
+
+    // A node moved as a whole takes every descendant along, and one left in
+    // place keeps them where they are, packed or not
+    if start_delta == end_delta {
+        if start_delta == 0 {
+            return Ok(());
+        }
+        // Checked by `fits` as well, through every packed element
+        if let Some(count) = node.shift_packed_children(start_delta)? {
+            report.shifted += count;
+            return Ok(());
+        }
+    }
+
+    if let Some(children) = node.children_mut() {
+        children.retain(|c| {
+            let removed = edit.removes(c.get_absolute_span());
+            if removed {
+                let mut child_path = path.clone();
+                child_path.push(c.get_ordinal());
+                report.removed.push(Coordinates::from_ordinals(&child_path));
+            }
+            !removed
+        });
+        for child in children.iter_mut() {
+            path.push(child.get_ordinal());
+            apply(child, edit, false, start_delta, path, report)?;
+            path.pop();
+        }
+    }
+    Ok(())
+}
+
+fn offset(value: u128, delta: i128) -> u128 {
+    (value as i128 + delta).clamp(0, u64::MAX as i128 + 1) as u128
+}
+
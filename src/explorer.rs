@@ -0,0 +1,294 @@
+use std::fmt;
+use std::io::{self, BufRead, Write};
+
+use crate::coordinates::Coordinates;
+use crate::disk_offsets::OffsetLayoutsError;
+use crate::file::File;
+use crate::types::NodeRef;
+
+// Maximum number of bytes `hex` dumps when no length is given.
+const DEFAULT_HEX_LEN: usize = 256;
+
+/// Interactive explorer over a loaded layout.
+///
+/// The explorer keeps a current position in the hierarchy (a path of
+/// ordinals, like a working directory) and understands a handful of
+/// shell-like commands:
+///
+/// - `ls` lists the children of the current node with their ranges
+/// - `cd 0/3/5` moves by ordinal or label (`cd __TEXT/__text`); a leading `/`
+///   starts from the file, `..` goes up
+/// - `up` is the same as `cd ..`
+/// - `pwd` prints the current path
+/// - `find 0x4738` runs a reverse lookup and prints the resulting path, with
+///   the label path when nodes are labeled and a note when the address falls
+///   in a gap between children; a `!` marks where the path enters a
+///   mounted file
+/// - `hex [len]` dumps the bytes of the current node (needs [`Explorer::with_data`])
+/// - `stats` prints [`File::stats`] for the whole layout
+/// - `history` lists previous commands, `!n` re-runs the n-th one (counting
+///   from 0, as `history` numbers them)
+///
+/// ```rust
+/// use janus_array::{File, Slices, DiskOffsets};
+/// use janus_array::explorer::Explorer;
+///
+/// let mut file = File::with_size(1024);
+/// let mut slice = Slices::default();
+/// slice.populate_values(0, 512, 0, 512, 0);
+/// file.add_slice(slice);
+///
+/// let mut explorer = Explorer::new(&file);
+/// explorer.execute("cd 0").unwrap();
+/// assert_eq!(explorer.execute("pwd").unwrap(), "/0");
+/// ```
+pub struct Explorer<'a> {
+    file: &'a File,
+    data: Option<&'a [u8]>,
+    cwd: Vec<usize>,
+    history: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub enum ExplorerError {
+    UnknownCommand(String),
+    MissingArgument(&'static str),
+    BadPath(String),
+    BadAddress(String),
+    BadHistoryIndex(String),
+    NoData,
+    OutOfRange(u64),
+    Lookup(OffsetLayoutsError),
+}
+
+impl fmt::Display for ExplorerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExplorerError::UnknownCommand(c) => write!(f, "unknown command `{}` (try `help`)", c),
+            ExplorerError::MissingArgument(cmd) => write!(f, "`{}` needs an argument", cmd),
+            ExplorerError::BadPath(p) => write!(f, "no such node: {}", p),
+            ExplorerError::BadAddress(a) => write!(f, "not an address: {}", a),
+            ExplorerError::BadHistoryIndex(i) => write!(f, "no such history entry: {}", i),
+            ExplorerError::NoData => write!(f, "no file data loaded"),
+            ExplorerError::OutOfRange(o) => write!(f, "out of range: {:#x}", o),
+            ExplorerError::Lookup(e) => write!(f, "lookup failed: {:?}", e),
+        }
+    }
+}
+
+impl<'a> Explorer<'a> {
+    pub fn new(file: &'a File) -> Self {
+        Self { file, data: None, cwd: Vec::new(), history: Vec::new() }
+    }
+
+    /// Attach the raw bytes the layout describes, enabling `hex`.
+    pub fn with_data(mut self, data: &'a [u8]) -> Self {
+        self.data = Some(data);
+        self
+    }
+
+    /// Commands executed so far, oldest first.
+    pub fn history(&self) -> &[String] {
+        &self.history
+    }
+
+    /// The current position as coordinates.
+    pub fn current_coordinates(&self) -> Coordinates {
+        Coordinates::from_ordinals(&self.cwd)
+    }
+
+    /// Execute a single command line and return its output.
+    ///
+    /// The line is recorded in the history whether or not it succeeds; `!n`
+    /// records the command it re-runs, and nothing when there is no entry `n`.
+    pub fn execute(&mut self, line: &str) -> Result<String, ExplorerError> {
+        let line = line.trim();
+        let line = match line.strip_prefix('!') {
+            Some(index) => {
+                let entry = index
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|i| self.history.get(i))
+                    .ok_or_else(|| ExplorerError::BadHistoryIndex(index.to_string()))?;
+                entry.clone()
+            }
+            None => line.to_string(),
+        };
+        self.history.push(line.clone());
+
+        let mut words = line.split_whitespace();
+        let command = words.next().unwrap_or("");
+        let argument = words.next();
+
+        match command {
+            "" => Ok(String::new()),
+            "help" => Ok(HELP.to_string()),
+            "pwd" => Ok(format_path(&self.cwd)),
+            "ls" => self.ls(),
+            "cd" => self.cd(argument.ok_or(ExplorerError::MissingArgument("cd"))?),
+            "up" => self.cd(".."),
+            "find" => self.find(argument.ok_or(ExplorerError::MissingArgument("find"))?),
+            "hex" => self.hex(argument),
+            "stats" => Ok(self.file.stats().to_string()),
+            "history" => Ok(self
+                .history
+                .iter()
+                .enumerate()
+                .map(|(i, h)| format!("{:4}  {}", i, h))
+                .collect::<Vec<_>>()
+                .join("\n")),
+            other => Err(ExplorerError::UnknownCommand(other.to_string())),
+        }
+    }
+
+    /// Run the read-eval-print loop until `quit`, `exit` or end of input.
+    pub fn run<R: BufRead, W: Write>(&mut self, input: R, mut output: W) -> io::Result<()> {
+        write!(output, "janus:{}> ", format_path(&self.cwd))?;
+        output.flush()?;
+        for line in input.lines() {
+            let line = line?;
+            if matches!(line.trim(), "quit" | "exit") {
+                break;
+            }
+            match self.execute(&line) {
+                Ok(text) if text.is_empty() => {}
+                Ok(text) => writeln!(output, "{}", text)?,
+                Err(e) => writeln!(output, "error: {}", e)?,
+            }
+            write!(output, "janus:{}> ", format_path(&self.cwd))?;
+            output.flush()?;
+        }
+        writeln!(output)
+    }
+
+    fn current_node(&self) -> NodeRef<'_> {
+        // `cwd` is only ever updated after the path has been resolved
+        self.file
+            .node_at(&Coordinates::from_ordinals(&self.cwd))
+            .unwrap_or(NodeRef::File(self.file))
+    }
+
+    fn ls(&self) -> Result<String, ExplorerError> {
+        let node = self.current_node();
+        let mut lines = vec![describe(&node)];
+        for child in node.children() {
+            lines.push(format!("  {}", describe(&child)));
+        }
+        Ok(lines.join("\n"))
+    }
+
+    fn cd(&mut self, path: &str) -> Result<String, ExplorerError> {
+        let mut target = if path.starts_with('/') { Vec::new() } else { self.cwd.clone() };
+        for part in path.split('/').filter(|p| !p.is_empty() && *p != ".") {
+            if part == ".." {
+                target.pop();
+                continue;
+            }
+            // A label names a child of the node reached so far; ordinals need no lookup
+            let parent = self.file.node_at(&Coordinates::from_ordinals(&target));
+            let labeled = parent.and_then(|p| p.children().into_iter().find(|c| c.label() == Some(part)));
+            let ordinal = match labeled {
+                Some(child) => child.ordinal(),
+                None => part.parse::<usize>().map_err(|_| ExplorerError::BadPath(path.to_string()))?,
+            };
+            target.push(ordinal);
+        }
+        if target.len() > 3 || self.file.node_at(&Coordinates::from_ordinals(&target)).is_none() {
+            return Err(ExplorerError::BadPath(path.to_string()));
+        }
+        self.cwd = target;
+        Ok(String::new())
+    }
+
+    fn find(&self, address: &str) -> Result<String, ExplorerError> {
+        let addr = parse_address(address)?;
+        let lookup = self.file.locate(addr).map_err(ExplorerError::Lookup)?;
+        let coords = &lookup.coordinates;
+        let ordinals = format!("/{}", coords);
+        let mut line = match self.file.label_path(coords) {
+            Some(labels) if format!("/{}", labels) != ordinals => format!("{:#x} -> {} ({})", addr, ordinals, labels),
+            _ => format!("{:#x} -> {}", addr, ordinals),
+        };
+        if !lookup.is_leaf() {
+            // Nothing below claims the address
+            line.push_str(&format!(", unclaimed: {} {}", lookup.level.name(), lookup.termination));
+        }
+        Ok(line)
+    }
+
+    fn hex(&self, length: Option<&str>) -> Result<String, ExplorerError> {
+        let data = self.data.ok_or(ExplorerError::NoData)?;
+        let limit = match length {
+            Some(l) => to_index(parse_address(l)?)?,
+            None => DEFAULT_HEX_LEN,
+        };
+        let range = self.current_node().absolute_range();
+        let start = to_index(range.start)?.min(data.len());
+        let end = to_index(range.end)?.min(data.len()).min(start.saturating_add(limit));
+
+        let lines: Vec<String> = data[start..end]
+            .chunks(16)
+            .enumerate()
+            .map(|(i, chunk)| {
+                let bytes: Vec<String> = chunk.iter().map(|b| format!("{:02x}", b)).collect();
+                let ascii: String = chunk
+                    .iter()
+                    .map(|&b| if b.is_ascii_graphic() || b == b' ' { b as char } else { '.' })
+                    .collect();
+                format!("{:08x}  {:<47}  |{}|", start + i * 16, bytes.join(" "), ascii)
+            })
+            .collect();
+        Ok(lines.join("\n"))
+    }
+}
+
+const HELP: &str = "\
+ls              list children of the current node
+cd <path>       move by ordinal or label (`0/3/5`, `/__TEXT`, `..`)
+up              move to the parent node
+pwd             print the current path
+find <addr>     locate the node containing an address (hex or decimal)
+hex [len]       dump the bytes of the current node
+stats           counts, sizes and coverage of every level
+history         list previous commands (`!n` re-runs entry n)
+quit            leave the explorer";
+
+fn format_path(path: &[usize]) -> String {
+    let parts: Vec<String> = path.iter().map(|o| o.to_string()).collect();
+    format!("/{}", parts.join("/"))
+}
+
+fn describe(node: &NodeRef<'_>) -> String {
+    let children = node.children().len();
+    let mut line = format!(
+        "[{}] {:<8} abs {}  rel {}  ({} children)",
+        node.ordinal(),
+        node.level().name(),
+        node.absolute_span(),
+        node.relative_span(),
+        children
+    );
+    if let Some(label) = node.label() {
+        line.push_str(&format!("  {}", label));
+    }
+    if let Some(kind) = node.kind() {
+        line.push_str(&format!(" <{}>", kind));
+    }
+    if let Some(mounted) = node.mounted() {
+        line.push_str(&format!("  mounts {} slices", mounted.slices().len()));
+    }
+    line
+}
+
+fn parse_address(text: &str) -> Result<u64, ExplorerError> {
+    let parsed = match text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => text.parse::<u64>(),
+    };
+    parsed.map_err(|_| ExplorerError::BadAddress(text.to_string()))
+}
+
+// Offsets that don't fit in `usize` can't index the loaded data
+fn to_index(offset: u64) -> Result<usize, ExplorerError> {
+    usize::try_from(offset).map_err(|_| ExplorerError::OutOfRange(offset))
+}
@@ -0,0 +1,240 @@
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use crate::disk_offsets::DiskOffsets;
+use crate::file::File;
+use crate::types::NodeRef;
+
+/// Options shared by the DOT and Mermaid exporters.
+#[derive(Debug, Clone, Default)]
+pub struct ExportOptions {
+    /// Deepest level to emit: 0 is the file alone, 1 adds slices, and so on.
+    /// `None` emits the whole tree.
+    pub max_depth: Option<usize>,
+    /// Runs of consecutive childless siblings longer than this are drawn as a
+    /// single summary node. `None` never collapses.
+    pub collapse_leaf_runs: Option<usize>,
+    /// Highlight the path `find_address` returns for this address.
+    pub highlight_address: Option<u64>,
+}
+
+struct GraphNode {
+    id: String,
+    label: Vec<String>,
+    highlighted: bool,
+}
+
+struct GraphEdge {
+    from: String,
+    to: String,
+    highlighted: bool,
+}
+
+#[derive(Default)]
+struct Graph {
+    nodes: Vec<GraphNode>,
+    edges: Vec<GraphEdge>,
+}
+
+/// Render the hierarchy as a Graphviz DOT digraph.
+///
+/// ```rust
+/// use janus_array::{File, Slices, Commands, DiskOffsets};
+/// use janus_array::export::{to_dot, ExportOptions};
+///
+/// let mut file = File::with_size(1024);
+/// let mut slice = Slices::default();
+/// slice.populate_values(0, 512, 0, 512, 0);
+/// let mut command = Commands::default();
+/// command.populate_values(0, 64, 0, 64, 0);
+/// slice.add_command(command);
+/// file.add_slice(slice);
+///
+/// let options = ExportOptions { highlight_address: Some(0x10), ..Default::default() };
+/// let dot = to_dot(&file, &options);
+/// assert!(dot.contains("n_0 -> n_0_0 [color="));
+/// ```
+pub fn to_dot(file: &File, options: &ExportOptions) -> String {
+    let graph = build_graph(file, options);
+    let mut out = String::from("digraph janus {\n    node [shape=box, fontname=\"monospace\"];\n");
+    for node in &graph.nodes {
+        let style = if node.highlighted { ", style=filled, fillcolor=\"#ffd966\"" } else { "" };
+        out.push_str(&format!(
+            "    {} [label=\"{}\"{}];\n",
+            node.id,
+            node.label.iter().map(|l| escape_dot(l)).collect::<Vec<_>>().join("\\n"),
+            style
+        ));
+    }
+    for edge in &graph.edges {
+        let style = if edge.highlighted { " [color=\"#cc0000\", penwidth=2]" } else { "" };
+        out.push_str(&format!("    {} -> {}{};\n", edge.from, edge.to, style));
+    }
+    out.push_str("}\n");
+    out
+}
+
+/// Render the hierarchy as a Mermaid flowchart.
+pub fn to_mermaid(file: &File, options: &ExportOptions) -> String {
+    let graph = build_graph(file, options);
+    let mut out = String::from("flowchart TD\n");
+    for node in &graph.nodes {
+        let label: Vec<String> = node.label.iter().map(|l| escape_mermaid(l)).collect();
+        out.push_str(&format!("    {}[\"{}\"]\n", node.id, label.join("<br/>")));
+    }
+    for edge in &graph.edges {
+        out.push_str(&format!("    {} --> {}\n", edge.from, edge.to));
+    }
+
+    let highlighted: Vec<&str> = graph
+        .nodes
+        .iter()
+        .filter(|n| n.highlighted)
+        .map(|n| n.id.as_str())
+        .collect();
+    if !highlighted.is_empty() {
+        out.push_str("    classDef highlight fill:#ffd966,stroke:#cc0000;\n");
+        out.push_str(&format!("    class {} highlight;\n", highlighted.join(",")));
+    }
+    for (i, edge) in graph.edges.iter().enumerate() {
+        if edge.highlighted {
+            out.push_str(&format!("    linkStyle {} stroke:#cc0000,stroke-width:2px;\n", i));
+        }
+    }
+    out
+}
+
+fn build_graph(file: &File, options: &ExportOptions) -> Graph {
+    // An address that cannot be found simply highlights nothing
+    let highlight: Option<Vec<usize>> = options
+        .highlight_address
+        .and_then(|addr| file.find_address(addr).ok())
+        .map(|c| c.ordinals());
+
+    let mut graph = Graph::default();
+    let root = NodeRef::File(file);
+    let mut path = Vec::new();
+    add_node(&mut graph, &root, &mut path, options, highlight.as_deref());
+    graph
+}
+
+fn add_node(
+    graph: &mut Graph,
+    node: &NodeRef<'_>,
+    path: &mut Vec<usize>,
+    options: &ExportOptions,
+    highlight: Option<&[usize]>,
+) -> String {
+    let id = node_id(path);
+    let on_path = highlight.is_some_and(|h| h.starts_with(path));
+    let children = node.children();
+    let truncated = options.max_depth.is_some_and(|d| path.len() >= d) && !children.is_empty();
+
+    let mut label = describe(node);
+    if truncated {
+        label.push(format!("({} children hidden)", children.len()));
+    }
+    graph.nodes.push(GraphNode { id: id.clone(), label, highlighted: on_path });
+    if truncated {
+        return id;
+    }
+
+    let mut i = 0;
+    while i < children.len() {
+        // Leaves from `i` on, only counted when runs may collapse
+        let run = match options.collapse_leaf_runs {
+            Some(_) => children[i..].iter().take_while(|c| !c.has_children()).count(),
+            None => 0,
+        };
+        if options.collapse_leaf_runs.is_some_and(|limit| run > limit) {
+            let run_nodes = &children[i..i + run];
+            let run_on_path = on_path
+                && highlight.is_some_and(|h| {
+                    h.get(path.len()).is_some_and(|o| run_nodes.iter().any(|c| c.ordinal() == *o))
+                });
+            let run_id = add_run(graph, path, run_nodes, run_on_path);
+            graph.edges.push(GraphEdge { from: id.clone(), to: run_id, highlighted: run_on_path });
+            i += run;
+            continue;
+        }
+        // A run too short to collapse is drawn node by node, then skipped at once
+        let step = run.max(1);
+        for child in &children[i..i + step] {
+            path.push(child.ordinal());
+            let child_on_path = highlight.is_some_and(|h| h.starts_with(path));
+            let child_id = add_node(graph, child, path, options, highlight);
+            path.pop();
+            graph.edges.push(GraphEdge { from: id.clone(), to: child_id, highlighted: child_on_path });
+        }
+        i += step;
+    }
+    id
+}
+
+fn add_run(graph: &mut Graph, path: &[usize], run: &[NodeRef<'_>], highlighted: bool) -> String {
+    let first = &run[0];
+    let last = &run[run.len() - 1];
+    let id = format!("{}_run{}", node_id(path), first.ordinal());
+    let label = vec![
+        format!("{} x {}", run.len(), first.level().name()),
+        format!("ordinals {}..={}", first.ordinal(), last.ordinal()),
+        format!("abs {}", last.absolute_span().with_start(first.absolute_span().start())),
+    ];
+    graph.nodes.push(GraphNode { id: id.clone(), label, highlighted });
+    id
+}
+
+fn node_id(path: &[usize]) -> String {
+    let mut id = String::from("n");
+    for ordinal in path {
+        id.push('_');
+        id.push_str(&ordinal.to_string());
+    }
+    id
+}
+
+fn describe(node: &NodeRef<'_>) -> Vec<String> {
+    let mut heading = format!("{} {}", node.level().name(), node.ordinal());
+    if let Some(label) = node.label() {
+        // Free-form: each format escapes it when rendering
+        heading.push_str(&format!(" {}", label));
+    }
+    vec![
+        heading,
+        format!("abs {}", node.absolute_span()),
+        format!("rel {}", node.relative_span()),
+    ]
+}
+
+// Inside a DOT double-quoted string
+fn escape_dot(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '"' => out.push_str("\\\""),
+            '\n' => out.push_str("\\n"),
+            '\r' => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+// Inside a Mermaid quoted label, which is rendered as HTML
+fn escape_mermaid(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("#quot;"),
+            '\\' => out.push_str("#92;"),
+            '<' => out.push_str("#lt;"),
+            '>' => out.push_str("#gt;"),
+            '\n' => out.push_str("<br/>"),
+            '\r' => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
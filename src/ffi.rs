@@ -0,0 +1,296 @@
+//! C ABI over `u64` layouts.
+//!
+//! A layout is built through an opaque [`JanusFile`] handle: records are added
+//! in any order with `janus_add_slice` / `janus_add_command` /
+//! `janus_add_element`, each carrying a caller-chosen tag, then
+//! `janus_finalize` places them exactly like [`LayoutBuilder`] does. After
+//! that the handle only answers queries. Every function reports failures with
+//! one of the `JANUS_*` status codes; a panic inside the library is caught at
+//! the boundary and reported as `JANUS_BROKEN` (or a null handle) instead of
+//! unwinding into C.
+//!
+//! The matching header is `include/janus_array.h`. It is generated from this
+//! file by the `ffi` integration test; run it with `JANUS_BLESS=1` and
+//! `--features ffi` after changing any declaration here. The module only
+//! exists with the `ffi` feature, which needs `std` to catch panics. To link from C, build the crate as a static
+//! or dynamic library, e.g.
+//! `cargo rustc --release --lib --features ffi --crate-type staticlib`.
+
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use core::ffi::c_void;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use crate::builder::LayoutBuilder;
+use crate::disk_offsets::{DiskOffsets, OffsetLayoutsError};
+use crate::file::File;
+use crate::types::{NodeLevel, NodeRef};
+
+/// Status code returned by every function of the C API.
+pub type JanusStatus = i32;
+
+/// Success.
+pub const JANUS_OK: JanusStatus = 0;
+/// The address falls in a gap between slices.
+pub const JANUS_NOT_FOUND: JanusStatus = 1;
+/// The address is outside the file.
+pub const JANUS_OUT_OF_RANGE: JanusStatus = 2;
+/// The layout is inconsistent, or the library panicked.
+pub const JANUS_BROKEN: JanusStatus = 3;
+/// A required pointer argument was null.
+pub const JANUS_NULL_POINTER: JanusStatus = 4;
+/// `offset + size` runs past the end of the address space.
+pub const JANUS_OVERFLOW: JanusStatus = 5;
+/// Records can't be added once the layout is finalized.
+pub const JANUS_FINALIZED: JanusStatus = 6;
+/// Queries need a finalized layout.
+pub const JANUS_NOT_FINALIZED: JanusStatus = 7;
+/// Some records could not be placed; the layout was built without them.
+pub const JANUS_UNPLACED: JanusStatus = 8;
+/// An iteration callback asked to stop.
+pub const JANUS_STOPPED: JanusStatus = 9;
+
+/// Opaque layout handle.
+pub struct JanusFile {
+    builder: Option<LayoutBuilder<u64>>,
+    file: File,
+    tags: BTreeMap<Vec<usize>, u64>,
+}
+
+/// Result of `janus_find_address`.
+///
+/// `depth` is the number of valid ordinals: 1 for a slice, 2 for a command,
+/// 3 for an element, 0 when `status` is not `JANUS_OK`. `tag` is the tag of
+/// the deepest node found.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JanusCoordinates {
+    pub status: JanusStatus,
+    pub depth: u32,
+    pub slice: usize,
+    pub command: usize,
+    pub element: usize,
+    pub tag: u64,
+}
+
+/// A node as seen by iteration callbacks.
+///
+/// `end` is exclusive and saturates at `UINT64_MAX` for a node holding the
+/// last byte of the address space.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JanusNode {
+    pub depth: u32,
+    pub slice: usize,
+    pub command: usize,
+    pub element: usize,
+    pub start: u64,
+    pub end: u64,
+    pub tag: u64,
+}
+
+/// Iteration callback; return non-zero to stop the iteration.
+pub type JanusVisitor = Option<unsafe extern "C" fn(node: *const JanusNode, user_data: *mut c_void) -> i32>;
+
+impl JanusCoordinates {
+    fn failed(status: JanusStatus) -> Self {
+        Self { status, depth: 0, slice: 0, command: 0, element: 0, tag: 0 }
+    }
+}
+
+// Run an entry point, turning a panic into `failed` so it never crosses into C.
+fn guard<T>(failed: T, body: impl FnOnce() -> T) -> T {
+    catch_unwind(AssertUnwindSafe(body)).unwrap_or(failed)
+}
+
+fn status_of(error: &OffsetLayoutsError) -> JanusStatus {
+    match error {
+        OffsetLayoutsError::NotFound(_) => JANUS_NOT_FOUND,
+        OffsetLayoutsError::AddressOutsideCurrentScope(..) => JANUS_OUT_OF_RANGE,
+        OffsetLayoutsError::AddressOverflow(..) => JANUS_OVERFLOW,
+        _ => JANUS_BROKEN,
+    }
+}
+
+/// Create an empty layout for a file of `size` bytes starting at address 0.
+/// Release it with `janus_file_free`. Returns null if the handle can't be created.
+#[no_mangle]
+pub extern "C" fn janus_file_new(size: u64) -> *mut JanusFile {
+    guard(core::ptr::null_mut(), || {
+        let handle = JanusFile { builder: Some(LayoutBuilder::new(size)), file: File::new(), tags: BTreeMap::new() };
+        Box::into_raw(Box::new(handle))
+    })
+}
+
+/// Release a layout. Null is accepted and ignored.
+///
+/// # Safety
+/// `file` must be null or a handle returned by `janus_file_new` that was not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn janus_file_free(file: *mut JanusFile) {
+    guard((), || {
+        if !file.is_null() {
+            drop(Box::from_raw(file));
+        }
+    })
+}
+
+unsafe fn add(file: *mut JanusFile, level: NodeLevel, offset: u64, size: u64, tag: u64) -> JanusStatus {
+    let Some(handle) = file.as_mut() else {
+        return JANUS_NULL_POINTER;
+    };
+    let Some(builder) = handle.builder.as_mut() else {
+        return JANUS_FINALIZED;
+    };
+    match builder.push_sized(level, offset, size, tag) {
+        Ok(()) => JANUS_OK,
+        Err(error) => status_of(&error),
+    }
+}
+
+/// Record a slice of `size` bytes at absolute address `offset`.
+///
+/// # Safety
+/// `file` must be null or a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn janus_add_slice(file: *mut JanusFile, offset: u64, size: u64, tag: u64) -> JanusStatus {
+    guard(JANUS_BROKEN, || add(file, NodeLevel::Slice, offset, size, tag))
+}
+
+/// Record a command; it goes under the slice containing it.
+///
+/// # Safety
+/// `file` must be null or a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn janus_add_command(file: *mut JanusFile, offset: u64, size: u64, tag: u64) -> JanusStatus {
+    guard(JANUS_BROKEN, || add(file, NodeLevel::Command, offset, size, tag))
+}
+
+/// Record an element; it goes under the command containing it.
+///
+/// # Safety
+/// `file` must be null or a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn janus_add_element(file: *mut JanusFile, offset: u64, size: u64, tag: u64) -> JanusStatus {
+    guard(JANUS_BROKEN, || add(file, NodeLevel::Element, offset, size, tag))
+}
+
+/// Place every record and freeze the layout. Returns `JANUS_UNPLACED` if some
+/// records fit nowhere; their number is stored in `unplaced` when it is not null.
+///
+/// # Safety
+/// `file` must be null or a live handle; `unplaced` must be null or writable.
+#[no_mangle]
+pub unsafe extern "C" fn janus_finalize(file: *mut JanusFile, unplaced: *mut usize) -> JanusStatus {
+    guard(JANUS_BROKEN, || finalize(file, unplaced))
+}
+
+unsafe fn finalize(file: *mut JanusFile, unplaced: *mut usize) -> JanusStatus {
+    let Some(handle) = file.as_mut() else {
+        return JANUS_NULL_POINTER;
+    };
+    let Some(builder) = handle.builder.take() else {
+        return JANUS_FINALIZED;
+    };
+    let built = builder.finish();
+    handle.tags = built.payloads.into_iter().map(|(coords, tag)| (coords.ordinals(), tag)).collect();
+    handle.file = built.file;
+    if !unplaced.is_null() {
+        *unplaced = built.orphans.len();
+    }
+    if built.orphans.is_empty() {
+        JANUS_OK
+    } else {
+        JANUS_UNPLACED
+    }
+}
+
+/// Locate the deepest node containing `address`.
+///
+/// # Safety
+/// `file` must be null or a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn janus_find_address(file: *const JanusFile, address: u64) -> JanusCoordinates {
+    guard(JanusCoordinates::failed(JANUS_BROKEN), || find_address(file, address))
+}
+
+unsafe fn find_address(file: *const JanusFile, address: u64) -> JanusCoordinates {
+    let Some(handle) = file.as_ref() else {
+        return JanusCoordinates::failed(JANUS_NULL_POINTER);
+    };
+    if handle.builder.is_some() {
+        return JanusCoordinates::failed(JANUS_NOT_FINALIZED);
+    }
+    match handle.file.find_address(address) {
+        Ok(coords) => {
+            let path = coords.ordinals();
+            JanusCoordinates {
+                status: JANUS_OK,
+                depth: path.len() as u32,
+                slice: coords.slice.unwrap_or(0),
+                command: coords.command.unwrap_or(0),
+                element: coords.element.unwrap_or(0),
+                tag: handle.tags.get(&path).copied().unwrap_or(0),
+            }
+        }
+        Err(error) => JanusCoordinates::failed(status_of(&error)),
+    }
+}
+
+/// Call `visit` on every node below the file, depth first in address order.
+/// Returns `JANUS_STOPPED` if the callback stopped the walk early.
+///
+/// # Safety
+/// `file` must be null or a live handle; `visit` must be safe to call with
+/// `user_data`.
+#[no_mangle]
+pub unsafe extern "C" fn janus_for_each_node(
+    file: *const JanusFile,
+    visit: JanusVisitor,
+    user_data: *mut c_void,
+) -> JanusStatus {
+    guard(JANUS_BROKEN, || for_each_node(file, visit, user_data))
+}
+
+unsafe fn for_each_node(file: *const JanusFile, visit: JanusVisitor, user_data: *mut c_void) -> JanusStatus {
+    let (Some(handle), Some(visit)) = (file.as_ref(), visit) else {
+        return JANUS_NULL_POINTER;
+    };
+    if handle.builder.is_some() {
+        return JANUS_NOT_FINALIZED;
+    }
+    let mut path = Vec::new();
+    walk(handle, &NodeRef::File(&handle.file), &mut path, visit, user_data)
+}
+
+unsafe fn walk(
+    handle: &JanusFile,
+    node: &NodeRef<'_>,
+    path: &mut Vec<usize>,
+    visit: unsafe extern "C" fn(*const JanusNode, *mut c_void) -> i32,
+    user_data: *mut c_void,
+) -> JanusStatus {
+    for child in node.children() {
+        path.push(child.ordinal());
+        let range = child.absolute_range();
+        let info = JanusNode {
+            depth: path.len() as u32,
+            slice: path.first().copied().unwrap_or(0),
+            command: path.get(1).copied().unwrap_or(0),
+            element: path.get(2).copied().unwrap_or(0),
+            start: range.start,
+            end: range.end,
+            tag: handle.tags.get(path.as_slice()).copied().unwrap_or(0),
+        };
+        if visit(&info, user_data) != 0 {
+            return JANUS_STOPPED;
+        }
+        let status = walk(handle, &child, path, visit, user_data);
+        path.pop();
+        if status != JANUS_OK {
+            return status;
+        }
+    }
+    JANUS_OK
+}
@@ -1,132 +1,248 @@
-use std::ops::Range;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use alloc::sync::Arc;
+
+use crate::address::Address;
+use crate::address_range::AddressRange;
 
 use crate::slices::Slices;
-use crate::types::ChildrenArray;
-use crate::disk_offsets::{DiskOffsets, OffsetLayoutsError};
+use crate::types::{ChildrenArray, NodeLevel, NodeMut, NodeRef};
+use crate::disk_offsets::{child_error, insert_sorted, locate_child, order_key, termination, update_sorted, DiskOffsets, OffsetLayoutsError};
 use crate::coordinates::Coordinates;
+use crate::lookup::{Lookup, LookupPolicy};
+use crate::labels::{Labeled, NodeTags};
 
+/// Root of the hierarchy.
+///
+/// The file is its own reference frame: its relative range is always its
+/// absolute range, so the relative setters of [`DiskOffsets`] are no-ops here.
+///
+/// Children are stored behind `Arc`s and copied on write, so cloning a `File`
+/// is cheap and gives an independent version: editing one copy only
+/// duplicates the levels along the edited path, everything else stays shared.
+/// [`crate::versioned::VersionedFile`] builds on this to publish snapshots.
 #[derive(Clone, Debug)]
-pub struct File {
-    pub start_abs_address: u64,
-    pub end_abs_address: u64,
-    pub start_rel_address: u64, // Always 0 for File (root level)
-    pub end_rel_address: u64,   // Same as end_abs_address
+pub struct File<A = u64> {
+    absolute_range: AddressRange<A>,
 
-    pub absolute_range: Range<u64>,
-    pub relative_range: Range<u64>, // Same as absolute_range for File
+    my_ordinal: usize, // Always 0 for File (only one file per structure)
 
-    pub my_ordinal: usize, // Always 0 for File (only one file per structure)
+    // Kept sorted by start address; shared copy-on-write between clones
+    pub(crate) slices: Option<Arc<Vec<Slices<A>>>>,
 
-    pub slices: Option<Vec<Slices>>,
+    tags: Option<Box<NodeTags>>,
+}
+
+impl File {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a new File with specified size
+    pub fn with_size(file_size: u64) -> Self {
+        Self::with_span(AddressRange::default().with_end(file_size))
+    }
 }
 
 impl Default for File {
     fn default() -> Self {
-        let empty_range: Range<u64> = 0..0;
-        Self {
-            start_abs_address: 0,
-            end_abs_address: 0,
-            start_rel_address: 0,
-            end_rel_address: 0,
-            absolute_range: empty_range.clone(),
-            relative_range: empty_range,
-            my_ordinal: 0,
-            slices: None,
-        }
+        Self::with_span(AddressRange::default())
     }
 }
 
-impl File {
-    pub fn new() -> Self {
-        let empty_range: Range<u64> = 0..0;
+impl<A: Address> File<A> {
+    /// Create a new File covering `span`, for any address type. The span may
+    /// run up to and including the last address.
+    pub fn with_span(span: AddressRange<A>) -> Self {
         Self {
-            start_abs_address: 0,
-            end_abs_address: 0,
-            start_rel_address: 0,
-            end_rel_address: 0,
-            absolute_range: empty_range.clone(),
-            relative_range: empty_range,
+            absolute_range: span,
             my_ordinal: 0,
             slices: None,
+            tags: None,
         }
     }
 
-    /// Create a new File with specified size
-    pub fn with_size(file_size: u64) -> Self {
-        let range = 0..file_size;
-        Self {
-            start_abs_address: 0,
-            end_abs_address: file_size,
-            start_rel_address: 0,
-            end_rel_address: file_size,
-            absolute_range: range.clone(),
-            relative_range: range,
-            my_ordinal: 0,
-            slices: None,
-        }
+    /// Slices of the file, sorted by start address
+    pub fn slices(&self) -> &[Slices<A>] {
+        self.slices.as_deref().map(Vec::as_slice).unwrap_or(&[])
     }
 
-    /// Add a slice to the file
-    pub fn add_slice(&mut self, slice: Slices) {
-        match &mut self.slices {
-            Some(slices) => slices.push(slice),
-            None => self.slices = Some(vec![slice]),
-        }
+    /// Add a slice to the file, keeping slices sorted by start address
+    pub fn add_slice(&mut self, slice: Slices<A>) {
+        insert_sorted(Arc::make_mut(self.slices.get_or_insert_with(Default::default)), slice);
+    }
+
+    /// Get the slice whose ordinal is `ordinal`
+    pub fn get_slice(&self, ordinal: usize) -> Option<&Slices<A>> {
+        self.slices().iter().find(|s| s.get_ordinal() == ordinal)
     }
-}
 
-impl DiskOffsets for File {
-    fn set_start_abs_address(&mut self, start_abs: u64) {
-        self.start_abs_address = start_abs;
+    /// Modify the slice whose ordinal is `ordinal` in place.
+    ///
+    /// The slice is moved back to its sorted position afterwards, so the
+    /// closure is free to change its range.
+    pub fn update_slice<R>(&mut self, ordinal: usize, f: impl FnOnce(&mut Slices<A>) -> R) -> Option<R> {
+        update_sorted(self.slices.as_mut().map(Arc::make_mut)?, ordinal, f)
     }
 
-    fn set_start_rel_address(&mut self, start_rel: u64) {
-        self.start_rel_address = start_rel;
+    /// Remove and return the slice whose ordinal is `ordinal`
+    pub fn remove_slice(&mut self, ordinal: usize) -> Option<Slices<A>> {
+        let slices = self.slices.as_mut().map(Arc::make_mut)?;
+        let index = slices.iter().position(|s| s.get_ordinal() == ordinal)?;
+        Some(slices.remove(index))
     }
 
-    fn set_end_abs_address(&mut self, end_abs: u64) {
-        self.end_abs_address = end_abs;
+    /// Resolve a set of coordinates to the deepest node they designate.
+    ///
+    /// Empty coordinates designate the file itself.
+    pub fn node_at(&self, coords: &Coordinates) -> Option<NodeRef<'_, A>> {
+        self.resolve(coords).ok()
     }
 
-    fn set_end_rel_address(&mut self, end_rel: u64) {
-        self.end_rel_address = end_rel;
+    /// Same as [`File::node_at`], failing with
+    /// [`OffsetLayoutsError::StaleCoordinates`] when the coordinates point to
+    /// a node that isn't there (anymore).
+    ///
+    /// ```rust
+    /// use janus_array::{Coordinates, DiskOffsets, File, OffsetLayoutsError, Slices};
+    ///
+    /// let mut file = File::with_size(0x100);
+    /// let mut slice = Slices::default();
+    /// slice.populate_values(0, 0x100, 0, 0x100, 0);
+    /// file.add_slice(slice);
+    ///
+    /// let coords: Coordinates = "0".parse().unwrap();
+    /// assert_eq!(file.resolve(&coords).unwrap().ordinal(), 0);
+    /// file.remove_slice(0);
+    /// assert!(matches!(file.resolve(&coords), Err(OffsetLayoutsError::StaleCoordinates(_, 0))));
+    /// ```
+    ///
+    /// Composite coordinates resolve to a node of the mounted file; the depth
+    /// of a stale path then counts each mount as one more step.
+    pub fn resolve(&self, coords: &Coordinates) -> Result<NodeRef<'_, A>, OffsetLayoutsError<A>> {
+        let mut node = NodeRef::File(self);
+        let ordinals = coords.ordinals();
+        for (depth, &ordinal) in ordinals.iter().enumerate() {
+            node = node
+                .child(ordinal)
+                .ok_or_else(|| OffsetLayoutsError::StaleCoordinates(coords.clone(), depth))?;
+        }
+        let Some(inner) = coords.inner() else {
+            return Ok(node);
+        };
+        let stale = |depth| OffsetLayoutsError::StaleCoordinates(coords.clone(), depth);
+        let mounted = node.mounted().ok_or_else(|| stale(ordinals.len()))?;
+        mounted.resolve(inner).map_err(|e| match e {
+            OffsetLayoutsError::StaleCoordinates(_, depth) => stale(ordinals.len() + 1 + depth),
+            e => e,
+        })
+    }
+
+    // Mutable access for changes that leave ranges alone, mounts included.
+    pub(crate) fn node_mut_at(&mut self, coords: &Coordinates) -> Option<NodeMut<'_, A>> {
+        let mut node = NodeMut::File(self);
+        for ordinal in coords.ordinals() {
+            node = node.child_mut(ordinal)?;
+        }
+        match coords.inner() {
+            None => Some(node),
+            Some(inner) => Arc::make_mut(node.mount_point_mut()?.as_mut()?).node_mut_at(inner),
+        }
+    }
+
+    /// Like [`DiskOffsets::find_address`], also telling at which level the
+    /// search stopped and why, so unclaimed bytes inside a structure can be
+    /// told apart from bytes owned by a leaf. An address between slices is
+    /// reported as a [`crate::Termination`] at the file level rather than as
+    /// [`OffsetLayoutsError::NotFound`].
+    pub fn locate(&self, address: A) -> Result<Lookup, OffsetLayoutsError<A>> {
+        self.locate_with(address, LookupPolicy::default())
+    }
+
+    /// [`File::locate`] under an explicit [`LookupPolicy`].
+    pub fn locate_with(&self, address: A, policy: LookupPolicy) -> Result<Lookup, OffsetLayoutsError<A>> {
+        let absolute_range = self.get_absolute_range();
+        if !self.contains_absolute_address(address) {
+            return Err(OffsetLayoutsError::AddressOutsideCurrentScope(address, absolute_range));
+        }
+        if policy == LookupPolicy::Strict && !self.has_children() {
+            return Err(OffsetLayoutsError::InconsistentStructure(address, absolute_range));
+        }
+        let slices = self.slices();
+        match locate_child(slices, address) {
+            Some(index) => slices[index]
+                .locate_with(address, policy)
+                .map_err(|e| child_error(e, OffsetLayoutsError::SliceIsBroken)),
+            None => Ok(Lookup {
+                coordinates: Coordinates::new(),
+                level: NodeLevel::File,
+                termination: termination(slices, address),
+            }),
+        }
     }
 
-    fn set_absolute_range_explicit(&mut self, range: Range<u64>) {
-        self.absolute_range = range;
+    /// Look up `relative`, an offset from the start of the slice whose
+    /// ordinal is `slice`, and return full coordinates. A slice that doesn't
+    /// exist is reported as [`OffsetLayoutsError::StaleCoordinates`].
+    ///
+    /// ```rust
+    /// use janus_array::{Commands, DiskOffsets, File, Slices};
+    ///
+    /// let mut command = Commands::default();
+    /// command.populate_values(0x4020, 0x4040, 0x20, 0x40, 3);
+    /// let mut slice = Slices::default();
+    /// slice.populate_values(0x4000, 0x8000, 0x4000, 0x8000, 1);
+    /// slice.add_command(command);
+    /// let mut file = File::with_size(0x8000);
+    /// file.add_slice(slice);
+    ///
+    /// let coords = file.find_in_slice(1, 0x30).unwrap();
+    /// assert_eq!((coords.slice, coords.command), (Some(1), Some(3)));
+    /// ```
+    pub fn find_in_slice(&self, slice: usize, relative: A) -> Result<Coordinates, OffsetLayoutsError<A>> {
+        self.get_slice(slice)
+            .ok_or_else(|| OffsetLayoutsError::StaleCoordinates(Coordinates::from_ordinals(&[slice]), 0))?
+            .find_relative_address(relative)
     }
 
-    fn set_absolute_range_implicit(&mut self) {
-        self.absolute_range = self.start_abs_address..self.end_abs_address;
+    /// [`DiskOffsets::find_address`] under an explicit [`LookupPolicy`].
+    ///
+    /// An address outside every slice is [`OffsetLayoutsError::NotFound`]
+    /// under both policies, including in a file without slices, which
+    /// [`LookupPolicy::Lenient`] otherwise treats as a leaf.
+    pub fn find_address_with(&self, address: A, policy: LookupPolicy) -> Result<Coordinates, OffsetLayoutsError<A>> {
+        let lookup = self.locate_with(address, policy)?;
+        if lookup.coordinates.depth() == 0 {
+            // Address falls between slices, or the file has none
+            return Err(OffsetLayoutsError::NotFound(address));
+        }
+        Ok(lookup.coordinates)
     }
+}
 
-    fn set_relative_range_explicit(&mut self, range: Range<u64>) {
-        self.relative_range = range;
+impl<A: Address> DiskOffsets<A> for File<A> {
+    fn set_absolute_span(&mut self, span: AddressRange<A>) {
+        self.absolute_range = span;
     }
 
-    fn set_relative_range_implicit(&mut self) {
+    fn set_relative_span(&mut self, _span: AddressRange<A>) {
         // For File, relative range is same as absolute range (it's the root)
-        self.relative_range = self.start_abs_address..self.end_abs_address;
     }
 
     fn set_ordinal(&mut self, ordinal: usize) {
         self.my_ordinal = ordinal;
     }
 
-    fn get_absolute_range(&self) -> Range<u64> {
-        self.absolute_range.clone()
-    }
-
-    fn get_relative_range(&self) -> Range<u64> {
-        self.relative_range.clone()
+    fn get_ordinal(&self) -> usize {
+        self.my_ordinal
     }
 
-    fn get_max_abs_address(&self) -> u64 {
-        self.end_abs_address
+    fn get_absolute_span(&self) -> AddressRange<A> {
+        self.absolute_range
     }
 
-    fn get_min_abs_address(&self) -> u64 {
-        self.start_abs_address
+    fn get_relative_span(&self) -> AddressRange<A> {
+        self.absolute_range
     }
 
     fn has_children(&self) -> bool {
@@ -136,10 +252,9 @@ impl DiskOffsets for File {
         }
     }
 
-    fn get_children(&mut self) -> Option<ChildrenArray> {
+    fn get_children(&self) -> Option<ChildrenArray<A>> {
         if self.has_children() {
-            self.sort_children();
-            let children = self.slices.clone()?;
+            let children = self.slices.as_deref()?.clone();
             Some(ChildrenArray::File(children))
         } else {
             None
@@ -147,86 +262,24 @@ impl DiskOffsets for File {
     }
 
     fn sort_children(&mut self) {
-        if let Some(children) = &mut self.slices {
-            children.sort_by_key(|s| s.start_abs_address);
+        if let Some(children) = self.slices.as_mut().map(Arc::make_mut) {
+            children.sort_by_key(order_key);
         }
     }
 
-    fn find_address(&mut self, absolute_address: u64) -> Result<Coordinates, OffsetLayoutsError> {
-        let absolute_range = self.get_absolute_range();
-        
-        if !absolute_range.contains(&absolute_address) {
-            return Err(OffsetLayoutsError::AddressOutsideCurrentScope(
-                absolute_address,
-                absolute_range,
-            ));
-        }
+    /// Under [`LookupPolicy::Lenient`]; see [`File::find_address_with`] for
+    /// addresses outside every slice.
+    fn find_address(&self, absolute_address: A) -> Result<Coordinates, OffsetLayoutsError<A>> {
+        self.find_address_with(absolute_address, LookupPolicy::default())
+    }
+}
 
-        let mut retval = Coordinates::new();
-        // File level doesn't set any coordinate (it's the root)
+impl<A> Labeled for File<A> {
+    fn tags(&self) -> Option<&NodeTags> {
+        self.tags.as_deref()
+    }
 
-        if self.has_children() {
-            let mut children = if let Some(ChildrenArray::File(v)) = self.get_children() {
-                v
-            } else {
-                return Err(OffsetLayoutsError::InconsistentSearch);
-            };
-
-            let mut start: usize = 0;
-            let mut end: usize = children.len() - 1;
-
-            while start <= end {
-                let mid = (start + end) / 2;
-                let range = children[mid].get_absolute_range();
-                
-                if range.contains(&absolute_address) {
-                    // Found the slice! Delegate the search recursively
-                    match children[mid].find_address(absolute_address) {
-                        Ok(coords) => {
-                            // Merge coordinates from child search
-                            if let Some(s) = coords.slice {
-                                retval.set_slice(s);
-                            } else {
-                                return Err(OffsetLayoutsError::SliceIsBroken);
-                            }
-                            if let Some(c) = coords.command {
-                                retval.set_command(c);
-                            }
-                            if let Some(e) = coords.element {
-                                retval.set_element(e);
-                            }
-                        }
-                        Err(_) => return Err(OffsetLayoutsError::SliceIsBroken),
-                    }
-                    return Ok(retval);
-                } else {
-                    let min = children[mid].get_min_abs_address();
-                    let max = children[mid].get_max_abs_address();
-                    
-                    if absolute_address < min {
-                        if mid == 0 {
-                            break;
-                        }
-                        end = mid - 1;
-                    } else if absolute_address > max {
-                        start = mid + 1;
-                    } else {
-                        return Err(OffsetLayoutsError::InconsistentStructure(
-                            absolute_address,
-                            range,
-                        ));
-                    }
-                }
-            }
-            
-            // If we get here, address wasn't found in any slice
-            Err(OffsetLayoutsError::NotFound(absolute_address))
-        } else {
-            // File has no slices - this shouldn't happen in practice
-            Err(OffsetLayoutsError::InconsistentStructure(
-                absolute_address,
-                absolute_range,
-            ))
-        }
+    fn tags_mut(&mut self) -> &mut Option<Box<NodeTags>> {
+        &mut self.tags
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,257 @@
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+use crate::address::Address;
+use crate::coordinates::Coordinates;
+use crate::disk_offsets::{DiskOffsets, OffsetLayoutsError};
+use crate::file::File;
+use crate::types::{NodeMut, NodeRef};
+
+/// Optional names attached to a node: a label identifying it (`"__TEXT"`,
+/// `"LC_SYMTAB"`, `".rodata"`) and a kind saying what it is (`"segment"`,
+/// `"load_command"`, `"section"`).
+///
+/// Both are reference counted, so the same kind can be shared by any number
+/// of nodes. Nodes without names pay for a single null pointer.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NodeTags {
+    label: Option<Arc<str>>,
+    kind: Option<Arc<str>>,
+}
+
+/// Access to the [`NodeTags`] of a node.
+///
+/// Labels are free-form, but a label containing `/` can't be addressed in a
+/// label path.
+///
+/// ```rust
+/// use janus_array::{Labeled, Slices};
+///
+/// let mut segment = Slices::default();
+/// segment.set_label("__TEXT");
+/// segment.set_kind("segment");
+/// assert_eq!(segment.label(), Some("__TEXT"));
+/// assert_eq!(segment.kind(), Some("segment"));
+/// ```
+pub trait Labeled {
+    // Functions to implement:
+    fn tags(&self) -> Option<&NodeTags>;
+    fn tags_mut(&mut self) -> &mut Option<Box<NodeTags>>;
+    //
+
+    fn label(&self) -> Option<&str> {
+        self.tags()?.label.as_deref()
+    }
+
+    fn kind(&self) -> Option<&str> {
+        self.tags()?.kind.as_deref()
+    }
+
+    fn set_label(&mut self, label: impl Into<Arc<str>>) {
+        self.tags_mut().get_or_insert_with(Default::default).label = Some(label.into());
+    }
+
+    fn set_kind(&mut self, kind: impl Into<Arc<str>>) {
+        self.tags_mut().get_or_insert_with(Default::default).kind = Some(kind.into());
+    }
+
+    /// Remove both the label and the kind.
+    fn clear_tags(&mut self) {
+        *self.tags_mut() = None;
+    }
+}
+
+impl<'a, A: Address> NodeRef<'a, A> {
+    pub fn label(&self) -> Option<&'a str> {
+        self.tags()?.label.as_deref()
+    }
+
+    pub fn kind(&self) -> Option<&'a str> {
+        self.tags()?.kind.as_deref()
+    }
+
+    fn tags(&self) -> Option<&'a NodeTags> {
+        match *self {
+            NodeRef::File(f) => f.tags(),
+            NodeRef::Slice(s) => s.tags(),
+            NodeRef::Command(c) => c.tags(),
+            NodeRef::Element(e) => e.tags(),
+            NodeRef::PackedElement(_) => None,
+        }
+    }
+
+    // Path segment naming this node: its label, or its ordinal when unlabeled.
+    fn segment(&self) -> String {
+        match self.label() {
+            Some(label) => label.to_string(),
+            None => self.ordinal().to_string(),
+        }
+    }
+}
+
+impl<'a, A: Address> NodeMut<'a, A> {
+    fn tags_mut(self) -> &'a mut Option<Box<NodeTags>> {
+        match self {
+            NodeMut::File(f) => f.tags_mut(),
+            NodeMut::Slice(s) => s.tags_mut(),
+            NodeMut::Command(c) => c.tags_mut(),
+            NodeMut::Element(e) => e.tags_mut(),
+        }
+    }
+}
+
+impl<A: Address> File<A> {
+    /// Coordinates of the first node labeled `label`, in document order.
+    pub fn find_by_label(&self, label: &str) -> Option<Coordinates> {
+        let mut found = None;
+        walk(&NodeRef::File(self), &mut Vec::new(), &mut |node, path| {
+            if node.label() == Some(label) {
+                found = Some(Coordinates::from_ordinals(path));
+                return false;
+            }
+            true
+        });
+        found
+    }
+
+    /// Coordinates of every node labeled `label`, in document order.
+    pub fn find_all_by_label(&self, label: &str) -> Vec<Coordinates> {
+        let mut found = Vec::new();
+        walk(&NodeRef::File(self), &mut Vec::new(), &mut |node, path| {
+            if node.label() == Some(label) {
+                found.push(Coordinates::from_ordinals(path));
+            }
+            true
+        });
+        found
+    }
+
+    /// Human-readable path of the first node labeled `label`, such as
+    /// `x86_64/__TEXT/__text`.
+    pub fn path_of_label(&self, label: &str) -> Option<String> {
+        self.label_path(&self.find_by_label(label)?)
+    }
+
+    /// Human-readable path of the node at `coords`: the labels from the
+    /// outermost slice down, with the ordinal standing in for unlabeled nodes.
+    /// The file itself has an empty path. The path inside a mounted file
+    /// follows a `!`, as in `member.o!__TEXT/__text`.
+    pub fn label_path(&self, coords: &Coordinates) -> Option<String> {
+        let mut node = NodeRef::File(self);
+        let mut segments = Vec::new();
+        for ordinal in coords.ordinals() {
+            node = node.child(ordinal)?;
+            segments.push(node.segment());
+        }
+        let path = segments.join("/");
+        match coords.inner() {
+            Some(inner) => Some(format!("{}!{}", path, node.mounted()?.label_path(inner)?)),
+            None => Some(path),
+        }
+    }
+
+    /// Resolve a path produced by [`File::label_path`] back to coordinates.
+    ///
+    /// Each segment matches a child by label or, failing that, by ordinal.
+    /// When labels repeat, the first branch in address order leading to a
+    /// complete match wins.
+    ///
+    /// ```rust
+    /// use janus_array::{Commands, DiskOffsets, File, Labeled, Slices};
+    ///
+    /// let mut text = Commands::default();
+    /// text.populate_values(0x1000, 0x2000, 0x1000, 0x2000, 0);
+    /// text.set_label("__text");
+    /// let mut segment = Slices::default();
+    /// segment.populate_values(0, 0x4000, 0, 0x4000, 0);
+    /// segment.set_label("__TEXT");
+    /// segment.add_command(text);
+    /// let mut file = File::with_size(0x4000);
+    /// file.add_slice(segment);
+    ///
+    /// assert_eq!(file.find_address_path(0x1800).unwrap(), "__TEXT/__text");
+    /// let coords = file.resolve_label_path("__TEXT/__text").unwrap();
+    /// assert_eq!((coords.slice, coords.command), (Some(0), Some(0)));
+    /// assert_eq!(file.resolve_label_path("__TEXT/0").unwrap().command, Some(0));
+    /// ```
+    pub fn resolve_label_path(&self, path: &str) -> Option<Coordinates> {
+        if let Some((outer, inner)) = path.split_once('!') {
+            let coords = self.resolve_label_path(outer)?;
+            let inner = self.resolve(&coords).ok()?.mounted()?.resolve_label_path(inner)?;
+            return Some(coords.with_inner(inner));
+        }
+        let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+        let mut ordinals = Vec::new();
+        resolve(&NodeRef::File(self), &segments, &mut ordinals).then(|| Coordinates::from_ordinals(&ordinals))
+    }
+
+    /// Label the node at `coords`, e.g. after building the layout with
+    /// [`crate::builder::LayoutBuilder`]. Returns false if there is no such node.
+    pub fn set_label_at(&mut self, coords: &Coordinates, label: impl Into<Arc<str>>) -> bool {
+        let label = label.into();
+        self.update_tags_at(coords, |tags| tags.get_or_insert_with(Default::default).label = Some(label))
+    }
+
+    /// Set the kind of the node at `coords`. Returns false if there is no such node.
+    pub fn set_kind_at(&mut self, coords: &Coordinates, kind: impl Into<Arc<str>>) -> bool {
+        let kind = kind.into();
+        self.update_tags_at(coords, |tags| tags.get_or_insert_with(Default::default).kind = Some(kind))
+    }
+
+    fn update_tags_at(&mut self, coords: &Coordinates, f: impl FnOnce(&mut Option<Box<NodeTags>>)) -> bool {
+        match self.node_mut_at(coords) {
+            Some(node) => {
+                f(node.tags_mut());
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Same as [`DiskOffsets::find_address`], returning the human-readable
+    /// path of the deepest node found.
+    pub fn find_address_path(&self, address: A) -> Result<String, OffsetLayoutsError<A>> {
+        let coords = self.find_address(address)?;
+        self.label_path(&coords).ok_or(OffsetLayoutsError::InconsistentSearch)
+    }
+}
+
+// Pre-order walk below `node`; stops as soon as `visit` returns false.
+fn walk<'a, A: Address>(
+    node: &NodeRef<'a, A>,
+    path: &mut Vec<usize>,
+    visit: &mut impl FnMut(&NodeRef<'a, A>, &[usize]) -> bool,
+) -> bool {
+    for child in node.children() {
+        path.push(child.ordinal());
+        let go_on = visit(&child, path) && walk(&child, path, visit);
+        path.pop();
+        if !go_on {
+            return false;
+        }
+    }
+    true
+}
+
+fn resolve<A: Address>(node: &NodeRef<'_, A>, segments: &[&str], ordinals: &mut Vec<usize>) -> bool {
+    let Some((first, rest)) = segments.split_first() else {
+        return true;
+    };
+    let children = node.children();
+    let by_label = children.iter().filter(|c| c.label() == Some(*first));
+    let by_ordinal = first
+        .parse::<usize>()
+        .ok()
+        .and_then(|o| children.iter().find(|c| c.ordinal() == o));
+    for child in by_label.chain(by_ordinal) {
+        ordinals.push(child.ordinal());
+        if resolve(child, rest, ordinals) {
+            return true;
+        }
+        ordinals.pop();
+    }
+    false
+}
@@ -11,7 +11,7 @@
 //! When parsing binary file formats with nested structures (executable load commands, 
 //! filesystem metadata, protocol packets), you typically need two access patterns:
 //!
-//! 1. **Direct navigation**: `file.slices[i].commands[j].elements[k]` - should be O(1)
+//! 1. **Direct navigation**: `file.slices()[i].commands()[j].elements()[k]` - should be O(1)
 //! 2. **Reverse lookup**: "What structure contains byte offset 0x47382?" - often O(n)
 //!
 //! Most solutions optimize for one pattern at the expense of the other, or maintain 
@@ -39,13 +39,13 @@
 //! slice.populate_values(0, 512, 0, 512, 0);
 //!
 //! let mut command = Commands::default();
-//! command.populate_values(100, 200, 100, 100, 0);
+//! command.populate_values(100, 200, 100, 200, 0);
 //!
 //! let mut element = CommandElements::default();
-//! element.populate_values(150, 180, 50, 30, 0);
+//! element.populate_values(150, 180, 50, 80, 0);
 //!
 //! // Direct access - O(1) when you know the path
-//! // let data = &file.slices[0].commands[0].elements[0];
+//! // let data = &file.slices()[0].commands()[0].elements()[0];
 //!
 //! // Reverse lookup - O(log n) to find structure containing an offset
 //! match file.find_address(175) {
@@ -70,17 +70,64 @@
 //!
 //! All levels implement the [`DiskOffsets`] trait, enabling recursive search:
 //!
-//! ```rust
-//! use janus_array::{DiskOffsets, OffsetLayoutsError, Coordinates};
-//! use std::ops::Range;
+//! ```ignore
+//! use janus_array::{AddressRange, DiskOffsets, OffsetLayoutsError, Coordinates};
 //!
 //! pub trait DiskOffsets {
-//!     fn find_address(&mut self, addr: u64) -> Result<Coordinates, OffsetLayoutsError>;
-//!     fn get_absolute_range(&self) -> Range<u64>;
+//!     fn find_address(&self, addr: u64) -> Result<Coordinates, OffsetLayoutsError>;
+//!     fn get_absolute_span(&self) -> AddressRange;
 //!     // ... other methods
 //! }
 //! ```
 //!
+//! Ranges are [`AddressRange`]s, which unlike `Range<u64>` can include the
+//! last address `u64::MAX`. Constructors taking an offset and a size fail with
+//! [`OffsetLayoutsError::AddressOverflow`] instead of wrapping around.
+//!
+//! ## Address types
+//!
+//! Nodes and [`DiskOffsets`] are generic over an [`Address`] type, `u64` by
+//! default: `File` is `File<u64>`. `u32` halves the size of every node for
+//! 32-bit targets, and [`Segmented`] handles `(segment, offset)` addresses.
+//! Layouts of other address types are built with the `with_span(s)`
+//! constructors; the builder, editing and tooling modules work on `u64`
+//! layouts.
+//!
+//! ```rust
+//! use janus_array::{AddressRange, Commands, DiskOffsets, File, Slices};
+//!
+//! let range = AddressRange::new(0u32, 0x100).unwrap();
+//! let mut slice = Slices::with_spans(range, range, 0);
+//! slice.add_command(Commands::with_spans(range, range, 0));
+//! let mut file = File::with_span(range);
+//! file.add_slice(slice);
+//! assert_eq!(file.find_address(0x80u32).unwrap().command, Some(0));
+//! ```
+//!
+//! ## Concurrency
+//!
+//! Lookups take `&self` and every level keeps its children sorted as they are
+//! added, so a built [`File`] is `Send + Sync` and can be queried from many
+//! threads at once without locking. [`File::find_addresses_parallel`] spreads a
+//! batch of lookups over scoped worker threads.
+//!
+//! Layouts that keep changing while being queried can live in a
+//! [`versioned::VersionedFile`]: readers take immutable snapshots while writers
+//! publish new versions that share every untouched subtree with the old ones.
+//!
+//! ## C ABI
+//!
+//! With the `ffi` feature, the `ffi` module exposes layouts to C and C++
+//! through an opaque handle, with the matching header in
+//! `include/janus_array.h`.
+//!
+//! ## `no_std`
+//!
+//! The crate builds under `no_std` with `alloc` when the default `std`
+//! feature is disabled. Lookups, building, editing, diffing and exporting are
+//! all available; the [`explorer`], [`versioned`] and the threaded batch
+//! lookups need `std`.
+//!
 //! ## Use Cases
 //!
 //! - Binary file format parsers (Mach-O, ELF, PE)
@@ -89,13 +136,37 @@
 //! - Debugging tools for address-to-symbol resolution
 //! - Any scenario requiring both hierarchical and offset-based access
 
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
 pub mod file;
 pub mod slices;
 pub mod commands;
 pub mod cmd_elements;
 pub mod disk_offsets;
+pub mod address;
+pub mod address_range;
 pub mod coordinates;
 pub mod types;
+pub mod labels;
+pub mod lookup;
+pub mod mount;
+pub mod packed;
+#[cfg(feature = "std")]
+pub mod explorer;
+pub mod export;
+pub mod diff;
+pub mod edit;
+pub mod batch;
+pub mod coverage;
+pub mod stats;
+pub mod workspace;
+#[cfg(feature = "std")]
+pub mod versioned;
+pub mod builder;
+#[cfg(feature = "ffi")]
+pub mod ffi;
 
 // Main public exports
 pub use file::File;
@@ -103,8 +174,16 @@ pub use slices::Slices;
 pub use commands::Commands;
 pub use cmd_elements::CommandElements;
 pub use disk_offsets::{DiskOffsets, OffsetLayoutsError};
-pub use coordinates::Coordinates;
-pub use types::ChildrenArray;
+pub use address::{Address, Segmented};
+pub use address_range::AddressRange;
+pub use coordinates::{Coordinates, ParseCoordinatesError};
+pub use types::{ChildrenArray, NodeLevel, NodeRef};
+pub use labels::{Labeled, NodeTags};
+pub use lookup::{Lookup, LookupPolicy, Termination};
+pub use mount::Mountable;
+pub use packed::PackedElement;
+pub use coverage::Coverage;
+pub use stats::{LayoutStats, LevelStats, SizeHistogram};
 
 /// Convenience module for importing commonly used types
 ///
@@ -114,6 +193,7 @@ pub use types::ChildrenArray;
 pub mod prelude {
     pub use crate::{
         File, Slices, Commands, CommandElements,
-        DiskOffsets, OffsetLayoutsError, Coordinates, ChildrenArray
+        DiskOffsets, OffsetLayoutsError, Address, AddressRange, Coordinates, ChildrenArray,
+        NodeLevel, NodeRef, Labeled, Mountable
     };
 }
\ No newline at end of file
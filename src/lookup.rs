@@ -0,0 +1,94 @@
+use core::fmt;
+
+use crate::coordinates::Coordinates;
+use crate::types::NodeLevel;
+
+/// Why a lookup stopped at the node it reports.
+///
+/// Ordinals name the siblings around the address, so tooling can report
+/// unclaimed bytes as "between `__text` and `__stubs`".
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Termination {
+    /// The node has no children: nothing finer to find.
+    Leaf,
+    /// The address lies between two children.
+    Gap { previous: usize, next: usize },
+    /// The address lies before the first child.
+    BeforeFirst { next: usize },
+    /// The address lies after the last child.
+    AfterLast { previous: usize },
+}
+
+impl fmt::Display for Termination {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Termination::Leaf => write!(f, "leaf"),
+            Termination::Gap { previous, next } => write!(f, "gap between {} and {}", previous, next),
+            Termination::BeforeFirst { next } => write!(f, "before {}", next),
+            Termination::AfterLast { previous } => write!(f, "after {}", previous),
+        }
+    }
+}
+
+/// How lookups treat a file, slice or command without children.
+///
+/// Elements have no children by definition and are leaves under both
+/// policies.
+///
+/// ```rust
+/// use janus_array::{DiskOffsets, File, LookupPolicy, Slices};
+///
+/// let mut file = File::with_size(0x100);
+/// let mut slice = Slices::default();
+/// slice.populate_values(0, 0x100, 0, 0x100, 0);
+/// file.add_slice(slice);
+///
+/// assert_eq!(file.find_address(0x10).unwrap().slice, Some(0));
+/// assert!(file.find_address_with(0x10, LookupPolicy::Strict).is_err());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum LookupPolicy {
+    /// A childless node is a leaf: the lookup stops there. The file is the
+    /// exception for [`crate::File::find_address_with`], which has no
+    /// coordinates to give for an address outside every slice and reports
+    /// [`crate::OffsetLayoutsError::NotFound`] even when the file has no
+    /// slices; [`crate::File::locate_with`] still stops at the file.
+    #[default]
+    Lenient,
+    /// A childless node is an error
+    /// ([`crate::OffsetLayoutsError::InconsistentStructure`]), for layouts
+    /// where every node is expected to be fully described.
+    Strict,
+}
+
+/// Result of a detailed lookup: the deepest node containing the address,
+/// its level, and why the search went no deeper.
+///
+/// ```rust
+/// use janus_array::{Commands, DiskOffsets, CommandElements, NodeLevel, Termination};
+///
+/// let mut command = Commands::default();
+/// command.populate_values(0, 0x100, 0, 0x100, 4);
+/// let mut element = CommandElements::default();
+/// element.populate_values(0x10, 0x20, 0x10, 0x20, 0);
+/// command.add_element(element);
+///
+/// let lookup = command.locate(0x80).unwrap();
+/// assert_eq!(lookup.level, NodeLevel::Command);
+/// assert_eq!(lookup.termination, Termination::AfterLast { previous: 0 });
+/// assert!(command.locate(0x18).unwrap().is_leaf());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Lookup {
+    pub coordinates: Coordinates,
+    pub level: NodeLevel,
+    pub termination: Termination,
+}
+
+impl Lookup {
+    /// Whether the address belongs to a node without children, i.e. no
+    /// bytes were left unclaimed at the level where the search stopped.
+    pub fn is_leaf(&self) -> bool {
+        self.termination == Termination::Leaf
+    }
+}
@@ -0,0 +1,120 @@
+use alloc::sync::Arc;
+
+use crate::address::Address;
+use crate::coordinates::Coordinates;
+use crate::disk_offsets::{DiskOffsets, OffsetLayoutsError};
+use crate::file::File;
+use crate::lookup::{Lookup, LookupPolicy};
+use crate::types::{NodeMut, NodeRef};
+
+/// A complete [`File`] layout embedded in a node: an archive member, a fat
+/// binary slice, a disk partition.
+///
+/// The mounted file keeps its own address space; an address `n` bytes into
+/// the node is looked up `n` bytes into the mounted file. Lookups descend into
+/// the mount only when the node has no children of its own, and return
+/// composite coordinates whose [`Coordinates::inner`] part is the path in the
+/// mounted file. Bytes of the node past the end of the mounted file stay
+/// with the node.
+///
+/// ```rust
+/// use janus_array::{Commands, DiskOffsets, File, Mountable, Slices};
+///
+/// // An archive member: its own layout, starting at 0
+/// let mut text = Slices::default();
+/// text.populate_values(0x0, 0x40, 0x0, 0x40, 0);
+/// let mut member = File::with_size(0x80);
+/// member.add_slice(text);
+///
+/// // Mounted on the command holding its bytes in the archive
+/// let mut entry = Commands::default();
+/// entry.populate_values(0x100, 0x180, 0x100, 0x180, 2);
+/// entry.mount(member);
+/// let mut archive_slice = Slices::default();
+/// archive_slice.populate_values(0x0, 0x1000, 0x0, 0x1000, 0);
+/// archive_slice.add_command(entry);
+/// let mut archive = File::with_size(0x1000);
+/// archive.add_slice(archive_slice);
+///
+/// let coords = archive.find_address(0x110).unwrap();
+/// assert_eq!(coords.to_string(), "0/2!0");
+/// assert_eq!(archive.resolve(&coords).unwrap().absolute_range(), 0x0..0x40);
+/// ```
+pub trait Mountable<A: Address = u64> {
+    // Functions to implement:
+    fn mount_point(&self) -> Option<&Arc<File<A>>>;
+    fn mount_point_mut(&mut self) -> &mut Option<Arc<File<A>>>;
+    //
+
+    fn mounted(&self) -> Option<&File<A>> {
+        self.mount_point().map(|file| &**file)
+    }
+
+    /// Mount `file`, replacing and returning the previous one. An `Arc` can
+    /// be passed to share one layout between several nodes.
+    fn mount(&mut self, file: impl Into<Arc<File<A>>>) -> Option<Arc<File<A>>> {
+        self.mount_point_mut().replace(file.into())
+    }
+
+    fn unmount(&mut self) -> Option<Arc<File<A>>> {
+        self.mount_point_mut().take()
+    }
+}
+
+impl<'a, A: Address> NodeRef<'a, A> {
+    /// The file mounted on this node, if any. The root file can't have one.
+    pub fn mounted(&self) -> Option<&'a File<A>> {
+        match *self {
+            NodeRef::File(_) => None,
+            NodeRef::Slice(s) => s.mounted(),
+            NodeRef::Command(c) => c.mounted(),
+            NodeRef::Element(e) => e.mounted(),
+            NodeRef::PackedElement(_) => None,
+        }
+    }
+}
+
+impl<'a, A: Address> NodeMut<'a, A> {
+    pub(crate) fn mount_point_mut(self) -> Option<&'a mut Option<Arc<File<A>>>> {
+        match self {
+            NodeMut::File(_) => None,
+            NodeMut::Slice(s) => Some(s.mount_point_mut()),
+            NodeMut::Command(c) => Some(c.mount_point_mut()),
+            NodeMut::Element(e) => Some(e.mount_point_mut()),
+        }
+    }
+}
+
+impl<A: Address> File<A> {
+    /// Mount `file` on the node at `coords`, e.g. after building the outer
+    /// layout with [`crate::builder::LayoutBuilder`]. Returns false if there
+    /// is no such node or it is the root.
+    pub fn mount_at(&mut self, coords: &Coordinates, file: impl Into<Arc<File<A>>>) -> bool {
+        match self.node_mut_at(coords).and_then(NodeMut::mount_point_mut) {
+            Some(point) => {
+                *point = Some(file.into());
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Lookup continued in the file mounted on a childless `node`, with
+/// coordinates relative to `node`. `None` when there is nothing to descend
+/// into, or `address` is past the end of the mounted file.
+pub(crate) fn locate_mounted<A: Address, T: DiskOffsets<A> + Mountable<A>>(
+    node: &T,
+    address: A,
+    policy: LookupPolicy,
+) -> Option<Result<Lookup, OffsetLayoutsError<A>>> {
+    if node.has_children() {
+        return None;
+    }
+    let file = node.mounted()?;
+    let inner = node.absolute_to_relative(address).and_then(|relative| file.relative_to_absolute(relative)).ok()?;
+    Some(file.locate_with(inner, policy).map(|lookup| Lookup {
+        coordinates: Coordinates::new().with_inner(lookup.coordinates),
+        ..lookup
+    }))
+}
@@ -0,0 +1,219 @@
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+use crate::address::Address;
+use crate::address_range::AddressRange;
+use crate::cmd_elements::CommandElements;
+use crate::disk_offsets::{DiskOffsets, OffsetLayoutsError};
+use crate::file::File;
+use crate::labels::Labeled;
+use crate::lookup::Termination;
+use crate::mount::Mountable;
+
+/// Elements of a command stored as two columns of offsets from the command
+/// start when packed, 8 bytes an element instead of a whole
+/// [`CommandElements`].
+///
+/// Ordinals are implied by the position, so only elements numbered in
+/// address order qualify, with relative ranges matching their place in the
+/// command, no tags, no mount and offsets below 4 GiB.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct PackedElements {
+    // Kept apart from the command range, which can change on its own
+    base: u128,
+    starts: Vec<u32>,
+    ends: Vec<u32>,
+    // Highest end, so that a shift can be checked without a scan
+    last_end: u32,
+}
+
+impl PackedElements {
+    /// Columns for `elements` of a command starting at `base`, or `None` if
+    /// one of them doesn't qualify.
+    pub(crate) fn pack<A: Address>(base: A, elements: &[CommandElements<A>]) -> Option<Self> {
+        if elements.is_empty() {
+            return None;
+        }
+        let base = base.to_wide();
+        let mut packed = Self {
+            base,
+            starts: Vec::with_capacity(elements.len()),
+            ends: Vec::with_capacity(elements.len()),
+            last_end: 0,
+        };
+        for (index, element) in elements.iter().enumerate() {
+            let span = element.get_absolute_span();
+            let start = u32::try_from(span.start().to_wide().checked_sub(base)?).ok()?;
+            let end = u32::try_from(span.end_wide().checked_sub(base)?).ok()?;
+            let relative = element.get_relative_span();
+            let plain = element.get_ordinal() == index
+                && relative.start().to_wide() == u128::from(start)
+                && relative.end_wide() == u128::from(end)
+                && element.tags().is_none()
+                && element.mounted().is_none();
+            if !plain {
+                return None;
+            }
+            packed.starts.push(start);
+            packed.ends.push(end);
+            packed.last_end = packed.last_end.max(end);
+        }
+        Some(packed)
+    }
+
+    /// The elements as nodes, or `None` if one of them no longer fits in the
+    /// address space, which [`PackedElements::shift`] prevents.
+    pub(crate) fn unpack<A: Address>(&self) -> Option<Vec<CommandElements<A>>> {
+        (0..self.len()).map(|index| self.element(index)).collect()
+    }
+
+    /// Move every element by `delta` bytes. Fails, leaving the elements
+    /// where they are, if one of them would leave the address space.
+    pub(crate) fn shift<A: Address>(&mut self, delta: i128) -> Result<(), OffsetLayoutsError<A>> {
+        let base = i128::try_from(self.base).ok().and_then(|base| base.checked_add(delta));
+        let fits = |base: u128| AddressRange::<A>::from_bounds(base, base + u128::from(self.last_end)).is_some();
+        match base.and_then(|base| u128::try_from(base).ok()).filter(|&base| fits(base)) {
+            Some(base) => {
+                self.base = base;
+                Ok(())
+            }
+            None => {
+                let start = A::from_wide(self.base).unwrap_or(A::MAX);
+                let end = A::from_wide(self.base + u128::from(self.last_end)).unwrap_or(A::MAX);
+                Err(OffsetLayoutsError::AddressOverflow(start, start..end))
+            }
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.starts.len()
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.starts.is_empty()
+    }
+
+    /// The element at `index`, if there is one and it fits in the address
+    /// space of `A`.
+    pub(crate) fn element<A: Address>(&self, index: usize) -> Option<CommandElements<A>> {
+        let (start, end) = (u128::from(*self.starts.get(index)?), u128::from(self.ends[index]));
+        let relative = AddressRange::from_bounds(start, end)?;
+        let absolute = AddressRange::from_bounds(self.base + start, self.base + end)?;
+        Some(CommandElements::with_spans(absolute, relative, index))
+    }
+
+    /// Index of the element containing `address`, with the same rules as
+    /// [`crate::disk_offsets::locate_child`].
+    pub(crate) fn locate(&self, address: u128) -> Option<usize> {
+        let offset = address.checked_sub(self.base)?;
+        let after = self.after(offset);
+        let mut exact_empty = None;
+        for index in (0..after).rev() {
+            let (start, end) = (u128::from(self.starts[index]), u128::from(self.ends[index]));
+            if start == end {
+                if start == offset {
+                    exact_empty = Some(index);
+                }
+                continue;
+            }
+            if offset < end {
+                return Some(index);
+            }
+            break;
+        }
+        exact_empty
+    }
+
+    /// Why no element contains `address`, when [`PackedElements::locate`]
+    /// found none.
+    pub(crate) fn termination(&self, address: u128) -> Termination {
+        let after = address.checked_sub(self.base).map_or(0, |offset| self.after(offset));
+        match (after.checked_sub(1), (after < self.len()).then_some(after)) {
+            (None, None) => Termination::Leaf,
+            (None, Some(next)) => Termination::BeforeFirst { next },
+            (Some(previous), None) => Termination::AfterLast { previous },
+            (Some(previous), Some(next)) => Termination::Gap { previous, next },
+        }
+    }
+
+    fn after(&self, offset: u128) -> usize {
+        self.starts.partition_point(|&start| u128::from(start) <= offset)
+    }
+}
+
+/// An element of a packed command, as [`crate::NodeRef::PackedElement`]
+/// hands it out. Its ordinal is its index in the command.
+#[derive(Debug, Clone, Copy)]
+pub struct PackedElement<A = u64> {
+    pub(crate) index: usize,
+    pub(crate) absolute: AddressRange<A>,
+    pub(crate) relative: AddressRange<A>,
+}
+
+impl<A: Address> PackedElement<A> {
+    pub(crate) fn new(columns: &PackedElements, index: usize) -> Option<Self> {
+        let element = columns.element(index)?;
+        Some(Self { index, absolute: element.get_absolute_span(), relative: element.get_relative_span() })
+    }
+
+    pub fn ordinal(&self) -> usize {
+        self.index
+    }
+
+    pub fn get_absolute_span(&self) -> AddressRange<A> {
+        self.absolute
+    }
+
+    pub fn get_relative_span(&self) -> AddressRange<A> {
+        self.relative
+    }
+
+    /// The element as a standalone node, e.g. for its [`DiskOffsets`]
+    /// conversions.
+    pub fn to_element(&self) -> CommandElements<A> {
+        CommandElements::with_spans(self.absolute, self.relative, self.index)
+    }
+}
+
+impl<A: Address> File<A> {
+    /// Pack the elements of every command that allows it, see
+    /// [`crate::Commands::pack_elements`]. Files mounted on nodes are left
+    /// alone. Returns how many commands are packed.
+    ///
+    /// ```rust
+    /// use janus_array::builder::LayoutBuilder;
+    /// use janus_array::{DiskOffsets, NodeLevel};
+    ///
+    /// let mut builder = LayoutBuilder::new(0x1000);
+    /// builder.push(NodeLevel::Slice, 0x0..0x1000, ()).unwrap();
+    /// builder.push(NodeLevel::Command, 0x100..0x200, ()).unwrap();
+    /// for i in 0..16 {
+    ///     builder.push(NodeLevel::Element, 0x100 + i * 0x10..0x108 + i * 0x10, ()).unwrap();
+    /// }
+    /// let mut file = builder.finish().file;
+    ///
+    /// assert_eq!(file.pack_elements(), 1);
+    /// let coords = file.find_address(0x134).unwrap();
+    /// assert_eq!(coords.to_string(), "0/0/3");
+    /// assert_eq!(file.resolve(&coords).unwrap().absolute_range(), 0x130..0x138);
+    /// ```
+    pub fn pack_elements(&mut self) -> usize {
+        let mut packed = 0;
+        for slice in self.slices.iter_mut().flat_map(|s| Arc::make_mut(s).iter_mut()) {
+            for command in slice.commands.iter_mut().flat_map(|c| Arc::make_mut(c).iter_mut()) {
+                packed += usize::from(command.pack_elements());
+            }
+        }
+        packed
+    }
+
+    /// Turn every packed command back into [`CommandElements`].
+    pub fn unpack_elements(&mut self) {
+        for slice in self.slices.iter_mut().flat_map(|s| Arc::make_mut(s).iter_mut()) {
+            for command in slice.commands.iter_mut().flat_map(|c| Arc::make_mut(c).iter_mut()) {
+                command.unpack_elements();
+            }
+        }
+    }
+}
+
@@ -1,125 +1,155 @@
-use std::ops::Range;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use crate::address::Address;
+use crate::address_range::AddressRange;
+use alloc::sync::Arc;
 
 use crate::commands::Commands;
-use crate::types::ChildrenArray;
+use crate::types::{ChildrenArray, NodeLevel};
 use crate::disk_offsets::OffsetLayoutsError;
 use crate::coordinates::Coordinates;
-use crate::disk_offsets::DiskOffsets;
+use crate::lookup::{Lookup, LookupPolicy};
+use crate::file::File;
+use crate::mount::{locate_mounted, Mountable};
+use crate::labels::{Labeled, NodeTags};
+use crate::disk_offsets::{child_error, insert_sorted, locate_child, order_key, termination, update_sorted, DiskOffsets};
 
 #[derive(Clone,Debug)]
-pub struct Slices{
-    pub start_abs_address: u64,
-    pub end_abs_address: u64,
-    pub start_rel_address: u64,
-    pub end_rel_address: u64,
+pub struct Slices<A = u64>{
+    absolute_range:AddressRange<A>,
+    relative_range:AddressRange<A>,
 
-    pub absolute_range:Range<u64>,
-    pub relative_range:Range<u64>,
+    my_ordinal:usize,
 
-    pub my_ordinal:usize,
+    // Kept sorted by start address; shared copy-on-write between clones
+    pub(crate) commands:Option<Arc<Vec<Commands<A>>>>,
 
-    pub commands:Option<Vec<Commands>>
-}
+    tags:Option<Box<NodeTags>>,
 
+    mount:Option<Arc<File<A>>>,
+}
 
-impl Default for Slices{
-    fn default () -> Self {
-        let empty_range: Range<u64> = 0..0;
-        Self { 
-            start_abs_address: 0, 
-            end_abs_address: 0, 
-            start_rel_address: 0, 
-            end_rel_address: 0, 
-            absolute_range: empty_range.clone(), 
-            relative_range: empty_range, 
-            my_ordinal: 0,
-            commands: None,
-        }
+impl Slices{
+    pub fn new() -> Self {
+        Self::default()
     }
 }
 
-impl Slices{
-    fn new() -> Self {
-        let empty_range: Range<u64> = 0..0;
-        Self { 
-            start_abs_address: 0, 
-            end_abs_address: 0, 
-            start_rel_address: 0, 
-            end_rel_address: 0, 
-            absolute_range: empty_range.clone(), 
-            relative_range: empty_range, 
-            my_ordinal: 0,
-            commands: None,
-        }
+impl Default for Slices {
+    fn default() -> Self {
+        Self::with_spans(AddressRange::default(), AddressRange::default(), 0)
     }
 }
 
-impl DiskOffsets for Slices {
-    fn set_start_abs_address(&mut self, start_abs:u64){
-        self.start_abs_address = start_abs; 
+impl<A: Address> Slices<A>{
+    /// Slice with the given ranges and no commands, for any address type
+    pub fn with_spans(absolute: AddressRange<A>, relative: AddressRange<A>, ordinal: usize) -> Self {
+        Self { absolute_range: absolute, relative_range: relative, my_ordinal: ordinal, commands: None, tags: None, mount: None }
     }
 
-    fn set_start_rel_address(&mut self, start_rel:u64){
-        self.start_rel_address = start_rel; 
+    /// Commands of the slice, sorted by start address
+    pub fn commands(&self) -> &[Commands<A>] {
+        self.commands.as_deref().map(Vec::as_slice).unwrap_or(&[])
     }
 
-    fn set_end_abs_address(&mut self, end_abs:u64){
-        self.end_abs_address = end_abs; 
+    /// Add a command to the slice, keeping commands sorted by start address
+    pub fn add_command(&mut self, command: Commands<A>) {
+        insert_sorted(Arc::make_mut(self.commands.get_or_insert_with(Default::default)), command);
     }
 
-    fn set_end_rel_address(&mut self, end_rel:u64){
-        self.end_rel_address = end_rel; 
+    /// Get the command whose ordinal is `ordinal`
+    pub fn get_command(&self, ordinal: usize) -> Option<&Commands<A>> {
+        self.commands().iter().find(|c| c.get_ordinal() == ordinal)
     }
 
-    fn set_absolute_range_explicit(&mut self, range:Range<u64>){
-        self.absolute_range=range;
+    /// Modify the command whose ordinal is `ordinal` in place, then restore the sort order
+    pub fn update_command<R>(&mut self, ordinal: usize, f: impl FnOnce(&mut Commands<A>) -> R) -> Option<R> {
+        update_sorted(self.commands.as_mut().map(Arc::make_mut)?, ordinal, f)
     }
 
-    fn set_absolute_range_implicit(&mut self){
-        self.absolute_range = self.start_abs_address..self.end_abs_address;
+    /// Remove and return the command whose ordinal is `ordinal`
+    pub fn remove_command(&mut self, ordinal: usize) -> Option<Commands<A>> {
+        let commands = self.commands.as_mut().map(Arc::make_mut)?;
+        let index = commands.iter().position(|c| c.get_ordinal() == ordinal)?;
+        Some(commands.remove(index))
     }
 
-    fn set_relative_range_explicit(&mut self, range:Range<u64>){
-        self.relative_range=range;
+    /// Like [`DiskOffsets::find_address`], also telling where the search
+    /// stopped: in a command (or one of its elements), or in the slice itself
+    /// and why.
+    pub fn locate(&self, address: A) -> Result<Lookup, OffsetLayoutsError<A>> {
+        self.locate_with(address, LookupPolicy::default())
     }
 
-    fn set_relative_range_implicit(&mut self){
-        self.relative_range = self.start_rel_address..self.end_rel_address;
+    /// [`Slices::locate`] under an explicit [`LookupPolicy`].
+    pub fn locate_with(&self, address: A, policy: LookupPolicy) -> Result<Lookup, OffsetLayoutsError<A>> {
+        let absolute_range = self.get_absolute_range();
+        if !self.contains_absolute_address(address) {
+            return Err(OffsetLayoutsError::AddressOutsideCurrentScope(address, absolute_range));
+        }
+        if policy == LookupPolicy::Strict && !self.has_children() && self.mounted().is_none() {
+            return Err(OffsetLayoutsError::InconsistentStructure(address, absolute_range));
+        }
+        let commands = self.commands();
+        let mut lookup = match locate_child(commands, address) {
+            Some(index) => commands[index]
+                .locate_with(address, policy)
+                .map_err(|e| child_error(e, OffsetLayoutsError::SliceIsBroken))?,
+            None => match locate_mounted(self, address, policy) {
+                Some(lookup) => lookup?,
+                None => Lookup {
+                    coordinates: Coordinates::new(),
+                    level: NodeLevel::Slice,
+                    termination: termination(commands, address),
+                },
+            },
+        };
+        lookup.coordinates.set_slice(self.my_ordinal);
+        Ok(lookup)
+    }
+
+    /// [`DiskOffsets::find_address`] under an explicit [`LookupPolicy`].
+    pub fn find_address_with(&self, address: A, policy: LookupPolicy) -> Result<Coordinates, OffsetLayoutsError<A>> {
+        self.locate_with(address, policy).map(|lookup| lookup.coordinates)
     }
+}
 
-    fn set_ordinal(&mut self, ordinal:usize){
-        self.my_ordinal=ordinal;
+impl<A: Address> DiskOffsets<A> for Slices<A> {
+    fn set_absolute_span(&mut self, span:AddressRange<A>){
+        self.absolute_range = span;
     }
 
-    fn get_absolute_range(&self)->Range<u64> {
-        self.absolute_range.clone()
+    fn set_relative_span(&mut self, span:AddressRange<A>){
+        self.relative_range = span;
     }
 
-    fn get_relative_range(&self)->Range<u64> {
-        self.relative_range.clone()
+    fn set_ordinal(&mut self, ordinal:usize){
+        self.my_ordinal=ordinal;
     }
 
-    fn get_max_abs_address(&self)->u64 {
-        self.end_abs_address
+    fn get_ordinal(&self)->usize {
+        self.my_ordinal
     }
 
-    fn get_min_abs_address(&self)->u64 {
-        self.start_abs_address
+    fn get_absolute_span(&self)->AddressRange<A> {
+        self.absolute_range
     }
 
+    fn get_relative_span(&self)->AddressRange<A> {
+        self.relative_range
+    }
 
     fn has_children(&self)->bool{
         match &self.commands {
-            Some(cmds) => cmds.len()>0,
+            Some(cmds) => !cmds.is_empty(),
             None =>false
         }
     }
 
-    fn get_children(&mut self)->Option<ChildrenArray> {
+    fn get_children(&self)->Option<ChildrenArray<A>> {
         
         if self.has_children() {
-            self.sort_children();
-            let children = self.commands.clone()?;
+            let children = self.commands.as_deref()?.clone();
             Some(ChildrenArray::Slice(children))
         } else {
             None
@@ -128,67 +158,33 @@ impl DiskOffsets for Slices {
     }
 
     fn sort_children(&mut self) {
-        if let Some(children) = &mut self.commands {
-            children.sort_by_key(|c| c.start_abs_address);
+        if let Some(children) = self.commands.as_mut().map(Arc::make_mut) {
+            children.sort_by_key(order_key);
         }
     }
 
-    fn find_address(&mut self, absolute_address:u64) -> Result<Coordinates, OffsetLayoutsError>{
-        let absolute_range = self.get_absolute_range();
-        if absolute_range.contains(&absolute_address){
-            let mut retval = Coordinates::new();
-            retval.set_slice(self.my_ordinal);
-            if self.has_children(){
-                let mut children = if let Some(ChildrenArray::Slice(v)) = self.get_children() {
-                    v
-                } else {
-                    return Err(OffsetLayoutsError::InconsistentSearch);
-                };
-
-                let mut start:usize = 0;
-                let mut end: usize = children.len() - 1 ;
-
-                while start <= end {
-                    let mid = (start + end) / 2;
-                    let range = children[mid].get_absolute_range();
-                    if range.contains(&absolute_address) {
-                        match children[mid].find_address(absolute_address) {
-                            Ok(coords) => {
-                                if let Some(c) = coords.command {
-                                    retval.set_command(c);
-                                } else {
-                                    return Err(OffsetLayoutsError::SliceIsBroken);
-                                }
-                                if let Some(e) = coords.element {
-                                    retval.set_element(e);
-                                }
-                            }
-                            Err(_) => return Err(OffsetLayoutsError::SliceIsBroken),
-                        }
-                        return Ok(retval);
-                    } else {
-                        let min = &children[mid].get_min_abs_address();
-                        let max = &children[mid].get_max_abs_address();
-                        let mid_range = &children[mid].get_absolute_range();
-                        if absolute_address < *min {
-                            if mid == 0 {
-                                break;
-                            }
-                            end = mid - 1;
-                        } else if absolute_address > *max {
-                            start = mid + 1;
-                        } else {
-                            return Err(OffsetLayoutsError::InconsistentStructure(absolute_address,mid_range.clone()));
-                        }
-                    }
-                }
-            } else {
-                return Err(OffsetLayoutsError::InconsistentStructure(absolute_address, absolute_range));
-            }
-            Ok(retval)
-        } else {
-            Err(OffsetLayoutsError::AddressOutsideCurrentScope(absolute_address,absolute_range))
-        }
+    fn find_address(&self, absolute_address:A) -> Result<Coordinates, OffsetLayoutsError<A>>{
+        self.locate(absolute_address).map(|lookup| lookup.coordinates)
     }
     
 }
+
+impl<A> Labeled for Slices<A> {
+    fn tags(&self) -> Option<&NodeTags> {
+        self.tags.as_deref()
+    }
+
+    fn tags_mut(&mut self) -> &mut Option<Box<NodeTags>> {
+        &mut self.tags
+    }
+}
+
+impl<A: Address> Mountable<A> for Slices<A> {
+    fn mount_point(&self) -> Option<&Arc<File<A>>> {
+        self.mount.as_ref()
+    }
+
+    fn mount_point_mut(&mut self) -> &mut Option<Arc<File<A>>> {
+        &mut self.mount
+    }
+}
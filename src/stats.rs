@@ -0,0 +1,258 @@
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::address::Address;
+use crate::address_range::AddressRange;
+use crate::coordinates::Coordinates;
+use crate::coverage::Coverage;
+use crate::file::File;
+use crate::types::{NodeLevel, NodeRef};
+
+/// Shape of a layout, level by level, for spotting anomalies such as an
+/// oversized command or a slice its commands barely cover.
+///
+/// Files mounted on nodes are not descended into.
+///
+/// ```rust
+/// use janus_array::{Commands, DiskOffsets, File, Slices};
+///
+/// let mut slice = Slices::default();
+/// slice.populate_values(0, 0x1000, 0, 0x1000, 0);
+/// for (i, (start, end)) in [(0x0, 0x20), (0x20, 0x40), (0x100, 0x900)].into_iter().enumerate() {
+///     let mut command = Commands::default();
+///     command.populate_values(start, end, start, end, i);
+///     slice.add_command(command);
+/// }
+/// let mut file = File::with_size(0x2000);
+/// file.add_slice(slice);
+///
+/// let stats = file.stats();
+/// assert_eq!(stats.commands.count, 3);
+/// assert_eq!(stats.commands.max_fan_out, 3);
+/// assert_eq!(stats.commands.average_size(), 0x2c0 as f64);
+/// let (coords, range) = stats.commands.largest.clone().unwrap();
+/// assert_eq!((coords.command, range.to_range()), (Some(2), 0x100..0x900));
+/// assert_eq!(stats.commands.parent_ratio(), 0x840 as f64 / 0x1000 as f64);
+/// assert_eq!(stats.coverage.covered, 0x840);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct LayoutStats<A = u64> {
+    pub slices: LevelStats<A>,
+    pub commands: LevelStats<A>,
+    pub elements: LevelStats<A>,
+    /// Bytes of the file owned by leaves, as [`File::coverage`] reports them.
+    pub coverage: Coverage,
+}
+
+/// Figures about the nodes of one level.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LevelStats<A = u64> {
+    pub count: usize,
+    /// Sum of the node sizes.
+    pub total_size: u128,
+    /// First node of the smallest size, in document order.
+    pub smallest: Option<(Coordinates, AddressRange<A>)>,
+    /// First node of the largest size, in document order.
+    pub largest: Option<(Coordinates, AddressRange<A>)>,
+    /// Bytes spanned by the nodes of the level above that have children;
+    /// childless ones are leaves and count towards [`LayoutStats::coverage`].
+    pub parent_size: u128,
+    /// Part of `parent_size` spanned by a node of this level.
+    pub parent_covered: u128,
+    /// Most nodes of this level under a single parent.
+    pub max_fan_out: usize,
+    pub histogram: SizeHistogram,
+}
+
+/// Node counts by size, in power of two buckets.
+///
+/// Bucket 0 counts empty nodes, bucket `i` sizes from `2^(i-1)` up to
+/// `2^i - 1`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct SizeHistogram {
+    buckets: Vec<usize>,
+}
+
+impl<A> Default for LevelStats<A> {
+    fn default() -> Self {
+        Self {
+            count: 0,
+            total_size: 0,
+            smallest: None,
+            largest: None,
+            parent_size: 0,
+            parent_covered: 0,
+            max_fan_out: 0,
+            histogram: SizeHistogram::default(),
+        }
+    }
+}
+
+impl<A> LayoutStats<A> {
+    /// Figures of a level below the file, `None` for [`NodeLevel::File`].
+    pub fn level(&self, level: NodeLevel) -> Option<&LevelStats<A>> {
+        match level {
+            NodeLevel::File => None,
+            NodeLevel::Slice => Some(&self.slices),
+            NodeLevel::Command => Some(&self.commands),
+            NodeLevel::Element => Some(&self.elements),
+        }
+    }
+
+    /// Number of nodes below the file.
+    pub fn node_count(&self) -> usize {
+        self.slices.count + self.commands.count + self.elements.count
+    }
+
+    /// Most children of a single node, at any level.
+    pub fn max_fan_out(&self) -> usize {
+        self.slices.max_fan_out.max(self.commands.max_fan_out).max(self.elements.max_fan_out)
+    }
+
+    fn level_mut(&mut self, level: NodeLevel) -> Option<&mut LevelStats<A>> {
+        match level {
+            NodeLevel::File => None,
+            NodeLevel::Slice => Some(&mut self.slices),
+            NodeLevel::Command => Some(&mut self.commands),
+            NodeLevel::Element => Some(&mut self.elements),
+        }
+    }
+}
+
+impl<A: Address> LevelStats<A> {
+    /// Mean node size, 0.0 for an empty level.
+    pub fn average_size(&self) -> f64 {
+        if self.count == 0 {
+            0.0
+        } else {
+            self.total_size as f64 / self.count as f64
+        }
+    }
+
+    /// Covered share of the parent ranges, from 0.0 to 1.0. Empty parents are
+    /// fully covered.
+    pub fn parent_ratio(&self) -> f64 {
+        if self.parent_size == 0 {
+            1.0
+        } else {
+            self.parent_covered as f64 / self.parent_size as f64
+        }
+    }
+
+    fn record(&mut self, coords: Coordinates, span: AddressRange<A>) {
+        let size = span.len();
+        self.count += 1;
+        self.total_size += size;
+        self.histogram.add(size);
+        if self.smallest.as_ref().map_or(true, |(_, s)| size < s.len()) {
+            self.smallest = Some((coords.clone(), span));
+        }
+        if self.largest.as_ref().map_or(true, |(_, l)| size > l.len()) {
+            self.largest = Some((coords, span));
+        }
+    }
+}
+
+impl SizeHistogram {
+    /// Count of every bucket, up to the last non-empty one.
+    pub fn buckets(&self) -> &[usize] {
+        &self.buckets
+    }
+
+    /// Smallest size counted in bucket `index`.
+    pub fn lower_bound(index: usize) -> u128 {
+        match index {
+            0 => 0,
+            i => 1u128 << (i - 1),
+        }
+    }
+
+    /// Non-empty buckets as `(lower bound, count)`.
+    pub fn iter(&self) -> impl Iterator<Item = (u128, usize)> + '_ {
+        self.buckets
+            .iter()
+            .enumerate()
+            .filter(|(_, &count)| count > 0)
+            .map(|(i, &count)| (Self::lower_bound(i), count))
+    }
+
+    fn add(&mut self, size: u128) {
+        let index = (u128::BITS - size.leading_zeros()) as usize;
+        if self.buckets.len() <= index {
+            self.buckets.resize(index + 1, 0);
+        }
+        self.buckets[index] += 1;
+    }
+}
+
+impl<A: Address> File<A> {
+    /// Counts, sizes, fan-out and coverage of every level of the layout.
+    pub fn stats(&self) -> LayoutStats<A> {
+        let mut stats = LayoutStats { coverage: self.coverage(), ..Default::default() };
+        collect(&NodeRef::File(self), &mut Vec::new(), &mut stats);
+        stats
+    }
+}
+
+fn collect<A: Address>(node: &NodeRef<'_, A>, path: &mut Vec<usize>, stats: &mut LayoutStats<A>) {
+    let children = node.children();
+    let Some(first) = children.first() else {
+        return;
+    };
+    let span = node.absolute_span();
+    let level = stats.level_mut(first.level()).expect("children are below the file");
+    level.parent_size += span.len();
+    level.max_fan_out = level.max_fan_out.max(children.len());
+
+    // Siblings are sorted by start, so one sweep measures their union
+    let mut cursor = span.start().to_wide();
+    for child in &children {
+        let child_span = child.absolute_span();
+        let start = child_span.start().to_wide().max(cursor);
+        let end = child_span.end_wide().min(span.end_wide());
+        if end > start {
+            level.parent_covered += end - start;
+            cursor = end;
+        }
+        path.push(child.ordinal());
+        level.record(Coordinates::from_ordinals(path), child_span);
+        path.pop();
+    }
+    for child in &children {
+        path.push(child.ordinal());
+        collect(child, path, stats);
+        path.pop();
+    }
+}
+
+impl<A: Address> fmt::Display for LayoutStats<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (name, level) in [("slices", &self.slices), ("commands", &self.commands), ("elements", &self.elements)] {
+            write!(f, "{:<9} {:>8} nodes", name, level.count)?;
+            if level.count > 0 {
+                write!(
+                    f,
+                    ", {:#x} bytes (avg {:#x}), {:.1}% of parents, fan-out {}",
+                    level.total_size,
+                    level.average_size() as u128,
+                    level.parent_ratio() * 100.0,
+                    level.max_fan_out
+                )?;
+            }
+            writeln!(f)?;
+            if let (Some((min_at, min)), Some((max_at, max))) = (&level.smallest, &level.largest) {
+                writeln!(f, "          smallest {} at {}, largest {} at {}", min, min_at, max, max_at)?;
+            }
+            for (lower, count) in level.histogram.iter() {
+                writeln!(f, "          >= {:#x}: {}", lower, count)?;
+            }
+        }
+        write!(
+            f,
+            "coverage  {:.1}%, {} gaps, largest {:#x}",
+            self.coverage.ratio() * 100.0,
+            self.coverage.gaps,
+            self.coverage.largest_gap
+        )
+    }
+}
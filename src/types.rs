@@ -1,10 +1,198 @@
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use crate::address::Address;
+use crate::address_range::AddressRange;
 use crate::cmd_elements::CommandElements;
 use crate::slices::Slices;
 use crate::commands::Commands;
+use crate::file::File;
+use crate::disk_offsets::{DiskOffsets, OffsetLayoutsError};
+use crate::packed::PackedElement;
 
 #[derive(Debug, Clone)]
-pub enum ChildrenArray {
-    File(Vec<Slices>),
-    Slice(Vec<Commands>),
-    Commands(Vec<CommandElements>),
+pub enum ChildrenArray<A = u64> {
+    File(Vec<Slices<A>>),
+    Slice(Vec<Commands<A>>),
+    Commands(Vec<CommandElements<A>>),
+}
+
+/// The four levels of the hierarchy, from the root down.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum NodeLevel {
+    File,
+    Slice,
+    Command,
+    Element,
+}
+
+impl NodeLevel {
+    pub fn name(&self) -> &'static str {
+        match self {
+            NodeLevel::File => "file",
+            NodeLevel::Slice => "slice",
+            NodeLevel::Command => "command",
+            NodeLevel::Element => "element",
+        }
+    }
+}
+
+/// A mutable reference to any node, for changes that leave ranges alone
+/// (tags, mounts) and so can't break the sibling order.
+pub(crate) enum NodeMut<'a, A = u64> {
+    File(&'a mut File<A>),
+    Slice(&'a mut Slices<A>),
+    Command(&'a mut Commands<A>),
+    Element(&'a mut CommandElements<A>),
+}
+
+impl<'a, A: Address> NodeMut<'a, A> {
+    pub(crate) fn child_mut(self, ordinal: usize) -> Option<NodeMut<'a, A>> {
+        match self {
+            NodeMut::File(f) => {
+                let slices = Arc::make_mut(f.slices.as_mut()?);
+                slices.iter_mut().find(|s| s.get_ordinal() == ordinal).map(NodeMut::Slice)
+            }
+            NodeMut::Slice(s) => {
+                let commands = Arc::make_mut(s.commands.as_mut()?);
+                commands.iter_mut().find(|c| c.get_ordinal() == ordinal).map(NodeMut::Command)
+            }
+            NodeMut::Command(c) => {
+                let elements = c.elements_mut()?;
+                elements.iter_mut().find(|e| e.get_ordinal() == ordinal).map(NodeMut::Element)
+            }
+            NodeMut::Element(_) => None,
+        }
+    }
+}
+
+/// A borrowed reference to any node of the hierarchy.
+///
+/// Used by the traversal helpers so that tooling can walk the tree without
+/// matching on every level by hand.
+#[derive(Debug, Clone, Copy)]
+pub enum NodeRef<'a, A = u64> {
+    File(&'a File<A>),
+    Slice(&'a Slices<A>),
+    Command(&'a Commands<A>),
+    Element(&'a CommandElements<A>),
+    /// An element of a command whose elements are packed.
+    PackedElement(PackedElement<A>),
+}
+
+impl<'a, A: Address> NodeRef<'a, A> {
+    pub fn level(&self) -> NodeLevel {
+        match self {
+            NodeRef::File(_) => NodeLevel::File,
+            NodeRef::Slice(_) => NodeLevel::Slice,
+            NodeRef::Command(_) => NodeLevel::Command,
+            NodeRef::Element(_) | NodeRef::PackedElement(_) => NodeLevel::Element,
+        }
+    }
+
+    pub fn ordinal(&self) -> usize {
+        match self {
+            NodeRef::File(f) => f.get_ordinal(),
+            NodeRef::Slice(s) => s.get_ordinal(),
+            NodeRef::Command(c) => c.get_ordinal(),
+            NodeRef::Element(e) => e.get_ordinal(),
+            NodeRef::PackedElement(e) => e.ordinal(),
+        }
+    }
+
+    pub fn absolute_range(&self) -> Range<A> {
+        match self {
+            NodeRef::File(f) => f.get_absolute_range(),
+            NodeRef::Slice(s) => s.get_absolute_range(),
+            NodeRef::Command(c) => c.get_absolute_range(),
+            NodeRef::Element(e) => e.get_absolute_range(),
+            NodeRef::PackedElement(e) => e.get_absolute_span().to_range(),
+        }
+    }
+
+    pub fn relative_range(&self) -> Range<A> {
+        match self {
+            NodeRef::File(f) => f.get_relative_range(),
+            NodeRef::Slice(s) => s.get_relative_range(),
+            NodeRef::Command(c) => c.get_relative_range(),
+            NodeRef::Element(e) => e.get_relative_range(),
+            NodeRef::PackedElement(e) => e.get_relative_span().to_range(),
+        }
+    }
+
+    /// Exact absolute range, including the last byte of the address space.
+    pub fn absolute_span(&self) -> AddressRange<A> {
+        match self {
+            NodeRef::File(f) => f.get_absolute_span(),
+            NodeRef::Slice(s) => s.get_absolute_span(),
+            NodeRef::Command(c) => c.get_absolute_span(),
+            NodeRef::Element(e) => e.get_absolute_span(),
+            NodeRef::PackedElement(e) => e.get_absolute_span(),
+        }
+    }
+
+    /// Exact relative range, including the last byte of the address space.
+    pub fn relative_span(&self) -> AddressRange<A> {
+        match self {
+            NodeRef::File(f) => f.get_relative_span(),
+            NodeRef::Slice(s) => s.get_relative_span(),
+            NodeRef::Command(c) => c.get_relative_span(),
+            NodeRef::Element(e) => e.get_relative_span(),
+            NodeRef::PackedElement(e) => e.get_relative_span(),
+        }
+    }
+
+    /// See [`DiskOffsets::relative_to_absolute`].
+    pub fn relative_to_absolute(&self, relative: A) -> Result<A, OffsetLayoutsError<A>> {
+        match self {
+            NodeRef::File(f) => f.relative_to_absolute(relative),
+            NodeRef::Slice(s) => s.relative_to_absolute(relative),
+            NodeRef::Command(c) => c.relative_to_absolute(relative),
+            NodeRef::Element(e) => e.relative_to_absolute(relative),
+            NodeRef::PackedElement(e) => e.to_element().relative_to_absolute(relative),
+        }
+    }
+
+    /// See [`DiskOffsets::absolute_to_relative`].
+    pub fn absolute_to_relative(&self, absolute: A) -> Result<A, OffsetLayoutsError<A>> {
+        match self {
+            NodeRef::File(f) => f.absolute_to_relative(absolute),
+            NodeRef::Slice(s) => s.absolute_to_relative(absolute),
+            NodeRef::Command(c) => c.absolute_to_relative(absolute),
+            NodeRef::Element(e) => e.absolute_to_relative(absolute),
+            NodeRef::PackedElement(e) => e.to_element().absolute_to_relative(absolute),
+        }
+    }
+
+    pub fn has_children(&self) -> bool {
+        match self {
+            NodeRef::File(f) => f.has_children(),
+            NodeRef::Slice(s) => s.has_children(),
+            NodeRef::Command(c) => c.has_children(),
+            NodeRef::Element(_) | NodeRef::PackedElement(_) => false,
+        }
+    }
+
+    /// Children of this node, in storage order.
+    pub fn children(&self) -> Vec<NodeRef<'a, A>> {
+        match *self {
+            NodeRef::File(f) => f.slices().iter().map(NodeRef::Slice).collect(),
+            NodeRef::Slice(s) => s.commands().iter().map(NodeRef::Command).collect(),
+            NodeRef::Command(c) if c.is_packed() => c.packed_elements().map(NodeRef::PackedElement).collect(),
+            NodeRef::Command(c) => c.elements().iter().map(NodeRef::Element).collect(),
+            NodeRef::Element(_) | NodeRef::PackedElement(_) => Vec::new(),
+        }
+    }
+
+    /// The child whose `my_ordinal` is `ordinal`, if any.
+    pub fn child(&self, ordinal: usize) -> Option<NodeRef<'a, A>> {
+        match *self {
+            NodeRef::File(f) => f.get_slice(ordinal).map(NodeRef::Slice),
+            NodeRef::Slice(s) => s.get_command(ordinal).map(NodeRef::Command),
+            NodeRef::Command(c) if c.is_packed() => c.packed_element(ordinal).map(NodeRef::PackedElement),
+            NodeRef::Command(c) => c.get_element(ordinal).map(NodeRef::Element),
+            NodeRef::Element(_) | NodeRef::PackedElement(_) => None,
+        }
+    }
 }
@@ -0,0 +1,91 @@
+use std::ops::Deref;
+use std::sync::{Arc, Mutex, RwLock};
+
+use crate::file::File;
+
+/// A layout that keeps changing while readers hold on to older versions.
+///
+/// Readers take a [`Snapshot`], which never changes afterwards. Writers edit a
+/// private copy of the latest version and publish it when done; since
+/// [`File`] children are copy-on-write, the copy shares every subtree the
+/// edit didn't touch with the versions still held by readers.
+///
+/// ```rust
+/// use janus_array::{File, Slices, DiskOffsets};
+/// use janus_array::versioned::VersionedFile;
+///
+/// let layout = VersionedFile::new(File::with_size(0x1000));
+/// let before = layout.snapshot();
+///
+/// layout.update(|file| {
+///     let mut slice = Slices::default();
+///     slice.populate_values(0, 0x800, 0, 0x800, 0);
+///     file.add_slice(slice);
+/// });
+///
+/// assert!(before.slices().is_empty());
+/// assert_eq!(layout.snapshot().slices().len(), 1);
+/// assert_eq!(layout.version(), before.version() + 1);
+/// ```
+#[derive(Debug)]
+pub struct VersionedFile {
+    current: RwLock<Snapshot>,
+    // Serializes writers so that concurrent updates can't drop each other
+    writer: Mutex<()>,
+}
+
+/// An immutable version of a [`VersionedFile`].
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    file: Arc<File>,
+    version: u64,
+}
+
+impl Snapshot {
+    /// Number of updates published before this version.
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+}
+
+impl Deref for Snapshot {
+    type Target = File;
+
+    fn deref(&self) -> &File {
+        &self.file
+    }
+}
+
+impl VersionedFile {
+    pub fn new(file: File) -> Self {
+        Self {
+            current: RwLock::new(Snapshot { file: Arc::new(file), version: 0 }),
+            writer: Mutex::new(()),
+        }
+    }
+
+    /// The latest published version.
+    pub fn snapshot(&self) -> Snapshot {
+        self.current.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Number of updates published so far.
+    pub fn version(&self) -> u64 {
+        self.snapshot().version
+    }
+
+    /// Apply `edit` to a copy of the latest version and publish the result.
+    ///
+    /// Readers are never blocked while `edit` runs; they keep seeing the
+    /// previous version until it returns.
+    pub fn update<R>(&self, edit: impl FnOnce(&mut File) -> R) -> R {
+        let _writer = self.writer.lock().unwrap_or_else(|e| e.into_inner());
+        let base = self.snapshot();
+        let mut next = File::clone(&base.file);
+        let result = edit(&mut next);
+
+        let mut current = self.current.write().unwrap_or_else(|e| e.into_inner());
+        *current = Snapshot { file: Arc::new(next), version: base.version + 1 };
+        result
+    }
+}
@@ -0,0 +1,191 @@
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::fmt;
+
+use crate::address::Address;
+use crate::coordinates::Coordinates;
+use crate::coverage::Coverage;
+use crate::disk_offsets::{DiskOffsets, OffsetLayoutsError};
+use crate::file::File;
+use crate::lookup::Lookup;
+
+/// A collection of independent layouts, e.g. every binary of a firmware
+/// bundle, keyed by an id or a path.
+///
+/// Files are kept in key order, which is the order of every result spanning
+/// several of them.
+///
+/// ```rust
+/// use janus_array::{DiskOffsets, File, Labeled, Slices};
+/// use janus_array::workspace::Workspace;
+///
+/// let mut slice = Slices::default();
+/// slice.populate_values(0, 0x100, 0, 0x100, 0);
+/// slice.set_label("__TEXT");
+/// let mut file = File::with_size(0x200);
+/// file.add_slice(slice);
+///
+/// let mut workspace = Workspace::new();
+/// workspace.insert("bin/init".to_string(), file);
+///
+/// assert_eq!(workspace.find_address("bin/init", 0x10).unwrap().slice, Some(0));
+/// assert!(workspace.find_address("bin/sh", 0x10).is_err());
+/// assert_eq!(workspace.find_by_label("__TEXT")[0].file, "bin/init");
+/// assert_eq!(workspace.coverage().covered, 0x100);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Workspace<K = String, A = u64> {
+    files: BTreeMap<K, File<A>>,
+}
+
+/// Error of a workspace query.
+#[derive(Debug, Clone)]
+pub enum WorkspaceError<A = u64> {
+    /// No file has the requested id.
+    UnknownFile,
+    /// The file exists, the lookup inside it failed.
+    Lookup(OffsetLayoutsError<A>),
+}
+
+impl<A: Address> fmt::Display for WorkspaceError<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WorkspaceError::UnknownFile => write!(f, "no such file in the workspace"),
+            WorkspaceError::Lookup(e) => write!(f, "lookup failed: {:?}", e),
+        }
+    }
+}
+
+/// A labeled node found by [`Workspace::find_by_label`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LabelMatch<'a, K> {
+    pub file: &'a K,
+    pub coordinates: Coordinates,
+    /// Label path inside the file, as [`File::label_path`] prints it.
+    pub path: String,
+}
+
+impl<K: Ord> Workspace<K> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<K: Ord> Default for Workspace<K> {
+    fn default() -> Self {
+        Self { files: BTreeMap::new() }
+    }
+}
+
+impl<K: Ord, A: Address> Workspace<K, A> {
+    /// Add a file, returning the one previously stored under `id`.
+    pub fn insert(&mut self, id: K, file: File<A>) -> Option<File<A>> {
+        self.files.insert(id, file)
+    }
+
+    pub fn remove<Q: Ord + ?Sized>(&mut self, id: &Q) -> Option<File<A>>
+    where
+        K: Borrow<Q>,
+    {
+        self.files.remove(id)
+    }
+
+    pub fn get<Q: Ord + ?Sized>(&self, id: &Q) -> Option<&File<A>>
+    where
+        K: Borrow<Q>,
+    {
+        self.files.get(id)
+    }
+
+    pub fn get_mut<Q: Ord + ?Sized>(&mut self, id: &Q) -> Option<&mut File<A>>
+    where
+        K: Borrow<Q>,
+    {
+        self.files.get_mut(id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// Files with their ids, in key order.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &File<A>)> {
+        self.files.iter()
+    }
+
+    /// Resolve a `(file_id, offset)` query.
+    pub fn find_address<Q: Ord + ?Sized>(&self, id: &Q, address: A) -> Result<Coordinates, WorkspaceError<A>>
+    where
+        K: Borrow<Q>,
+    {
+        self.file(id)?.find_address(address).map_err(WorkspaceError::Lookup)
+    }
+
+    /// Same as [`Workspace::find_address`], with [`File::locate`] details.
+    pub fn locate<Q: Ord + ?Sized>(&self, id: &Q, address: A) -> Result<Lookup, WorkspaceError<A>>
+    where
+        K: Borrow<Q>,
+    {
+        self.file(id)?.locate(address).map_err(WorkspaceError::Lookup)
+    }
+
+    /// Resolve a batch of `(file_id, offset)` queries, one result per query.
+    pub fn find_addresses<Q: Ord + ?Sized>(&self, queries: &[(&Q, A)]) -> Vec<Result<Coordinates, WorkspaceError<A>>>
+    where
+        K: Borrow<Q>,
+    {
+        queries.iter().map(|&(id, address)| self.find_address(id, address)).collect()
+    }
+
+    /// Every node labeled `label`, across all files: files in key order,
+    /// nodes in document order.
+    pub fn find_by_label(&self, label: &str) -> Vec<LabelMatch<'_, K>> {
+        let mut found = Vec::new();
+        for (id, file) in &self.files {
+            for coordinates in file.find_all_by_label(label) {
+                let path = file.label_path(&coordinates).unwrap_or_default();
+                found.push(LabelMatch { file: id, coordinates, path });
+            }
+        }
+        found
+    }
+
+    /// Coverage of every file, in key order.
+    pub fn coverage_by_file(&self) -> Vec<(&K, Coverage)> {
+        self.files.iter().map(|(id, file)| (id, file.coverage())).collect()
+    }
+
+    /// Coverage aggregated over the whole collection.
+    pub fn coverage(&self) -> Coverage {
+        let mut total = Coverage::default();
+        for file in self.files.values() {
+            total.merge(&file.coverage());
+        }
+        total
+    }
+
+    fn file<Q: Ord + ?Sized>(&self, id: &Q) -> Result<&File<A>, WorkspaceError<A>>
+    where
+        K: Borrow<Q>,
+    {
+        self.files.get(id).ok_or(WorkspaceError::UnknownFile)
+    }
+}
+
+impl<K: Ord, A: Address> FromIterator<(K, File<A>)> for Workspace<K, A> {
+    fn from_iter<I: IntoIterator<Item = (K, File<A>)>>(iter: I) -> Self {
+        Self { files: iter.into_iter().collect() }
+    }
+}
+
+impl<K: Ord, A: Address> Extend<(K, File<A>)> for Workspace<K, A> {
+    fn extend<I: IntoIterator<Item = (K, File<A>)>>(&mut self, iter: I) {
+        self.files.extend(iter);
+    }
+}
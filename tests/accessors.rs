@@ -0,0 +1,68 @@
+use janus_array::{CommandElements, Commands, DiskOffsets, Slices};
+
+#[test]
+fn bounds_are_read_from_the_ranges() {
+    let mut command = Commands::default();
+    command.populate_values(0x100, 0x200, 0x10, 0x110, 3);
+
+    assert_eq!((command.get_min_abs_address(), command.get_max_abs_address()), (0x100, 0x200));
+    assert_eq!((command.get_min_rel_address(), command.get_max_rel_address()), (0x10, 0x110));
+    assert_eq!(command.get_ordinal(), 3);
+
+    command.set_absolute_range_explicit(0x400..0x480);
+    command.set_relative_range_explicit(0x0..0x80);
+    assert_eq!((command.get_min_abs_address(), command.get_max_abs_address()), (0x400, 0x480));
+    assert_eq!((command.get_min_rel_address(), command.get_max_rel_address()), (0x0, 0x80));
+}
+
+#[test]
+fn single_bound_setters_update_the_ranges() {
+    let mut element = CommandElements::default();
+    element.populate_values(0x100, 0x200, 0x0, 0x100, 0);
+
+    element.set_start_abs_address(0x180);
+    assert_eq!(element.get_absolute_range(), 0x180..0x200);
+    element.set_end_abs_address(0x300);
+    assert_eq!(element.get_absolute_range(), 0x180..0x300);
+    element.set_start_rel_address(0x80);
+    element.set_end_rel_address(0x180);
+    assert_eq!(element.get_relative_range(), 0x80..0x180);
+}
+
+#[test]
+fn crossing_bounds_drag_the_other_one_along() {
+    let mut slice = Slices::default();
+    slice.populate_values(0x100, 0x200, 0x100, 0x200, 0);
+
+    // A start past the end leaves an empty range, never an inverted one
+    slice.set_start_abs_address(0x280);
+    assert_eq!(slice.get_absolute_range(), 0x280..0x280);
+    slice.set_end_abs_address(0x80);
+    assert_eq!(slice.get_absolute_range(), 0x80..0x80);
+
+    slice.set_end_rel_address(0x50);
+    assert_eq!(slice.get_relative_range(), 0x50..0x50);
+    slice.set_start_rel_address(0x60);
+    assert_eq!(slice.get_relative_range(), 0x60..0x60);
+}
+
+#[test]
+fn updates_through_the_parent_keep_children_sorted() {
+    let mut slice = Slices::default();
+    slice.populate_values(0x0, 0x1000, 0x0, 0x1000, 0);
+    for (ordinal, start) in [(0, 0x0), (1, 0x100), (2, 0x200)] {
+        let mut command = Commands::default();
+        command.populate_values(start, start + 0x100, start, start + 0x100, ordinal);
+        slice.add_command(command);
+    }
+
+    // Move the first command past the others
+    slice.update_command(0, |c| {
+        c.set_absolute_range_explicit(0x800..0x900);
+        c.set_relative_range_explicit(0x800..0x900);
+    });
+    let order: Vec<usize> = slice.commands().iter().map(|c| c.get_ordinal()).collect();
+    assert_eq!(order, vec![1, 2, 0]);
+    assert_eq!(slice.find_address(0x850).unwrap().command, Some(0));
+    assert!(slice.find_address(0x50).unwrap().command.is_none());
+}
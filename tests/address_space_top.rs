@@ -0,0 +1,154 @@
+use janus_array::builder::LayoutBuilder;
+use janus_array::diff::{diff, ChangeKind};
+use janus_array::{AddressRange, CommandElements, Commands, DiskOffsets, File, NodeLevel, OffsetLayoutsError, Slices};
+
+const TOP: u64 = u64::MAX;
+
+// A file covering the whole address space, with a slice holding its last
+// 0x1000 bytes and a command and element ending on the last byte.
+fn top_file() -> File {
+    let mut element = CommandElements::default();
+    element.populate_offset_size(TOP - 0xf, 0xf0, 0x10, 0).unwrap();
+    let mut command = Commands::default();
+    command.populate_offset_size(TOP - 0xff, 0xf00, 0x100, 0).unwrap();
+    command.add_element(element);
+    let mut slice = Slices::default();
+    slice.populate_offset_size(TOP - 0xfff, TOP - 0xfff, 0x1000, 0).unwrap();
+    slice.add_command(command);
+
+    let mut file = File::with_span(AddressRange::to_end_of_space(0));
+    file.add_slice(slice);
+    file
+}
+
+#[test]
+fn last_byte_of_the_address_space_is_found() {
+    let file = top_file();
+
+    let coords = file.find_address(TOP).unwrap();
+    assert_eq!((coords.slice, coords.command, coords.element), (Some(0), Some(0), Some(0)));
+    let coords = file.find_address(TOP - 0x10).unwrap();
+    assert_eq!((coords.slice, coords.command, coords.element), (Some(0), Some(0), None));
+    assert!(matches!(file.find_address(TOP - 0x1000), Err(OffsetLayoutsError::NotFound(_))));
+}
+
+#[test]
+fn spans_keep_the_exact_end() {
+    let file = top_file();
+    let slice = &file.slices()[0];
+
+    assert_eq!(slice.get_absolute_span().end(), None);
+    assert_eq!(slice.get_absolute_span().len(), 0x1000);
+    assert_eq!(slice.get_absolute_span().last(), Some(TOP));
+    // The Range view can't hold 2^64 and stops one byte short
+    assert_eq!(slice.get_absolute_range(), TOP - 0xfff..TOP);
+    assert_eq!(file.get_absolute_span().len(), 1 << 64);
+}
+
+#[test]
+fn checked_constructors_reject_overflow() {
+    assert!(AddressRange::from_offset_size(TOP, 1).is_ok());
+    assert!(matches!(AddressRange::from_offset_size(TOP, 2), Err(OffsetLayoutsError::AddressOverflow(..))));
+    assert!(AddressRange::from_inclusive(TOP, TOP).unwrap().contains(TOP));
+    assert!(AddressRange::from_inclusive(2u64, 1).is_err());
+
+    let mut command = Commands::default();
+    assert!(command.populate_offset_size(TOP - 1, 0, 2, 0).is_ok());
+    assert!(command.populate_offset_size(TOP - 1, 0, 3, 0).is_err());
+
+    let mut builder = LayoutBuilder::<()>::new(0);
+    assert!(builder.push_sized(NodeLevel::Slice, TOP, 1, ()).is_ok());
+    assert!(builder.push_sized(NodeLevel::Slice, TOP, 2, ()).is_err());
+}
+
+#[test]
+fn default_ranges_and_nodes_are_empty() {
+    let range = AddressRange::<u64>::default();
+    assert!(range.is_empty());
+    assert_eq!(range.len(), 0);
+    assert!(!range.contains(0) && !range.contains(TOP));
+
+    // Only the exact start of a zero-length node is findable
+    let file = File::default();
+    assert!(!file.contains_absolute_address(0x10) && !file.contains_absolute_address(TOP));
+    assert!(!Slices::default().contains_absolute_address(TOP));
+    assert!(!Commands::default().contains_absolute_address(0x10));
+    assert!(!CommandElements::default().contains_absolute_address(0x10));
+    assert_eq!(File::new().get_absolute_span(), range);
+}
+
+#[test]
+fn inverted_ranges_are_rejected() {
+    let inverted = std::ops::Range { start: 0x200u64, end: 0x100 };
+    assert!(matches!(AddressRange::new(0x200u64, 0x100), Err(OffsetLayoutsError::InconsistentStructure(0x200, _))));
+    assert!(AddressRange::try_from(inverted.clone()).is_err());
+    assert_eq!(AddressRange::new(0x100u64, 0x100).unwrap().len(), 0);
+
+    let mut builder = LayoutBuilder::new(0x1000);
+    assert!(builder.push(NodeLevel::Slice, inverted.clone(), ()).is_err());
+    assert!(builder.is_empty());
+    assert!(LayoutBuilder::<()>::with_range(inverted.clone()).is_err());
+    assert!(matches!(File::from_ranges(vec![vec![inverted]]), Err(OffsetLayoutsError::InconsistentStructure(..))));
+}
+
+#[test]
+fn two_bound_setters_clamp_an_inverted_range() {
+    let mut element = CommandElements::default();
+    element.populate_values(0x100, 0x180, 0x80, 0x0, 0);
+    assert_eq!(element.get_absolute_range(), 0x100..0x180);
+    assert_eq!(element.get_relative_range(), 0x80..0x80);
+
+    element.set_absolutes(0x200, 0x100);
+    assert_eq!(element.get_absolute_range(), 0x200..0x200);
+    element.set_relative_range_explicit(std::ops::Range { start: 0x40, end: 0x10 });
+    assert_eq!(element.get_relative_range(), 0x40..0x40);
+}
+
+#[test]
+fn zero_length_node_at_the_last_address() {
+    let mut file = File::with_span(AddressRange::to_end_of_space(TOP - 0xff));
+    let mut slice = Slices::default();
+    slice.populate_offset_size(TOP - 0xff, 0, 0x100, 0).unwrap();
+    let mut marker = Commands::default();
+    marker.populate_offset_size(TOP, 0xff, 0, 0).unwrap();
+    slice.add_command(marker);
+    file.add_slice(slice);
+
+    assert_eq!(file.find_address(TOP).unwrap().command, Some(0));
+}
+
+#[test]
+fn builder_places_records_up_to_the_last_byte() {
+    let mut builder = LayoutBuilder::with_span(AddressRange::to_end_of_space(0));
+    builder.push_sized(NodeLevel::Slice, TOP - 0xfff, 0x1000, "tail").unwrap();
+    builder.push_sized(NodeLevel::Command, TOP - 0xf, 0x10, "last").unwrap();
+    builder.push_span(NodeLevel::Element, AddressRange::from_inclusive(TOP, TOP).unwrap(), "byte");
+    let built = builder.finish();
+
+    assert!(built.orphans.is_empty());
+    let coords = built.file.find_address(TOP).unwrap();
+    assert_eq!((coords.slice, coords.command, coords.element), (Some(0), Some(0), Some(0)));
+    let command = &built.file.slices()[0].commands()[0];
+    assert_eq!(command.get_relative_span(), AddressRange::new(0xff0, 0x1000).unwrap());
+}
+
+#[test]
+fn edits_fail_instead_of_wrapping() {
+    let mut file = top_file();
+    assert!(matches!(file.insert_bytes(TOP - 0x800, 1), Err(OffsetLayoutsError::AddressOverflow(..))));
+    assert!(file.rebase(1).is_err());
+    // A failed edit leaves the tree untouched
+    assert_eq!(file.find_address(TOP).unwrap().element, Some(0));
+
+    let report = file.remove_span(AddressRange::to_end_of_space(TOP - 0xf)).unwrap();
+    assert_eq!(report.removed.len(), 1);
+    assert_eq!(file.slices()[0].get_absolute_span(), AddressRange::new(TOP - 0xfff, TOP - 0xf).unwrap());
+
+    let before = top_file();
+    let mut after = before.clone();
+    after.remove_bytes(0..0x10).unwrap();
+    assert_eq!(after.find_address(TOP - 0x10).unwrap().element, Some(0));
+    assert_eq!(after.get_absolute_span().end(), Some(TOP - 0xf));
+    let kinds: Vec<ChangeKind> = diff(&before, &after).iter().map(|c| c.kind).collect();
+    assert_eq!(kinds, [ChangeKind::Resized, ChangeKind::Moved, ChangeKind::Moved, ChangeKind::Moved]);
+}
@@ -0,0 +1,89 @@
+use std::mem::size_of;
+
+use janus_array::{AddressRange, CommandElements, Commands, DiskOffsets, File, OffsetLayoutsError, Segmented, Slices};
+
+fn span32(start: u32, end: u32) -> AddressRange<u32> {
+    AddressRange::new(start, end).unwrap()
+}
+
+// A 32-bit firmware image: one slice, two commands, elements in the second.
+fn firmware() -> File<u32> {
+    let mut command = Commands::with_spans(span32(0x100, 0x200), span32(0x100, 0x200), 1);
+    command.add_element(CommandElements::with_spans(span32(0x100, 0x110), span32(0, 0x10), 0));
+    command.add_element(CommandElements::with_spans(span32(0x180, 0x190), span32(0x80, 0x90), 1));
+    let mut slice = Slices::with_spans(span32(0, 0x1000), span32(0, 0x1000), 0);
+    slice.add_command(Commands::with_spans(span32(0, 0x100), span32(0, 0x100), 0));
+    slice.add_command(command);
+
+    let mut file = File::with_span(span32(0, 0x1000));
+    file.add_slice(slice);
+    file
+}
+
+#[test]
+fn u32_layout_answers_lookups() {
+    let file = firmware();
+
+    let coords = file.find_address(0x185).unwrap();
+    assert_eq!((coords.slice, coords.command, coords.element), (Some(0), Some(1), Some(1)));
+    assert_eq!(file.find_address(0x50).unwrap().command, Some(0));
+    assert!(matches!(file.find_address(0x2000), Err(OffsetLayoutsError::AddressOutsideCurrentScope(0x2000, _))));
+
+    let results = file.find_addresses(&[0x0, 0x105, 0x185, 0xfff]);
+    assert!(results.iter().all(|r| r.is_ok()));
+}
+
+#[test]
+fn u32_nodes_are_smaller() {
+    assert!(size_of::<CommandElements<u32>>() < size_of::<CommandElements>());
+}
+
+#[test]
+fn u32_ranges_reach_the_last_address_and_reject_overflow() {
+    let mut element = CommandElements::with_spans(span32(0, 0), span32(0, 0), 0);
+    element.populate_offset_size(u32::MAX - 0xf, 0xf0, 0x10, 0).unwrap();
+    assert!(element.contains_absolute_address(u32::MAX));
+    assert_eq!(element.get_absolute_span().end(), None);
+
+    let err = element.populate_offset_size(u32::MAX - 0xf, 0, 0x11, 0).unwrap_err();
+    assert!(matches!(err, OffsetLayoutsError::AddressOverflow(..)));
+    // Sizes are u64, so a size that doesn't fit in u32 is an overflow too
+    assert!(element.populate_offset_size(0, 0, 1 << 32, 0).is_ok());
+    assert!(element.populate_offset_size(1, 0, 1 << 32, 0).is_err());
+}
+
+fn seg(segment: u32, offset: u32) -> Segmented {
+    Segmented::new(segment, offset)
+}
+
+#[test]
+fn segmented_layout_answers_lookups() {
+    let code = AddressRange::new(seg(1, 0), seg(1, 0x8000)).unwrap();
+    let data = AddressRange::new(seg(2, 0), seg(2, 0x4000)).unwrap();
+    let relative = AddressRange::new(seg(0, 0), seg(0, 0x100)).unwrap();
+
+    let mut code_slice = Slices::with_spans(code, code, 0);
+    code_slice.add_command(Commands::with_spans(AddressRange::new(seg(1, 0x100), seg(1, 0x200)).unwrap(), relative, 0));
+    let mut data_slice = Slices::with_spans(data, data, 1);
+    data_slice.add_command(Commands::with_spans(AddressRange::new(seg(2, 0x100), seg(2, 0x200)).unwrap(), relative, 0));
+
+    let mut file = File::with_span(AddressRange::new(seg(0, 0), seg(3, 0)).unwrap());
+    // Added out of order on purpose
+    file.add_slice(data_slice);
+    file.add_slice(code_slice);
+
+    let coords = file.find_address(seg(2, 0x180)).unwrap();
+    assert_eq!((coords.slice, coords.command), (Some(1), Some(0)));
+    assert_eq!(file.find_address(seg(1, 0x180)).unwrap().slice, Some(0));
+    assert_eq!(file.find_address(seg(1, 0x7fff)).unwrap().command, None);
+    assert!(matches!(file.find_address(seg(0, 0x180)), Err(OffsetLayoutsError::NotFound(_))));
+}
+
+#[test]
+fn segmented_sizes_carry_into_the_next_segment() {
+    let range = AddressRange::from_offset_size(seg(1, u32::MAX), 2).unwrap();
+    assert_eq!(range.end(), Some(seg(2, 1)));
+    assert!(range.contains(seg(2, 0)));
+    assert_eq!(range.to_string(), "0x1:0xffffffff..0x2:0x1");
+    assert!(AddressRange::from_offset_size(seg(u32::MAX, u32::MAX), 2).is_err());
+}
@@ -0,0 +1,155 @@
+use janus_array::builder::{AmbiguityPolicy, BuildOptions, LayoutBuilder, OrphanReason, StraddlePolicy};
+use janus_array::{DiskOffsets, File, NodeLevel, OffsetLayoutsError};
+
+#[test]
+fn records_pushed_in_any_order_are_sorted_under_their_parents() {
+    let mut builder = LayoutBuilder::new(0x1000);
+    builder.push(NodeLevel::Element, 0x410..0x420, "e2").unwrap();
+    builder.push(NodeLevel::Command, 0x400..0x500, "c1").unwrap();
+    builder.push(NodeLevel::Element, 0x120..0x130, "e1").unwrap();
+    builder.push(NodeLevel::Slice, 0x0..0x800, "s0").unwrap();
+    builder.push(NodeLevel::Element, 0x100..0x110, "e0").unwrap();
+    builder.push(NodeLevel::Command, 0x100..0x200, "c0").unwrap();
+    assert_eq!(builder.len(), 6);
+
+    let built = builder.finish();
+    assert!(built.orphans.is_empty());
+    let file = &built.file;
+    assert_eq!(file.get_absolute_range(), 0x0..0x1000);
+
+    // Ordinals follow addresses, not push order
+    let commands = file.slices()[0].commands();
+    assert_eq!(commands[0].get_absolute_range(), 0x100..0x200);
+    assert_eq!(commands[1].get_absolute_range(), 0x400..0x500);
+    assert_eq!(commands[0].elements()[1].get_absolute_range(), 0x120..0x130);
+    assert_eq!(commands[0].elements()[1].get_ordinal(), 1);
+    // Relative ranges are measured from the parent
+    assert_eq!(commands[1].elements()[0].get_relative_range(), 0x10..0x20);
+    assert_eq!(commands[1].get_relative_range(), 0x400..0x500);
+
+    let payloads: Vec<(Vec<usize>, &str)> = built.payloads.iter().map(|(c, p)| (c.ordinals(), *p)).collect();
+    let expected = [(&[0][..], "s0"), (&[0, 0], "c0"), (&[0, 0, 0], "e0"), (&[0, 0, 1], "e1"), (&[0, 1], "c1"), (&[0, 1, 0], "e2")];
+    assert_eq!(payloads, expected.map(|(c, p)| (c.to_vec(), p)));
+    assert_eq!(file.find_address(0x415).unwrap().ordinals(), vec![0, 1, 0]);
+}
+
+#[test]
+fn records_without_a_parent_are_orphaned() {
+    let mut builder = LayoutBuilder::with_range(0x1000..0x2000).unwrap();
+    builder.push(NodeLevel::File, 0x1000..0x2000, 0).unwrap();
+    builder.push(NodeLevel::Slice, 0x1000..0x1800, 1).unwrap();
+    builder.push(NodeLevel::Command, 0x1100..0x1200, 2).unwrap();
+    // Past every command, then before the file, then across the slice's end
+    builder.push(NodeLevel::Element, 0x1300..0x1310, 3).unwrap();
+    builder.push(NodeLevel::Slice, 0x800..0x900, 4).unwrap();
+    builder.push(NodeLevel::Command, 0x1700..0x1900, 5).unwrap();
+
+    let built = builder.finish();
+    let orphans: Vec<(usize, OrphanReason)> = built.orphans.iter().map(|o| (o.payload, o.reason)).collect();
+    assert_eq!(
+        orphans,
+        vec![
+            (0, OrphanReason::RootLevel),
+            (3, OrphanReason::NoParent),
+            (4, OrphanReason::NoParent),
+            (5, OrphanReason::Straddles),
+        ]
+    );
+    assert_eq!(built.orphans[3].level, NodeLevel::Command);
+    assert_eq!(built.orphans[3].range.to_range(), 0x1700..0x1900);
+    assert_eq!(built.payloads.len(), 2);
+    assert_eq!(built.file.slices()[0].commands().len(), 1);
+}
+
+#[test]
+fn straddling_ranges_are_orphaned_or_clipped() {
+    let levels = || vec![vec![0x100..0x800], vec![0x700..0x900, 0x80..0x200]];
+
+    let built = File::from_ranges_with(levels(), BuildOptions::default()).unwrap();
+    let orphans: Vec<(usize, OrphanReason)> = built.orphans.iter().map(|o| (o.payload, o.reason)).collect();
+    assert_eq!(orphans, vec![(0, OrphanReason::Straddles), (1, OrphanReason::Straddles)]);
+
+    let clip = BuildOptions { straddling: StraddlePolicy::Clip, ..Default::default() };
+    let built = File::from_ranges_with(levels(), clip).unwrap();
+    assert!(built.orphans.is_empty());
+    let commands = built.file.slices()[0].commands();
+    assert_eq!(commands[0].get_absolute_range(), 0x100..0x200);
+    assert_eq!(commands[1].get_absolute_range(), 0x700..0x800);
+    assert_eq!(commands[1].get_relative_range(), 0x600..0x700);
+    // Payloads keep pointing at the original list entries
+    assert_eq!((built.payloads[1].0.ordinals(), built.payloads[1].1), (vec![0, 0], 1));
+}
+
+#[test]
+fn overlapping_siblings_keep_the_first_or_the_larger() {
+    let levels = || vec![vec![0x0..0x1000], vec![0x100..0x200, 0x180..0x400, 0x400..0x480]];
+
+    let built = File::from_ranges_with(levels(), BuildOptions::default()).unwrap();
+    assert_eq!(built.orphans.len(), 1);
+    assert_eq!((built.orphans[0].payload, built.orphans[0].reason), (1, OrphanReason::OverlapsSibling));
+    assert_eq!(built.file.slices()[0].commands()[0].get_absolute_range(), 0x100..0x200);
+
+    let larger = BuildOptions { ambiguous: AmbiguityPolicy::KeepLarger, ..Default::default() };
+    let built = File::from_ranges_with(levels(), larger).unwrap();
+    assert_eq!((built.orphans[0].payload, built.orphans[0].reason), (0, OrphanReason::OverlapsSibling));
+    let kept: Vec<_> = built.file.slices()[0].commands().iter().map(|c| c.get_absolute_range()).collect();
+    assert_eq!(kept, vec![0x180..0x400, 0x400..0x480]);
+}
+
+#[test]
+fn from_ranges_spans_up_to_the_highest_end() {
+    let file = File::from_ranges(vec![vec![0x100..0x800, 0x800..0x900], vec![0x880..0x8c0]]).unwrap();
+    assert_eq!(file.get_absolute_range(), 0x0..0x900);
+    assert_eq!(file.find_address(0x8a0).unwrap().ordinals(), vec![1, 0]);
+    assert_eq!(File::from_ranges(Vec::new()).unwrap().get_absolute_range(), 0x0..0x0);
+}
+
+#[test]
+fn from_ranges_reports_the_first_unplaced_range_and_extra_levels() {
+    let unplaced = File::from_ranges(vec![vec![0x0..0x100], vec![0x200..0x300, 0x80..0x180]]);
+    assert!(matches!(unplaced, Err(OffsetLayoutsError::UnplacedRange(r)) if r == (0x200..0x300)));
+
+    let levels = vec![vec![0x0..0x100]; 4];
+    assert!(matches!(File::from_ranges(levels.clone()), Err(OffsetLayoutsError::TooManyLevels(4))));
+    assert!(matches!(File::from_ranges_with(levels, BuildOptions::default()), Err(OffsetLayoutsError::TooManyLevels(4))));
+}
+
+#[test]
+fn zero_length_parents_only_hold_zero_length_children() {
+    let mut builder = LayoutBuilder::new(0x1000);
+    builder.push(NodeLevel::Slice, 0x0..0x1000, "slice").unwrap();
+    builder.push(NodeLevel::Command, 0x100..0x200, "owner").unwrap();
+    builder.push(NodeLevel::Command, 0x200..0x200, "marker").unwrap();
+    builder.push(NodeLevel::Element, 0x200..0x200, "empty").unwrap();
+    builder.push(NodeLevel::Element, 0x200..0x210, "bytes").unwrap();
+
+    let built = builder.finish();
+    // `owner` ends where `marker` sits, so only the empty element fits
+    let placed: Vec<(Vec<usize>, &str)> = built.payloads.iter().map(|(c, p)| (c.ordinals(), *p)).collect();
+    assert_eq!(placed[3], (vec![0, 1, 0], "empty"));
+    assert_eq!(built.orphans.len(), 1);
+    assert_eq!((built.orphans[0].payload, built.orphans[0].reason), ("bytes", OrphanReason::NoParent));
+
+    // A parent owning bytes wins over a zero-length one at the same address
+    let mut builder = LayoutBuilder::new(0x1000);
+    builder.push(NodeLevel::Slice, 0x0..0x1000, ()).unwrap();
+    builder.push(NodeLevel::Command, 0x200..0x200, ()).unwrap();
+    builder.push(NodeLevel::Command, 0x200..0x300, ()).unwrap();
+    builder.push(NodeLevel::Element, 0x200..0x200, ()).unwrap();
+    let built = builder.finish();
+    let commands = built.file.slices()[0].commands();
+    assert!(built.orphans.is_empty());
+    assert_eq!((commands[0].elements().len(), commands[1].elements().len()), (0, 1));
+}
+
+#[test]
+fn push_sized_rejects_nodes_past_the_address_space() {
+    let mut builder = LayoutBuilder::<()>::new(0x1000);
+    builder.push_sized(NodeLevel::Slice, 0x100, 0x100, ()).unwrap();
+    assert!(matches!(
+        builder.push_sized(NodeLevel::Slice, u64::MAX, 2, ()),
+        Err(OffsetLayoutsError::AddressOverflow(..))
+    ));
+    assert_eq!(builder.len(), 1);
+    assert!(LayoutBuilder::<()>::new(0x10).finish().file.slices().is_empty());
+}
@@ -0,0 +1,75 @@
+#![cfg(feature = "std")]
+
+use std::sync::Arc;
+use std::thread;
+
+use janus_array::{CommandElements, Commands, DiskOffsets, File, Slices};
+
+fn layout() -> File {
+    let mut file = File::with_size(0x10000);
+    for s in 0..4u64 {
+        let slice_start = s * 0x4000;
+        let mut slice = Slices::default();
+        slice.populate_values(slice_start, slice_start + 0x4000, slice_start, slice_start + 0x4000, s as usize);
+        for c in 0..16u64 {
+            let cmd_start = slice_start + c * 0x400;
+            let mut command = Commands::default();
+            command.populate_values(cmd_start, cmd_start + 0x400, c * 0x400, (c + 1) * 0x400, c as usize);
+            // Leave every other command's tail unclaimed
+            for e in 0..(if c % 2 == 0 { 8 } else { 6 }) {
+                let el_start = cmd_start + e * 0x80;
+                let mut element = CommandElements::default();
+                element.populate_values(el_start, el_start + 0x80, e * 0x80, (e + 1) * 0x80, e as usize);
+                command.add_element(element);
+            }
+            slice.add_command(command);
+        }
+        file.add_slice(slice);
+    }
+    file
+}
+
+#[test]
+fn shared_layout_is_queryable_from_many_threads() {
+    let file = Arc::new(layout());
+    let workers: Vec<_> = (0..8u64)
+        .map(|t| {
+            let file = Arc::clone(&file);
+            thread::spawn(move || {
+                for addr in (t..0x10000).step_by(97) {
+                    let coords = file.find_address(addr).expect("address inside the file");
+                    assert_eq!(coords.slice, Some((addr / 0x4000) as usize));
+                    assert_eq!(coords.command, Some((addr % 0x4000 / 0x400) as usize));
+                }
+            })
+        })
+        .collect();
+    for worker in workers {
+        worker.join().unwrap();
+    }
+}
+
+#[test]
+fn parallel_batch_matches_sequential_batch() {
+    let file = layout();
+    let addresses: Vec<u64> = (0..0x10100).step_by(13).collect();
+
+    let sequential = file.find_addresses(&addresses);
+    for threads in [1, 2, 3, 8, 64] {
+        let parallel = file.find_addresses_parallel(&addresses, threads);
+        assert_eq!(parallel.len(), sequential.len());
+        for (p, s) in parallel.iter().zip(&sequential) {
+            assert_eq!(format!("{:?}", p), format!("{:?}", s));
+        }
+    }
+}
+
+#[test]
+fn batch_reports_unclaimed_bytes_and_out_of_range_addresses() {
+    let file = layout();
+    // 0x400 + 0x300 is past the last element of command 1 in slice 0
+    let results = file.find_addresses(&[0x700, 0x10000]);
+    let gap = results[0].as_ref().unwrap();
+    assert_eq!((gap.slice, gap.command, gap.element), (Some(0), Some(1), None));
+    assert!(results[1].is_err());
+}
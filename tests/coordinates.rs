@@ -0,0 +1,103 @@
+use std::collections::HashMap;
+
+use janus_array::builder::LayoutBuilder;
+use janus_array::{Coordinates, DiskOffsets, File, NodeLevel, OffsetLayoutsError, ParseCoordinatesError};
+
+fn layout() -> File {
+    let mut builder = LayoutBuilder::new(0x1000);
+    builder.push(NodeLevel::Slice, 0x800..0x1000, ()).unwrap();
+    builder.push(NodeLevel::Slice, 0x0..0x800, ()).unwrap();
+    builder.push(NodeLevel::Command, 0x100..0x200, ()).unwrap();
+    builder.push(NodeLevel::Command, 0x0..0x100, ()).unwrap();
+    builder.push(NodeLevel::Command, 0x900..0xa00, ()).unwrap();
+    builder.push(NodeLevel::Element, 0x120..0x140, ()).unwrap();
+    builder.finish().file
+}
+
+fn at(path: &[usize]) -> Coordinates {
+    Coordinates::from_ordinals(path)
+}
+
+#[test]
+fn display_and_parse_round_trip() {
+    for text in ["", "0", "0/12", "0/12/3"] {
+        let coords: Coordinates = text.parse().unwrap();
+        assert_eq!(coords.to_string(), text);
+    }
+    assert_eq!("/1/2".parse::<Coordinates>().unwrap(), at(&[1, 2]));
+    assert_eq!("0/x".parse::<Coordinates>(), Err(ParseCoordinatesError::InvalidOrdinal("x".to_string())));
+    assert_eq!("0//1".parse::<Coordinates>(), Err(ParseCoordinatesError::InvalidOrdinal(String::new())));
+    assert_eq!("0/1/2/3".parse::<Coordinates>(), Err(ParseCoordinatesError::TooDeep(4)));
+}
+
+#[test]
+fn sorting_follows_document_order() {
+    let file = layout();
+    let mut found: Vec<Coordinates> =
+        [0x950, 0x130, 0x10, 0x900, 0x150, 0x400].iter().map(|&a| file.find_address(a).unwrap()).collect();
+    found.sort();
+    found.dedup();
+
+    let printed: Vec<String> = found.iter().map(|c| c.to_string()).collect();
+    assert_eq!(printed, ["0", "0/0", "0/1", "0/1/0", "1/0"]);
+    // Parents sort before their children
+    assert!(at(&[0, 1]) < at(&[0, 1, 0]) && at(&[0, 1, 0]) < at(&[0, 2]));
+}
+
+#[test]
+fn coordinates_are_usable_as_map_keys() {
+    let file = layout();
+    let mut hits: HashMap<Coordinates, usize> = HashMap::new();
+    for address in (0..0x1000).step_by(0x10) {
+        *hits.entry(file.find_address(address).unwrap()).or_default() += 1;
+    }
+    assert_eq!(hits[&at(&[0, 1, 0])], 2);
+    assert_eq!(hits[&at(&[1, 0])], 16);
+    assert_eq!(hits.len(), 6);
+}
+
+#[test]
+fn tree_helpers() {
+    let element = at(&[0, 1, 0]);
+    assert_eq!(element.depth(), 3);
+    assert_eq!(element.parent(), Some(at(&[0, 1])));
+    assert_eq!(at(&[0]).parent(), Some(Coordinates::new()));
+    assert_eq!(Coordinates::new().parent(), None);
+
+    assert!(Coordinates::new().is_ancestor_of(&element));
+    assert!(at(&[0]).is_ancestor_of(&element));
+    assert!(!element.is_ancestor_of(&element));
+    assert!(!at(&[1]).is_ancestor_of(&element));
+}
+
+#[test]
+fn resolve_reports_stale_coordinates() {
+    let mut file = layout();
+    let element = file.find_address(0x130).unwrap();
+    assert_eq!(file.resolve(&element).unwrap().absolute_range(), 0x120..0x140);
+    assert_eq!(file.resolve(&Coordinates::new()).unwrap().ordinal(), 0);
+
+    file.update_slice(0, |slice| slice.remove_command(1));
+    match file.resolve(&element) {
+        Err(OffsetLayoutsError::StaleCoordinates(coords, depth)) => {
+            assert_eq!(coords, element);
+            assert_eq!(depth, 1);
+        }
+        other => panic!("unexpected {:?}", other.map(|n| n.ordinal())),
+    }
+    assert!(file.node_at(&element).is_none());
+}
+
+#[test]
+fn inner_path_is_set_through_the_builder_method() {
+    let coords = at(&[0, 3]).with_inner(at(&[1, 0]));
+    assert_eq!(coords.inner(), Some(&at(&[1, 0])));
+    assert_eq!(coords.to_string(), "0/3!1/0");
+    assert_eq!("0/3!1/0".parse::<Coordinates>().unwrap(), coords);
+    assert!(at(&[0, 3]).inner().is_none());
+
+    // The ordinals stay public fields
+    let mut coords = coords;
+    coords.set_element(2);
+    assert_eq!((coords.slice, coords.command, coords.element), (Some(0), Some(3), Some(2)));
+}
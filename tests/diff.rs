@@ -0,0 +1,143 @@
+use std::ops::Range;
+
+use janus_array::diff::{diff, diff_by, ChangeKind, LayoutChange, MatchBy};
+use janus_array::{CommandElements, Commands, Coordinates, DiskOffsets, File, NodeLevel, NodeRef, Slices};
+
+// One slice of commands, numbered in address order. Each command gets the
+// relative offset `key * 0x1000`, which stays put when it moves or resizes.
+fn layout(commands: &[(Range<u64>, u64)]) -> File {
+    let mut sorted = commands.to_vec();
+    sorted.sort_by_key(|(range, _)| range.start);
+    let mut slice = Slices::default();
+    slice.populate_values(0x0, 0x1000, 0x0, 0x1000, 0);
+    for (ordinal, (range, key)) in sorted.into_iter().enumerate() {
+        let mut command = Commands::default();
+        let relative = key * 0x1000;
+        command.populate_values(range.start, range.end, relative, relative + (range.end - range.start), ordinal);
+        slice.add_command(command);
+    }
+    let mut file = File::with_size(0x1000);
+    file.add_slice(slice);
+    file
+}
+
+fn original() -> File {
+    layout(&[(0x0..0x100, 1), (0x100..0x200, 2), (0x200..0x300, 3)])
+}
+
+fn by_key(node: &NodeRef<'_>) -> String {
+    node.relative_range().start.to_string()
+}
+
+fn path(coords: &Coordinates) -> String {
+    let ordinals: Vec<String> =
+        [coords.slice, coords.command, coords.element].iter().flatten().map(|o| o.to_string()).collect();
+    ordinals.join("/")
+}
+
+// (kind, old path, new path) of every change
+fn summary(changes: &[LayoutChange]) -> Vec<(ChangeKind, Option<String>, Option<String>)> {
+    changes.iter().map(|c| (c.kind, c.old.as_ref().map(path), c.new.as_ref().map(path))).collect()
+}
+
+fn change(kind: ChangeKind, old: Option<&str>, new: Option<&str>) -> (ChangeKind, Option<String>, Option<String>) {
+    (kind, old.map(String::from), new.map(String::from))
+}
+
+#[test]
+fn moved_node_under_each_matching_mode() {
+    let old = original();
+    // Key 2 moves past key 3, which takes over its ordinal
+    let new = layout(&[(0x0..0x100, 1), (0x400..0x500, 2), (0x200..0x300, 3)]);
+
+    // Ordinals pair 2 with 3 and 3 with 2: both look moved
+    assert_eq!(
+        summary(&diff(&old, &new)),
+        vec![
+            change(ChangeKind::Moved, Some("0/1"), Some("0/1")),
+            change(ChangeKind::Moved, Some("0/2"), Some("0/2")),
+        ]
+    );
+    // No range is shared: key 2 disappears from one place and appears in the other
+    assert_eq!(
+        summary(&diff_by(&old, &new, &MatchBy::Range)),
+        vec![change(ChangeKind::Removed, Some("0/1"), None), change(ChangeKind::Added, None, Some("0/2"))]
+    );
+    // Keys follow 2; 3 only changed ordinal, which is not a change
+    let changes = diff_by(&old, &new, &MatchBy::Key(&by_key));
+    assert_eq!(summary(&changes), vec![change(ChangeKind::Moved, Some("0/1"), Some("0/2"))]);
+    assert_eq!(changes[0].level, NodeLevel::Command);
+    assert_eq!(changes[0].old_range.unwrap().to_range(), 0x100..0x200);
+    assert_eq!(changes[0].new_range.unwrap().to_range(), 0x400..0x500);
+}
+
+#[test]
+fn resized_node_under_each_matching_mode() {
+    let old = original();
+    let new = layout(&[(0x0..0x100, 1), (0x100..0x180, 2), (0x200..0x300, 3)]);
+
+    let resized = vec![change(ChangeKind::Resized, Some("0/1"), Some("0/1"))];
+    assert_eq!(summary(&diff(&old, &new)), resized);
+    assert_eq!(summary(&diff_by(&old, &new, &MatchBy::Key(&by_key))), resized);
+    assert_eq!(
+        summary(&diff_by(&old, &new, &MatchBy::Range)),
+        vec![change(ChangeKind::Removed, Some("0/1"), None), change(ChangeKind::Added, None, Some("0/1"))]
+    );
+
+    // Moving and resizing at once is reported as a resize
+    let new = layout(&[(0x0..0x100, 1), (0x180..0x200, 2), (0x200..0x300, 3)]);
+    let changes = diff(&old, &new);
+    assert_eq!(summary(&changes), resized);
+    assert_eq!(changes[0].new_range.unwrap().to_range(), 0x180..0x200);
+}
+
+#[test]
+fn rekeyed_node_under_each_matching_mode() {
+    let old = original();
+    let new = layout(&[(0x0..0x100, 1), (0x100..0x200, 4), (0x200..0x300, 3)]);
+
+    // Relative offsets aren't compared, only used as keys here
+    assert!(diff(&old, &new).is_empty());
+    assert!(diff_by(&old, &new, &MatchBy::Range).is_empty());
+    assert_eq!(
+        summary(&diff_by(&old, &new, &MatchBy::Key(&by_key))),
+        vec![change(ChangeKind::Removed, Some("0/1"), None), change(ChangeKind::Added, None, Some("0/1"))]
+    );
+}
+
+#[test]
+fn duplicate_keys_pair_in_storage_order() {
+    let old = layout(&[(0x0..0x100, 7), (0x100..0x200, 7), (0x200..0x300, 7)]);
+    let new = layout(&[(0x0..0x100, 7), (0x180..0x280, 7)]);
+
+    // First with first, second with second; the third has no partner left
+    assert_eq!(
+        summary(&diff_by(&old, &new, &MatchBy::Key(&by_key))),
+        vec![change(ChangeKind::Moved, Some("0/1"), Some("0/1")), change(ChangeKind::Removed, Some("0/2"), None)]
+    );
+    assert_eq!(
+        summary(&diff_by(&new, &old, &MatchBy::Key(&by_key))),
+        vec![change(ChangeKind::Moved, Some("0/1"), Some("0/1")), change(ChangeKind::Added, None, Some("0/2"))]
+    );
+}
+
+#[test]
+fn children_of_removed_nodes_are_not_reported() {
+    let mut command = Commands::default();
+    command.populate_values(0x0, 0x100, 0x0, 0x100, 0);
+    let mut element = CommandElements::default();
+    element.populate_values(0x0, 0x10, 0x0, 0x10, 0);
+    command.add_element(element);
+    let mut slice = Slices::default();
+    slice.populate_values(0x0, 0x800, 0x0, 0x800, 0);
+    slice.add_command(command);
+    let mut old = File::with_size(0x1000);
+    old.add_slice(slice);
+    let new = File::with_size(0x1000);
+
+    for by in [MatchBy::Ordinal, MatchBy::Range, MatchBy::Key(&by_key)] {
+        let changes = diff_by(&old, &new, &by);
+        assert_eq!(summary(&changes), vec![change(ChangeKind::Removed, Some("0"), None)]);
+        assert_eq!(changes[0].level, NodeLevel::Slice);
+    }
+}
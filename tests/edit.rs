@@ -0,0 +1,146 @@
+use std::ops::Range;
+
+use janus_array::{CommandElements, Commands, Coordinates, DiskOffsets, File, OffsetLayoutsError, Slices};
+
+// A slice with a command holding two elements, then a childless command.
+// Relative ranges are offsets from the parent's start.
+fn layout() -> File {
+    let mut first = Commands::default();
+    first.populate_values(0x100, 0x300, 0x0, 0x200, 0);
+    for (ordinal, start) in [0x100u64, 0x200].into_iter().enumerate() {
+        let mut element = CommandElements::default();
+        element.populate_values(start, start + 0x80, start - 0x100, start - 0x80, ordinal);
+        first.add_element(element);
+    }
+    let mut second = Commands::default();
+    second.populate_values(0x400, 0x600, 0x300, 0x500, 1);
+    let mut slice = Slices::default();
+    slice.populate_values(0x100, 0x900, 0x100, 0x900, 0);
+    slice.add_command(first);
+    slice.add_command(second);
+    let mut file = File::with_size(0x1000);
+    file.add_slice(slice);
+    file
+}
+
+type Path = (Option<usize>, Option<usize>, Option<usize>);
+
+fn at(path: &[usize]) -> Path {
+    (path.first().copied(), path.get(1).copied(), path.get(2).copied())
+}
+
+fn paths(coords: &[Coordinates]) -> Vec<Path> {
+    coords.iter().map(|c| (c.slice, c.command, c.element)).collect()
+}
+
+fn coordinates(path: &[usize]) -> Coordinates {
+    let mut coords = Coordinates::new();
+    coords.slice = path.first().copied();
+    coords.command = path.get(1).copied();
+    coords.element = path.get(2).copied();
+    coords
+}
+
+fn found(file: &File, address: u64) -> Path {
+    let coords = file.find_address(address).unwrap();
+    (coords.slice, coords.command, coords.element)
+}
+
+fn range(file: &File, path: &[usize]) -> Range<u64> {
+    file.node_at(&coordinates(path)).unwrap().absolute_range()
+}
+
+fn relative(file: &File, path: &[usize]) -> Range<u64> {
+    file.node_at(&coordinates(path)).unwrap().relative_range()
+}
+
+#[test]
+fn insert_grows_containing_nodes_and_shifts_later_ones() {
+    let mut file = layout();
+    let report = file.insert_bytes(0x240, 0x10).unwrap();
+
+    assert_eq!(file.get_absolute_range(), 0x0..0x1010);
+    assert_eq!(range(&file, &[0]), 0x100..0x910);
+    assert_eq!(range(&file, &[0, 0]), 0x100..0x310);
+    assert_eq!(range(&file, &[0, 0, 0]), 0x100..0x180);
+    assert_eq!(range(&file, &[0, 0, 1]), 0x200..0x290);
+    assert_eq!(range(&file, &[0, 1]), 0x410..0x610);
+    // Children keep their offset from a parent that moved
+    assert_eq!(relative(&file, &[0, 1]), 0x310..0x510);
+    assert_eq!(relative(&file, &[0, 0, 1]), 0x100..0x190);
+
+    assert_eq!(report.shifted, 1);
+    assert_eq!(paths(&report.grown), vec![at(&[]), at(&[0]), at(&[0, 0]), at(&[0, 0, 1])]);
+    assert_eq!(paths(&report.split), vec![at(&[0, 0, 1])]);
+    assert!(report.shrunk.is_empty() && report.removed.is_empty());
+    assert_eq!(found(&file, 0x605), at(&[0, 1]));
+}
+
+#[test]
+fn insert_at_a_node_start_moves_the_node() {
+    let mut file = layout();
+    let report = file.insert_bytes(0x400, 0x10).unwrap();
+
+    assert_eq!(range(&file, &[0, 1]), 0x410..0x610);
+    assert_eq!(report.shifted, 1);
+    assert!(report.split.is_empty());
+    assert_eq!(found(&file, 0x410), at(&[0, 1]));
+
+    // Except for the file itself, which keeps its start
+    let report = file.insert_bytes(0x0, 0x10).unwrap();
+    assert_eq!(file.get_absolute_range(), 0x0..0x1020);
+    assert_eq!(range(&file, &[0]), 0x110..0x920);
+    assert_eq!(report.shifted, 5);
+}
+
+#[test]
+fn remove_shrinks_overlapping_nodes_and_drops_covered_ones() {
+    let mut file = layout();
+    let report = file.remove_bytes(0x180..0x220).unwrap();
+
+    assert_eq!(range(&file, &[0, 0]), 0x100..0x260);
+    assert_eq!(range(&file, &[0, 0, 0]), 0x100..0x180);
+    assert_eq!(range(&file, &[0, 0, 1]), 0x180..0x1e0);
+    assert_eq!(relative(&file, &[0, 0, 1]), 0x80..0xe0);
+    assert_eq!(range(&file, &[0, 1]), 0x360..0x560);
+    assert_eq!(paths(&report.shrunk), vec![at(&[]), at(&[0]), at(&[0, 0]), at(&[0, 0, 1])]);
+    assert!(report.removed.is_empty());
+
+    // The childless command lies entirely inside the removed range
+    let report = file.remove_bytes(0x360..0x560).unwrap();
+    assert_eq!(paths(&report.removed), vec![at(&[0, 1])]);
+    assert!(file.node_at(&coordinates(&[0, 1])).is_none());
+    assert_eq!(range(&file, &[0]), 0x100..0x660);
+}
+
+#[test]
+fn remove_rejects_an_inverted_range() {
+    let mut file = layout();
+    let inverted = Range { start: 0x200, end: 0x100 };
+    assert!(matches!(file.remove_bytes(inverted), Err(OffsetLayoutsError::InconsistentStructure(..))));
+    assert_eq!(range(&file, &[0, 0]), 0x100..0x300);
+}
+
+#[test]
+fn rebase_moves_everything_and_keeps_relative_ranges() {
+    let mut file = layout();
+    let report = file.rebase(0x1000).unwrap();
+
+    assert_eq!(file.get_absolute_range(), 0x1000..0x2000);
+    assert_eq!(range(&file, &[0, 0, 1]), 0x1200..0x1280);
+    assert_eq!(relative(&file, &[0, 0, 1]), 0x100..0x180);
+    assert_eq!(report.shifted, 6);
+    assert_eq!(found(&file, 0x1210), at(&[0, 0, 1]));
+
+    file.rebase(-0x1000).unwrap();
+    assert_eq!(file.get_absolute_range(), 0x0..0x1000);
+}
+
+#[test]
+fn edits_that_would_overflow_leave_the_tree_untouched() {
+    let mut file = layout();
+    assert!(matches!(file.rebase(-0x1), Err(OffsetLayoutsError::AddressOverflow(..))));
+    assert!(matches!(file.insert_bytes(0x800, u64::MAX), Err(OffsetLayoutsError::AddressOverflow(..))));
+    assert_eq!(file.get_absolute_range(), 0x0..0x1000);
+    assert_eq!(range(&file, &[0, 1]), 0x400..0x600);
+}
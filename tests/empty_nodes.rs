@@ -0,0 +1,118 @@
+use janus_array::{
+    CommandElements, Commands, Coordinates, DiskOffsets, File, LookupPolicy, NodeLevel, OffsetLayoutsError, Slices,
+    Termination,
+};
+
+use LookupPolicy::{Lenient, Strict};
+
+fn element(ordinal: usize, start: u64, end: u64) -> CommandElements {
+    let mut element = CommandElements::default();
+    element.populate_values(start, end, start, end, ordinal);
+    element
+}
+
+fn command(ordinal: usize, start: u64, end: u64, elements: Vec<CommandElements>) -> Commands {
+    let mut command = Commands::default();
+    command.populate_values(start, end, start, end, ordinal);
+    for e in elements {
+        command.add_element(e);
+    }
+    command
+}
+
+fn slice(ordinal: usize, start: u64, end: u64, commands: Vec<Commands>) -> Slices {
+    let mut slice = Slices::default();
+    slice.populate_values(start, end, start, end, ordinal);
+    for c in commands {
+        slice.add_command(c);
+    }
+    slice
+}
+
+// slice 0: one command with an element, one empty command
+// slice 1: no commands at all
+fn layout() -> File {
+    let mut file = File::with_size(0x1000);
+    file.add_slice(slice(
+        0,
+        0x0,
+        0x800,
+        vec![command(0, 0x0, 0x100, vec![element(0, 0x0, 0x10)]), command(1, 0x100, 0x200, vec![])],
+    ));
+    file.add_slice(slice(1, 0x800, 0x1000, vec![]));
+    file
+}
+
+fn is_inconsistent_at(result: Result<Coordinates, OffsetLayoutsError>, range: std::ops::Range<u64>) -> bool {
+    matches!(result, Err(OffsetLayoutsError::InconsistentStructure(_, r)) if r == range)
+}
+
+#[test]
+fn empty_file() {
+    let file = File::with_size(0x100);
+
+    let lookup = file.locate_with(0x10, Lenient).unwrap();
+    assert_eq!((lookup.level, lookup.termination), (NodeLevel::File, Termination::Leaf));
+    // A leaf, but not one with coordinates: coordinate lookups still miss
+    assert!(matches!(file.find_address(0x10), Err(OffsetLayoutsError::NotFound(0x10))));
+    assert!(matches!(file.find_address_with(0x10, Lenient), Err(OffsetLayoutsError::NotFound(0x10))));
+
+    assert!(is_inconsistent_at(file.find_address_with(0x10, Strict), 0x0..0x100));
+    // Out of range is reported first under both policies
+    for policy in [Lenient, Strict] {
+        assert!(matches!(file.find_address_with(0x200, policy), Err(OffsetLayoutsError::AddressOutsideCurrentScope(..))));
+    }
+}
+
+#[test]
+fn empty_slice() {
+    let file = layout();
+
+    let lookup = file.locate(0x900).unwrap();
+    assert_eq!(lookup.coordinates, Coordinates::from_ordinals(&[1]));
+    assert_eq!((lookup.level, lookup.termination), (NodeLevel::Slice, Termination::Leaf));
+    assert!(is_inconsistent_at(file.find_address_with(0x900, Strict), 0x800..0x1000));
+
+    // Same answers when asking the slice directly
+    let empty = &file.slices()[1];
+    assert_eq!(empty.find_address(0x900).unwrap(), Coordinates::from_ordinals(&[1]));
+    assert!(is_inconsistent_at(empty.find_address_with(0x900, Strict), 0x800..0x1000));
+}
+
+#[test]
+fn empty_command() {
+    let file = layout();
+
+    let lookup = file.locate(0x180).unwrap();
+    assert_eq!(lookup.coordinates, Coordinates::from_ordinals(&[0, 1]));
+    assert_eq!((lookup.level, lookup.termination), (NodeLevel::Command, Termination::Leaf));
+    // The error names the empty command, not its slice
+    assert!(is_inconsistent_at(file.find_address_with(0x180, Strict), 0x100..0x200));
+
+    let empty = file.slices()[0].get_command(1).unwrap();
+    assert_eq!(empty.find_address(0x180).unwrap().command, Some(1));
+    assert!(is_inconsistent_at(empty.find_address_with(0x180, Strict), 0x100..0x200));
+}
+
+#[test]
+fn complete_branches_pass_both_policies() {
+    let file = layout();
+    for policy in [Lenient, Strict] {
+        assert_eq!(file.find_address_with(0x8, policy).unwrap(), Coordinates::from_ordinals(&[0, 0, 0]));
+        // A gap is not an empty node
+        let lookup = file.locate_with(0x50, policy).unwrap();
+        assert_eq!(lookup.termination, Termination::AfterLast { previous: 0 });
+        let lookup = file.locate_with(0x300, policy).unwrap();
+        assert_eq!(lookup.termination, Termination::AfterLast { previous: 1 });
+    }
+}
+
+#[test]
+fn zero_length_empty_nodes() {
+    let mut file = File::with_size(0x100);
+    file.add_slice(slice(0, 0x0, 0x100, vec![command(0, 0x40, 0x40, vec![])]));
+
+    assert_eq!(file.find_address(0x40).unwrap(), Coordinates::from_ordinals(&[0, 0]));
+    assert!(is_inconsistent_at(file.find_address_with(0x40, Strict), 0x40..0x40));
+    assert_eq!(file.find_address_with(0x41, Strict).unwrap(), Coordinates::from_ordinals(&[0]));
+}
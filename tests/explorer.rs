@@ -0,0 +1,130 @@
+#![cfg(feature = "std")]
+
+use janus_array::explorer::{Explorer, ExplorerError};
+use janus_array::{CommandElements, Commands, DiskOffsets, File, Slices};
+
+// The layout of examples/explore.rs: two slices of four commands of eight
+// elements, 0x80 bytes each.
+fn layout() -> (File, Vec<u8>) {
+    let data: Vec<u8> = (0..0x2000u32).map(|i| (i % 251) as u8).collect();
+    let mut file = File::with_size(data.len() as u64);
+    for s in 0..2u64 {
+        let slice_start = s * 0x1000;
+        let mut slice = Slices::default();
+        slice.populate_values(slice_start, slice_start + 0x1000, slice_start, slice_start + 0x1000, s as usize);
+        for c in 0..4u64 {
+            let cmd_start = slice_start + c * 0x400;
+            let mut command = Commands::default();
+            command.populate_values(cmd_start, cmd_start + 0x400, c * 0x400, (c + 1) * 0x400, c as usize);
+            for e in 0..8u64 {
+                let el_start = cmd_start + e * 0x80;
+                let mut element = CommandElements::default();
+                element.populate_values(el_start, el_start + 0x80, e * 0x80, (e + 1) * 0x80, e as usize);
+                command.add_element(element);
+            }
+            slice.add_command(command);
+        }
+        file.add_slice(slice);
+    }
+    (file, data)
+}
+
+#[test]
+fn cd_ls_and_up_move_through_the_tree() {
+    let (file, _) = layout();
+    let mut explorer = Explorer::new(&file);
+
+    explorer.execute("cd 0/3").unwrap();
+    assert_eq!(explorer.execute("pwd").unwrap(), "/0/3");
+    let listing = explorer.execute("ls").unwrap();
+    let lines: Vec<&str> = listing.lines().collect();
+    assert_eq!(lines.len(), 9);
+    assert_eq!(lines[0], "[3] command  abs 0xc00..0x1000  rel 0xc00..0x1000  (8 children)");
+    assert_eq!(lines[1], "  [0] element  abs 0xc00..0xc80  rel 0x0..0x80  (0 children)");
+
+    explorer.execute("up").unwrap();
+    assert_eq!(explorer.execute("pwd").unwrap(), "/0");
+    explorer.execute("cd ../1/2/./5").unwrap();
+    let coords = explorer.current_coordinates();
+    assert_eq!((coords.slice, coords.command, coords.element), (Some(1), Some(2), Some(5)));
+    explorer.execute("cd /").unwrap();
+    assert_eq!(explorer.execute("pwd").unwrap(), "/");
+    explorer.execute("up").unwrap();
+    assert_eq!(explorer.execute("pwd").unwrap(), "/", "the file has no parent");
+}
+
+#[test]
+fn cd_rejects_missing_nodes_and_stays_put() {
+    let (file, _) = layout();
+    let mut explorer = Explorer::new(&file);
+    explorer.execute("cd 1").unwrap();
+
+    assert!(matches!(explorer.execute("cd 9"), Err(ExplorerError::BadPath(p)) if p == "9"));
+    assert!(matches!(explorer.execute("cd 0/0/0/0"), Err(ExplorerError::BadPath(_))));
+    assert!(matches!(explorer.execute("cd text"), Err(ExplorerError::BadPath(_))));
+    assert!(matches!(explorer.execute("cd"), Err(ExplorerError::MissingArgument("cd"))));
+    assert_eq!(explorer.execute("pwd").unwrap(), "/1");
+}
+
+#[test]
+fn find_prints_the_path_of_an_address() {
+    let (file, _) = layout();
+    let mut explorer = Explorer::new(&file);
+
+    assert_eq!(explorer.execute("find 0x1338").unwrap(), "0x1338 -> /1/0/6");
+    assert_eq!(explorer.execute("find 4920").unwrap(), "0x1338 -> /1/0/6");
+    assert!(matches!(explorer.execute("find 0x4738"), Err(ExplorerError::Lookup(_))));
+    assert!(matches!(explorer.execute("find zz"), Err(ExplorerError::BadAddress(_))));
+}
+
+#[test]
+fn hex_dumps_the_current_node() {
+    let (file, data) = layout();
+    let mut explorer = Explorer::new(&file);
+    assert!(matches!(explorer.execute("hex"), Err(ExplorerError::NoData)));
+
+    let mut explorer = Explorer::new(&file).with_data(&data);
+    explorer.execute("cd 0/0/1").unwrap();
+    let dump = explorer.execute("hex").unwrap();
+    // The element is 0x80 bytes long: eight rows of sixteen
+    assert_eq!(dump.lines().count(), 8);
+    assert!(dump.starts_with("00000080  80 81 82 83 84 85 86 87 88 89 8a 8b 8c 8d 8e 8f  |"));
+    assert_eq!(explorer.execute("hex 0x20").unwrap().lines().count(), 2);
+    assert_eq!(explorer.execute("hex 5").unwrap().lines().next().unwrap().split_whitespace().count(), 7);
+}
+
+#[test]
+fn history_lists_and_reruns_commands() {
+    let (file, _) = layout();
+    let mut explorer = Explorer::new(&file);
+    explorer.execute("cd 1").unwrap();
+    explorer.execute("pwd").unwrap();
+    assert!(explorer.execute("bogus").is_err());
+
+    assert_eq!(explorer.execute("history").unwrap(), "   0  cd 1\n   1  pwd\n   2  bogus\n   3  history");
+    explorer.execute("up").unwrap();
+    // Re-runs `cd 1` and records it as such
+    explorer.execute("!0").unwrap();
+    assert_eq!(explorer.execute("pwd").unwrap(), "/1");
+    assert_eq!(explorer.history()[5], "cd 1");
+
+    // An entry that doesn't exist fails and isn't recorded
+    let len = explorer.history().len();
+    assert!(matches!(explorer.execute("!99"), Err(ExplorerError::BadHistoryIndex(i)) if i == "99"));
+    assert!(matches!(explorer.execute("!x"), Err(ExplorerError::BadHistoryIndex(_))));
+    assert_eq!(explorer.history().len(), len);
+}
+
+#[test]
+fn run_reads_commands_until_quit() {
+    let (file, _) = layout();
+    let script = "cd 0/3\npwd\nnope\nquit\npwd\n";
+    let mut output = Vec::new();
+    Explorer::new(&file).run(script.as_bytes(), &mut output).unwrap();
+
+    let output = String::from_utf8(output).unwrap();
+    assert_eq!(
+        output,
+        "janus:/> janus:/0/3> /0/3\njanus:/0/3> error: unknown command `nope` (try `help`)\njanus:/0/3> \n"
+    );
+}
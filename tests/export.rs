@@ -0,0 +1,87 @@
+use janus_array::export::{to_dot, to_mermaid, ExportOptions};
+use janus_array::{CommandElements, Commands, Coordinates, DiskOffsets, File, Slices};
+
+// One slice holding a command with six elements and a childless command.
+fn layout() -> File {
+    let mut file = File::with_size(0x1000);
+    let mut slice = Slices::default();
+    slice.populate_values(0x0, 0x800, 0x0, 0x800, 0);
+    let mut command = Commands::default();
+    command.populate_values(0x0, 0x100, 0x0, 0x100, 0);
+    for i in 0..6u64 {
+        let mut element = CommandElements::default();
+        element.populate_values(i * 0x20, i * 0x20 + 0x10, i * 0x20, i * 0x20 + 0x10, i as usize);
+        command.add_element(element);
+    }
+    slice.add_command(command);
+    let mut empty = Commands::default();
+    empty.populate_values(0x100, 0x200, 0x100, 0x200, 1);
+    slice.add_command(empty);
+    file.add_slice(slice);
+    file
+}
+
+#[test]
+fn mermaid_lists_nodes_edges_and_highlights() {
+    let file = layout();
+    let options = ExportOptions { highlight_address: Some(0x44), ..Default::default() };
+    let mermaid = to_mermaid(&file, &options);
+
+    assert!(mermaid.starts_with("flowchart TD\n"));
+    assert!(mermaid.contains("    n_0_0_2[\"element 2<br/>abs 0x40..0x50<br/>rel 0x40..0x50\"]\n"));
+    assert!(mermaid.contains("    n_0_0 --> n_0_0_2\n"));
+    assert_eq!(mermaid.matches(" --> ").count(), 9);
+    assert!(mermaid.contains("    class n,n_0,n_0_0,n_0_0_2 highlight;\n"));
+    // Edges file -> slice, slice -> command and command -> element 2
+    assert_eq!(mermaid.matches("linkStyle").count(), 3);
+    // linkStyle counts edges in the order they are listed
+    let edges: Vec<&str> = mermaid.lines().filter(|l| l.contains(" --> ")).collect();
+    let index = edges.iter().position(|l| l.trim() == "n --> n_0").unwrap();
+    assert!(mermaid.contains(&format!("    linkStyle {} stroke", index)));
+}
+
+#[test]
+fn max_depth_hides_deeper_levels() {
+    let file = layout();
+    let options = ExportOptions { max_depth: Some(2), ..Default::default() };
+    let dot = to_dot(&file, &options);
+
+    assert!(dot.contains("n_0_0 [label=\"command 0\\nabs 0x0..0x100\\nrel 0x0..0x100\\n(6 children hidden)\"];"));
+    assert!(!dot.contains("n_0_0_0"));
+    // The childless command has nothing to hide
+    assert!(dot.contains("n_0_1 [label=\"command 1\\nabs 0x100..0x200\\nrel 0x100..0x200\"];"));
+
+    let only_file = to_mermaid(&file, &ExportOptions { max_depth: Some(0), ..Default::default() });
+    assert!(only_file.contains("(1 children hidden)"));
+    assert!(!only_file.contains("-->"));
+}
+
+#[test]
+fn long_leaf_runs_collapse_into_one_node() {
+    let file = layout();
+    let collapse = |limit| ExportOptions { collapse_leaf_runs: Some(limit), highlight_address: Some(0x44), ..Default::default() };
+
+    let dot = to_dot(&file, &collapse(3));
+    assert!(dot.contains("n_0_0_run0 [label=\"6 x element\\nordinals 0..=5\\nabs 0x0..0xb0\", style=filled"));
+    assert!(dot.contains("n_0_0 -> n_0_0_run0 [color="));
+    assert!(!dot.contains("n_0_0_1 "));
+    // The slice's commands don't form a run: one of them has children
+    assert!(dot.contains("n_0 -> n_0_0 [color=") && dot.contains("n_0 -> n_0_1;"));
+
+    // Runs no longer than the limit are drawn as they are
+    let dot = to_dot(&file, &collapse(6));
+    assert!(!dot.contains("_run"));
+    assert_eq!(dot.matches(" -> ").count(), 9);
+    assert_eq!(dot, to_dot(&file, &ExportOptions { highlight_address: Some(0x44), ..Default::default() }));
+}
+
+#[test]
+fn labels_are_escaped_for_each_format() {
+    let mut file = layout();
+    assert!(file.set_label_at(&Coordinates::from_ordinals(&[0]), "a \"b\" c\\d\ne <f>"));
+
+    let dot = to_dot(&file, &ExportOptions::default());
+    assert!(dot.contains("n_0 [label=\"slice 0 a \\\"b\\\" c\\\\d\\ne <f>\\nabs"));
+    let mermaid = to_mermaid(&file, &ExportOptions::default());
+    assert!(mermaid.contains("n_0[\"slice 0 a #quot;b#quot; c#92;d<br/>e #lt;f#gt;<br/>abs"));
+}
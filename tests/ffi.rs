@@ -0,0 +1,248 @@
+#![cfg(feature = "ffi")]
+
+use std::ffi::c_void;
+use std::ptr;
+
+use janus_array::ffi::*;
+
+// Header generation: `src/ffi.rs` is the single source of truth, the header
+// is derived from its declarations. Run with JANUS_BLESS=1 to rewrite it.
+
+const SOURCE: &str = include_str!("../src/ffi.rs");
+const HEADER_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/include/janus_array.h");
+
+fn c_type(rust: &str) -> String {
+    let rust = rust.trim();
+    if let Some(inner) = rust.strip_prefix("*const ") {
+        return format!("const {} *", c_type(inner));
+    }
+    if let Some(inner) = rust.strip_prefix("*mut ") {
+        return format!("{} *", c_type(inner));
+    }
+    match rust {
+        "u64" => "uint64_t",
+        "u32" => "uint32_t",
+        "i32" => "int32_t",
+        "usize" => "size_t",
+        "c_void" => "void",
+        other => other,
+    }
+    .to_string()
+}
+
+// `name: Type` to `CType name`, keeping the star next to the name.
+fn c_decl(param: &str) -> String {
+    let (name, ty) = param.split_once(':').expect("parameter without a type");
+    let ty = c_type(ty);
+    if ty.ends_with('*') {
+        format!("{}{}", ty, name.trim())
+    } else {
+        format!("{} {}", ty, name.trim())
+    }
+}
+
+fn c_params(params: &str) -> String {
+    let params: Vec<String> = params.split(',').map(str::trim).filter(|p| !p.is_empty()).map(c_decl).collect();
+    if params.is_empty() {
+        "void".to_string()
+    } else {
+        params.join(", ")
+    }
+}
+
+fn generate_header(source: &str) -> String {
+    let mut out = String::from(
+        "/* Generated from src/ffi.rs by tests/ffi.rs; do not edit. */\n\
+         #ifndef JANUS_ARRAY_H\n#define JANUS_ARRAY_H\n\n\
+         #include <stddef.h>\n#include <stdint.h>\n\n\
+         #ifdef __cplusplus\nextern \"C\" {\n#endif\n",
+    );
+    let mut docs: Vec<String> = Vec::new();
+    let mut in_safety = false;
+    let mut repr_c = false;
+    let mut exported = false;
+    let mut lines = source.lines();
+
+    while let Some(line) = lines.next() {
+        let line = line.trim();
+        if let Some(doc) = line.strip_prefix("///") {
+            let doc = doc.trim();
+            in_safety |= doc == "# Safety";
+            if !in_safety {
+                docs.push(doc.to_string());
+            }
+            continue;
+        }
+        let comment: String = docs
+            .iter()
+            .filter(|d| !d.is_empty())
+            .map(|d| format!("// {}\n", d))
+            .collect();
+
+        if line == "#[repr(C)]" {
+            repr_c = true;
+            continue;
+        } else if line == "#[no_mangle]" {
+            exported = true;
+            continue;
+        } else if line.starts_with("#[") {
+            continue;
+        }
+
+        if let Some(rest) = line.strip_prefix("pub const ") {
+            let (name, value) = rest.split_once(':').unwrap();
+            let value = value.split_once('=').unwrap().1.trim_end_matches(';').trim();
+            out.push_str(&format!("{}#define {} {}\n", comment, name.trim(), value));
+        } else if let Some(rest) = line.strip_prefix("pub type ") {
+            let (name, ty) = rest.split_once('=').unwrap();
+            let ty = ty.trim().trim_end_matches(';');
+            let name = name.trim();
+            if let Some(func) = ty.strip_prefix("Option<unsafe extern \"C\" fn(") {
+                let (params, ret) = func.trim_end_matches('>').split_once(") -> ").unwrap();
+                out.push_str(&format!("\n{}typedef {} (*{})({});\n", comment, c_type(ret), name, c_params(params)));
+            } else {
+                out.push_str(&format!("\n{}typedef {} {};\n\n", comment, c_type(ty), name));
+            }
+        } else if let Some(rest) = line.strip_prefix("pub struct ") {
+            let name = rest.trim_end_matches('{').trim();
+            let mut fields = Vec::new();
+            for field in lines.by_ref() {
+                let field = field.trim();
+                if field == "}" {
+                    break;
+                }
+                if let Some(field) = field.strip_prefix("pub ") {
+                    fields.push(format!("    {};\n", c_decl(field.trim_end_matches(','))));
+                }
+            }
+            if repr_c {
+                out.push_str(&format!("\n{}typedef struct {} {{\n{}}} {};\n", comment, name, fields.concat(), name));
+            } else {
+                out.push_str(&format!("\n{}typedef struct {} {};\n", comment, name, name));
+            }
+        } else if exported && line.contains("extern \"C\" fn ") {
+            // Signatures may span several lines, up to the opening brace
+            let mut signature = line.to_string();
+            while !signature.ends_with('{') {
+                signature.push_str(lines.next().unwrap().trim());
+            }
+            let rest = signature.split_once("fn ").unwrap().1;
+            let (name, rest) = rest.split_once('(').unwrap();
+            let (params, ret) = rest.rsplit_once(')').unwrap();
+            let ret = ret.trim_end_matches('{').trim().strip_prefix("->").unwrap_or("c_void");
+            let name = c_decl(&format!("{}: {}", name, ret.trim()));
+            out.push_str(&format!("\n{}{}({});\n", comment, name, c_params(params)));
+        }
+
+        if !line.is_empty() {
+            docs.clear();
+            in_safety = false;
+            repr_c = false;
+            exported = false;
+        }
+    }
+    out.push_str("\n#ifdef __cplusplus\n}\n#endif\n\n#endif /* JANUS_ARRAY_H */\n");
+    out
+}
+
+#[test]
+fn header_is_up_to_date() {
+    let header = generate_header(SOURCE);
+    if std::env::var_os("JANUS_BLESS").is_some() {
+        std::fs::write(HEADER_PATH, &header).unwrap();
+    }
+    let committed = std::fs::read_to_string(HEADER_PATH).unwrap_or_default();
+    assert!(committed == header, "include/janus_array.h is stale; rerun with JANUS_BLESS=1 and --features ffi");
+}
+
+// Behaviour, driven through the extern functions only.
+
+struct Handle(*mut JanusFile);
+
+impl Drop for Handle {
+    fn drop(&mut self) {
+        unsafe { janus_file_free(self.0) }
+    }
+}
+
+fn sample() -> Handle {
+    let file = Handle(janus_file_new(0x1000));
+    unsafe {
+        // Out of order on purpose
+        assert_eq!(janus_add_element(file.0, 0x120, 0x10, 300), JANUS_OK);
+        assert_eq!(janus_add_command(file.0, 0x100, 0x100, 200), JANUS_OK);
+        assert_eq!(janus_add_command(file.0, 0x0, 0x100, 201), JANUS_OK);
+        assert_eq!(janus_add_slice(file.0, 0x0, 0x800, 100), JANUS_OK);
+        assert_eq!(janus_finalize(file.0, ptr::null_mut()), JANUS_OK);
+    }
+    file
+}
+
+#[test]
+fn find_address_returns_coordinates_and_tags() {
+    let file = sample();
+    unsafe {
+        let found = janus_find_address(file.0, 0x125);
+        assert_eq!(found.status, JANUS_OK);
+        assert_eq!((found.depth, found.slice, found.command, found.element), (3, 0, 1, 0));
+        assert_eq!(found.tag, 300);
+
+        let found = janus_find_address(file.0, 0x40);
+        assert_eq!((found.depth, found.command, found.tag), (2, 0, 201));
+
+        assert_eq!(janus_find_address(file.0, 0x900).status, JANUS_NOT_FOUND);
+        assert_eq!(janus_find_address(file.0, 0x2000).status, JANUS_OUT_OF_RANGE);
+        assert_eq!(janus_find_address(ptr::null(), 0).status, JANUS_NULL_POINTER);
+    }
+}
+
+#[test]
+fn lifecycle_is_enforced() {
+    let file = Handle(janus_file_new(0x100));
+    unsafe {
+        assert_eq!(janus_add_slice(file.0, 0, 0x100, 1), JANUS_OK);
+        assert_eq!(janus_find_address(file.0, 0).status, JANUS_NOT_FINALIZED);
+        assert_eq!(janus_add_slice(file.0, u64::MAX, 2, 2), JANUS_OVERFLOW);
+        assert_eq!(janus_add_command(file.0, 0x200, 0x10, 3), JANUS_OK);
+
+        let mut unplaced = 0usize;
+        assert_eq!(janus_finalize(file.0, &mut unplaced), JANUS_UNPLACED);
+        assert_eq!(unplaced, 1);
+        assert_eq!(janus_finalize(file.0, ptr::null_mut()), JANUS_FINALIZED);
+        assert_eq!(janus_add_slice(file.0, 0, 0x10, 4), JANUS_FINALIZED);
+        assert_eq!(janus_add_slice(ptr::null_mut(), 0, 0x10, 4), JANUS_NULL_POINTER);
+        janus_file_free(ptr::null_mut());
+    }
+}
+
+unsafe extern "C" fn collect(node: *const JanusNode, user_data: *mut c_void) -> i32 {
+    let nodes = &mut *(user_data as *mut Vec<JanusNode>);
+    nodes.push(*node);
+    0
+}
+
+unsafe extern "C" fn stop_at_commands(node: *const JanusNode, user_data: *mut c_void) -> i32 {
+    let visited = &mut *(user_data as *mut usize);
+    *visited += 1;
+    ((*node).depth == 2) as i32
+}
+
+#[test]
+fn iteration_visits_nodes_depth_first() {
+    let file = sample();
+    unsafe {
+        let mut nodes: Vec<JanusNode> = Vec::new();
+        let status = janus_for_each_node(file.0, Some(collect), &mut nodes as *mut Vec<JanusNode> as *mut c_void);
+        assert_eq!(status, JANUS_OK);
+        let tags: Vec<u64> = nodes.iter().map(|n| n.tag).collect();
+        assert_eq!(tags, [100, 201, 200, 300]);
+        assert_eq!((nodes[3].start, nodes[3].end, nodes[3].depth), (0x120, 0x130, 3));
+
+        let mut visited = 0usize;
+        let status = janus_for_each_node(file.0, Some(stop_at_commands), &mut visited as *mut usize as *mut c_void);
+        assert_eq!(status, JANUS_STOPPED);
+        assert_eq!(visited, 2);
+
+        assert_eq!(janus_for_each_node(file.0, None, ptr::null_mut()), JANUS_NULL_POINTER);
+    }
+}
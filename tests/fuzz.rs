@@ -0,0 +1,248 @@
+//! Fuzz targets, runnable offline.
+//!
+//! Each `fuzz_*` function takes raw bytes like a `cargo fuzz` target and
+//! panics on any broken invariant; to run one under libFuzzer, wrap it in
+//! `fuzz_target!(|data: &[u8]| fuzz_builder(data))`. Here they are fed a
+//! small corpus plus pseudo-random inputs: `JANUS_FUZZ_ITERATIONS` sets how
+//! many (default 2000) and `JANUS_FUZZ_SEED` where they start. A failing input
+//! is printed in hex before the panic propagates.
+
+#![cfg(feature = "std")]
+
+use std::env;
+use std::panic::{self, AssertUnwindSafe};
+
+use janus_array::builder::{AmbiguityPolicy, BuildOptions, LayoutBuilder, OrphanReason, StraddlePolicy};
+use janus_array::versioned::{Snapshot, VersionedFile};
+use janus_array::{AddressRange, Commands, Coordinates, DiskOffsets, File, NodeLevel, NodeRef, Slices};
+
+const DEFAULT_SEED: u64 = 0x6675_7a7a;
+const DEFAULT_ITERATIONS: u64 = 2000;
+
+// Reads the input front to back, yielding zeros once it runs out.
+struct Input<'a>(&'a [u8]);
+
+impl Input<'_> {
+    fn byte(&mut self) -> u8 {
+        match self.0.split_first() {
+            Some((&b, rest)) => {
+                self.0 = rest;
+                b
+            }
+            None => 0,
+        }
+    }
+
+    fn word(&mut self) -> u64 {
+        u64::from(self.byte()) << 8 | u64::from(self.byte())
+    }
+
+    fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+fn level(byte: u8) -> NodeLevel {
+    match byte % 4 {
+        0 => NodeLevel::File,
+        1 => NodeLevel::Slice,
+        2 => NodeLevel::Command,
+        _ => NodeLevel::Element,
+    }
+}
+
+/// Records in any order, overlapping, straddling or outside the file: the
+/// builder must place or orphan each one and produce a well-formed tree.
+fn fuzz_builder(data: &[u8]) {
+    let mut input = Input(data);
+    let flags = input.byte();
+    let options = BuildOptions {
+        straddling: if flags & 1 == 0 { StraddlePolicy::Orphan } else { StraddlePolicy::Clip },
+        ambiguous: if flags & 2 == 0 { AmbiguityPolicy::KeepFirst } else { AmbiguityPolicy::KeepLarger },
+    };
+    let file_size = input.word() + 1;
+    let mut builder = LayoutBuilder::new(file_size).with_options(options);
+    let mut records = Vec::new();
+    while !input.is_empty() {
+        let kind = input.byte();
+        let start = input.word();
+        let len = u64::from(input.byte()) << (kind >> 5);
+        let range = start..start + len;
+        builder.push(level(kind), range.clone(), records.len()).unwrap();
+        records.push((level(kind), range));
+    }
+
+    let built = builder.finish();
+    assert_eq!(built.payloads.len() + built.orphans.len(), records.len());
+    for orphan in &built.orphans {
+        assert_eq!(orphan.reason == OrphanReason::RootLevel, orphan.level == NodeLevel::File);
+        assert_eq!(records[orphan.payload].1, orphan.range.to_range());
+    }
+
+    check_tree(&NodeRef::File(&built.file));
+    for (coords, index) in &built.payloads {
+        let (level, range) = &records[*index];
+        let node = built.file.resolve(coords).expect("placed records resolve");
+        assert_eq!(node.level(), *level);
+        let placed = node.absolute_range();
+        match options.straddling {
+            StraddlePolicy::Orphan => assert_eq!(placed, *range),
+            StraddlePolicy::Clip => assert!(range.start <= placed.start && placed.end <= range.end),
+        }
+        if !node.has_children() && placed.start < placed.end {
+            assert_eq!(built.file.find_address(placed.start).ok().as_ref(), Some(coords));
+            assert_eq!(built.file.find_address(placed.end - 1).ok().as_ref(), Some(coords));
+        }
+    }
+}
+
+// Children inside their parent, in address order, numbered in that order,
+// not overlapping, with relative ranges matching the absolute ones.
+fn check_tree(node: &NodeRef<'_>) {
+    let parent = node.absolute_span();
+    let mut previous_end = parent.start();
+    for (index, child) in node.children().iter().enumerate() {
+        let span = child.absolute_span();
+        assert_eq!(child.ordinal(), index);
+        assert!(parent.contains_range(&span) || (span.is_empty() && span.start() == parent.start()));
+        if !span.is_empty() {
+            assert!(span.start() >= previous_end, "{} overlaps a sibling in {}", span, parent);
+            previous_end = span.end().unwrap_or(u64::MAX);
+        }
+        assert_eq!(node.absolute_to_relative(span.start()).ok(), Some(child.relative_span().start()));
+        assert_eq!(child.relative_span().len(), span.len());
+        check_tree(child);
+    }
+}
+
+/// Edits published while readers hold snapshots: a snapshot must keep
+/// answering exactly as when it was taken.
+fn fuzz_snapshot_reader(data: &[u8]) {
+    let mut input = Input(data);
+    let layout = VersionedFile::new(File::with_size(0x10000));
+    let mut held: Vec<(Snapshot, Vec<String>)> = Vec::new();
+    let mut updates = 0;
+    let probes: Vec<u64> = (0..16).map(|i| i * 0x1001).collect();
+    let answers = |file: &File| -> Vec<String> { probes.iter().map(|&a| format!("{:?}", file.find_address(a))).collect() };
+
+    while !input.is_empty() {
+        match input.byte() % 4 {
+            0 => {
+                let start = input.word();
+                let end = (start + u64::from(input.byte()) * 0x40).min(0x10000);
+                let ordinal = layout.snapshot().slices().len();
+                layout.update(|f| f.add_slice(Slices::with_spans(AddressRange::new(start, end).unwrap(), AddressRange::new(start, end).unwrap(), ordinal)));
+                updates += 1;
+            }
+            1 => {
+                let slice = usize::from(input.byte());
+                let (offset, len) = (input.word(), u64::from(input.byte()));
+                layout.update(|f| {
+                    f.update_slice(slice, |s| {
+                        let span = s.get_absolute_span();
+                        let start = (span.start() + offset).min(span.end_wide() as u64);
+                        let end = (start + len).min(span.end_wide() as u64);
+                        let ordinal = s.commands().len();
+                        s.add_command(Commands::with_spans(
+                            AddressRange::new(start, end).unwrap(),
+                            AddressRange::new(start - span.start(), end - span.start()).unwrap(),
+                            ordinal,
+                        ));
+                    })
+                });
+                updates += 1;
+            }
+            2 => {
+                let slice = usize::from(input.byte());
+                layout.update(|f| f.remove_slice(slice));
+                updates += 1;
+            }
+            _ => {
+                let snapshot = layout.snapshot();
+                let seen = answers(&snapshot);
+                held.push((snapshot, seen));
+            }
+        }
+    }
+    for (snapshot, seen) in &held {
+        assert_eq!(&answers(snapshot), seen, "snapshot {} changed", snapshot.version());
+    }
+    assert_eq!(layout.version(), updates);
+    assert!(held.windows(2).all(|w| w[0].0.version() <= w[1].0.version()));
+}
+
+/// Any text: parsing never panics and what parses prints back the same.
+fn fuzz_coordinates(data: &[u8]) {
+    let text = String::from_utf8_lossy(data);
+    let Ok(coords) = text.parse::<Coordinates>() else {
+        return;
+    };
+    assert_eq!(coords.to_string().parse::<Coordinates>().as_ref(), Ok(&coords));
+    let mut current = coords.clone();
+    while let Some(parent) = current.parent() {
+        assert!(parent.is_ancestor_of(&coords));
+        assert!(parent < current);
+        current = parent;
+    }
+    assert_eq!(current, Coordinates::new());
+}
+
+fn run(target: fn(&[u8]), corpus: &[&[u8]]) {
+    let seed = env::var("JANUS_FUZZ_SEED").ok().and_then(|s| s.parse().ok()).unwrap_or(DEFAULT_SEED);
+    let iterations = env::var("JANUS_FUZZ_ITERATIONS").ok().and_then(|s| s.parse().ok()).unwrap_or(DEFAULT_ITERATIONS);
+
+    let mut state = seed;
+    let mut next = move || {
+        // xorshift64*
+        state ^= state >> 12;
+        state ^= state << 25;
+        state ^= state >> 27;
+        state.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    };
+    let generated = (0..iterations).map(|_| {
+        // Up to 255 bytes, short inputs being the most common
+        let len = (next() % 256) as usize >> (next() % 8);
+        // Also mostly small bytes, so ranges land near each other, and bytes
+        // spelling paths, so parsers get past the first character
+        match next() % 3 {
+            0 => (0..len).map(|_| next() as u8).collect::<Vec<u8>>(),
+            1 => (0..len).map(|_| next() as u8 & 0x0f).collect(),
+            _ => (0..len).map(|_| b"0123456789/!"[(next() % 12) as usize]).collect(),
+        }
+    });
+    for input in corpus.iter().map(|c| c.to_vec()).chain(generated) {
+        if let Err(panic) = panic::catch_unwind(AssertUnwindSafe(|| target(&input))) {
+            let hex: Vec<String> = input.iter().map(|b| format!("{:02x}", b)).collect();
+            eprintln!("failing input: {}", hex.join(""));
+            panic::resume_unwind(panic);
+        }
+    }
+}
+
+#[test]
+fn builder() {
+    run(
+        fuzz_builder,
+        &[
+            b"",
+            &[0xff; 64],
+            // Identical records at every level, then one straddling a slice
+            &[0, 0x10, 0, 1, 0, 0, 0x80, 2, 0, 0, 0x40, 3, 0, 0, 0x20, 1, 0, 0, 0x80, 2, 0, 0x70, 0x40],
+            // A record ending past the file and the end of the file itself
+            &[1, 0, 0xff, 1, 0, 0xf0, 0xff, 1, 0, 0x80, 0xff],
+        ],
+    );
+}
+
+#[test]
+fn snapshot_reader() {
+    run(
+        fuzz_snapshot_reader,
+        &[b"", &[3, 0, 0, 0, 0xff, 3, 1, 0, 0, 0x10, 0x20, 3, 2, 0, 3, 2, 0, 3]],
+    );
+}
+
+#[test]
+fn coordinates_parser() {
+    run(fuzz_coordinates, &[b"", b"/", b"0/1/2", b"0/2!0/1!3", b"!", b"0/1/2/3", b"!!!", b"18446744073709551616"]);
+}
@@ -0,0 +1,103 @@
+use janus_array::builder::LayoutBuilder;
+#[cfg(feature = "std")]
+use janus_array::explorer::Explorer;
+#[cfg(feature = "std")]
+use janus_array::export::{to_dot, ExportOptions};
+use janus_array::{Coordinates, File, Labeled, NodeLevel};
+
+// A fat Mach-O: two architectures with the same segment and section names.
+fn fat_macho() -> File {
+    let mut builder = LayoutBuilder::new(0x20000);
+    for (arch, base) in [("x86_64", 0x0), ("arm64", 0x10000)] {
+        builder.push(NodeLevel::Slice, base..base + 0x10000, arch).unwrap();
+        builder.push(NodeLevel::Command, base..base + 0x8000, "__TEXT").unwrap();
+        builder.push(NodeLevel::Command, base + 0x8000..base + 0x10000, "__DATA").unwrap();
+        builder.push(NodeLevel::Element, base + 0x1000..base + 0x4000, "__text").unwrap();
+        builder.push(NodeLevel::Element, base + 0x4000..base + 0x4800, "__cstring").unwrap();
+    }
+    let built = builder.finish();
+    assert!(built.orphans.is_empty());
+
+    let mut file = built.file;
+    for (coords, label) in built.payloads {
+        assert!(file.set_label_at(&coords, label));
+        let kind = match coords.ordinals().len() {
+            1 => "architecture",
+            2 => "segment",
+            _ => "section",
+        };
+        assert!(file.set_kind_at(&coords, kind));
+    }
+    file
+}
+
+#[test]
+fn lookups_return_label_paths() {
+    let file = fat_macho();
+
+    assert_eq!(file.find_address_path(0x1800).unwrap(), "x86_64/__TEXT/__text");
+    assert_eq!(file.find_address_path(0x14100).unwrap(), "arm64/__TEXT/__cstring");
+    assert_eq!(file.find_address_path(0x19000).unwrap(), "arm64/__DATA");
+    // Unlabeled levels fall back to the ordinal; the file itself has no segment
+    assert_eq!(file.label_path(&Coordinates::default()).unwrap(), "");
+}
+
+#[test]
+fn duplicate_labels_resolve_in_document_order() {
+    let file = fat_macho();
+
+    let first = file.find_by_label("__text").unwrap();
+    assert_eq!(first.ordinals(), vec![0, 0, 0]);
+    assert_eq!(file.find_all_by_label("__text").len(), 2);
+    assert_eq!(file.path_of_label("__cstring").unwrap(), "x86_64/__TEXT/__cstring");
+    assert!(file.find_by_label("__LINKEDIT").is_none());
+
+    // A full path picks the right one
+    let arm = file.resolve_label_path("arm64/__TEXT/__text").unwrap();
+    assert_eq!(arm.ordinals(), vec![1, 0, 0]);
+    assert_eq!(file.resolve_label_path("1/0/__text").unwrap().ordinals(), arm.ordinals());
+    assert!(file.resolve_label_path("arm64/__TEXT/__bss").is_none());
+}
+
+#[test]
+fn label_paths_round_trip() {
+    let file = fat_macho();
+    for address in [0x0, 0x1800, 0x4400, 0x9000, 0x11800, 0x1ffff] {
+        let coords = file.find_address_path(address).unwrap();
+        let resolved = file.resolve_label_path(&coords).unwrap();
+        assert_eq!(file.label_path(&resolved).unwrap(), coords);
+    }
+}
+
+#[test]
+fn tags_survive_clones_and_can_be_cleared() {
+    let mut file = fat_macho();
+    let snapshot = file.clone();
+    let text = file.find_by_label("__TEXT").unwrap();
+
+    file.update_slice(0, |slice| slice.update_command(0, |command| command.clear_tags()));
+    assert_eq!(file.label_path(&text).unwrap(), "x86_64/0");
+    assert_eq!(snapshot.label_path(&text).unwrap(), "x86_64/__TEXT");
+    assert_eq!(snapshot.slices()[0].kind(), Some("architecture"));
+
+    assert!(!file.set_label_at(&Coordinates::from_ordinals(&[7]), "nope"));
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn explorer_and_export_show_labels() {
+    let file = fat_macho();
+    let mut explorer = Explorer::new(&file);
+
+    explorer.execute("cd arm64/__TEXT").unwrap();
+    assert_eq!(explorer.execute("pwd").unwrap(), "/1/0");
+    assert!(explorer.execute("ls").unwrap().contains("__cstring <section>"));
+    assert_eq!(explorer.execute("find 0x1800").unwrap(), "0x1800 -> /0/0/0 (x86_64/__TEXT/__text)");
+    assert!(explorer.execute("cd /x86_64/__LINKEDIT").is_err());
+    assert_eq!(
+        explorer.execute("find 0x4900").unwrap(),
+        "0x4900 -> /0/0 (x86_64/__TEXT), unclaimed: command after 1"
+    );
+
+    assert!(to_dot(&file, &ExportOptions::default()).contains("slice 1 arm64"));
+}
@@ -0,0 +1,73 @@
+use janus_array::builder::LayoutBuilder;
+use janus_array::{Coordinates, DiskOffsets, File, NodeLevel, OffsetLayoutsError, Termination};
+
+// Gaps at every level:
+// slice 0 0x100..0x800, commands 0x200..0x300 and 0x400..0x500,
+//   elements 0x420..0x440 and 0x460..0x480 in the second command
+// slice 1 0x900..0xa00, one command 0x900..0xa00 without elements
+fn layout() -> File {
+    let mut builder = LayoutBuilder::new(0x1000);
+    builder.push(NodeLevel::Slice, 0x100..0x800, ()).unwrap();
+    builder.push(NodeLevel::Slice, 0x900..0xa00, ()).unwrap();
+    builder.push(NodeLevel::Command, 0x200..0x300, ()).unwrap();
+    builder.push(NodeLevel::Command, 0x400..0x500, ()).unwrap();
+    builder.push(NodeLevel::Command, 0x900..0xa00, ()).unwrap();
+    builder.push(NodeLevel::Element, 0x420..0x440, ()).unwrap();
+    builder.push(NodeLevel::Element, 0x460..0x480, ()).unwrap();
+    builder.finish().file
+}
+
+fn stop(file: &File, address: u64) -> (String, NodeLevel, Termination) {
+    let lookup = file.locate(address).unwrap();
+    (lookup.coordinates.to_string(), lookup.level, lookup.termination)
+}
+
+#[test]
+fn leaves_at_every_depth() {
+    let file = layout();
+    assert_eq!(stop(&file, 0x430), ("0/1/0".to_string(), NodeLevel::Element, Termination::Leaf));
+    assert_eq!(stop(&file, 0x250), ("0/0".to_string(), NodeLevel::Command, Termination::Leaf));
+    assert_eq!(stop(&file, 0x950), ("1/0".to_string(), NodeLevel::Command, Termination::Leaf));
+}
+
+#[test]
+fn gaps_inside_a_command() {
+    let file = layout();
+    assert_eq!(stop(&file, 0x400), ("0/1".to_string(), NodeLevel::Command, Termination::BeforeFirst { next: 0 }));
+    assert_eq!(
+        stop(&file, 0x450),
+        ("0/1".to_string(), NodeLevel::Command, Termination::Gap { previous: 0, next: 1 })
+    );
+    assert_eq!(stop(&file, 0x480), ("0/1".to_string(), NodeLevel::Command, Termination::AfterLast { previous: 1 }));
+
+    // find_address can't tell these apart from a leaf
+    assert_eq!(file.find_address(0x450).unwrap(), Coordinates::from_ordinals(&[0, 1]));
+}
+
+#[test]
+fn gaps_inside_a_slice_and_the_file() {
+    let file = layout();
+    assert_eq!(stop(&file, 0x100), ("0".to_string(), NodeLevel::Slice, Termination::BeforeFirst { next: 0 }));
+    assert_eq!(stop(&file, 0x350), ("0".to_string(), NodeLevel::Slice, Termination::Gap { previous: 0, next: 1 }));
+    assert_eq!(stop(&file, 0x7ff), ("0".to_string(), NodeLevel::Slice, Termination::AfterLast { previous: 1 }));
+
+    assert_eq!(stop(&file, 0x0), (String::new(), NodeLevel::File, Termination::BeforeFirst { next: 0 }));
+    assert_eq!(stop(&file, 0x850), (String::new(), NodeLevel::File, Termination::Gap { previous: 0, next: 1 }));
+    assert_eq!(stop(&file, 0xfff), (String::new(), NodeLevel::File, Termination::AfterLast { previous: 1 }));
+    // Still an error for find_address
+    assert!(matches!(file.find_address(0x850), Err(OffsetLayoutsError::NotFound(0x850))));
+}
+
+#[test]
+fn find_address_agrees_with_locate() {
+    let file = layout();
+    for address in (0..0x1000).step_by(8) {
+        let lookup = file.locate(address).unwrap();
+        match file.find_address(address) {
+            Ok(coords) => assert_eq!(coords, lookup.coordinates),
+            Err(_) => assert_eq!(lookup.coordinates, Coordinates::new()),
+        }
+        assert_eq!(lookup.level as usize, lookup.coordinates.depth());
+    }
+    assert!(file.locate(0x1000).is_err());
+}
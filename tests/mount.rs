@@ -0,0 +1,181 @@
+use std::sync::Arc;
+
+use janus_array::builder::LayoutBuilder;
+use janus_array::{
+    Coordinates, DiskOffsets, File, Labeled, LookupPolicy, Mountable, NodeLevel, OffsetLayoutsError, Termination,
+};
+
+// An object file: one segment with two sections.
+fn member() -> File {
+    let mut builder = LayoutBuilder::new(0x200);
+    builder.push(NodeLevel::Slice, 0x0..0x100, ()).unwrap();
+    builder.push(NodeLevel::Command, 0x20..0x60, ()).unwrap();
+    builder.push(NodeLevel::Command, 0x60..0x100, ()).unwrap();
+    let mut file = builder.finish().file;
+    file.set_label_at(&Coordinates::from_ordinals(&[0]), "__TEXT");
+    file.set_label_at(&Coordinates::from_ordinals(&[0, 1]), "__text");
+    file
+}
+
+// An archive: a header and two members, the second one 0x100 bytes longer
+// than the object mounted on it.
+fn archive() -> File {
+    let mut builder = LayoutBuilder::new(0x1000);
+    builder.push(NodeLevel::Slice, 0x0..0x1000, ()).unwrap();
+    builder.push(NodeLevel::Command, 0x0..0x40, ()).unwrap();
+    builder.push(NodeLevel::Command, 0x100..0x300, ()).unwrap();
+    builder.push(NodeLevel::Command, 0x400..0x700, ()).unwrap();
+    let mut file = builder.finish().file;
+    let member = Arc::new(member());
+    assert!(file.mount_at(&Coordinates::from_ordinals(&[0, 1]), Arc::clone(&member)));
+    assert!(file.mount_at(&Coordinates::from_ordinals(&[0, 2]), member));
+    file.set_label_at(&Coordinates::from_ordinals(&[0]), "archive");
+    file.set_label_at(&Coordinates::from_ordinals(&[0, 2]), "b.o");
+    file
+}
+
+#[test]
+fn lookups_descend_into_mounted_files() {
+    let file = archive();
+
+    let coords = file.find_address(0x470).unwrap();
+    assert_eq!(coords.ordinals(), vec![0, 2]);
+    assert_eq!(coords.inner(), Some(&Coordinates::from_ordinals(&[0, 1])));
+    assert_eq!(coords.to_string(), "0/2!0/1");
+    assert_eq!(file.resolve(&coords).unwrap().absolute_range(), 0x60..0x100);
+
+    // Both mounts share the layout, each in its own place
+    assert_eq!(file.find_address(0x130).unwrap().to_string(), "0/1!0/0");
+    assert_eq!(file.find_address(0x10).unwrap().to_string(), "0/0");
+}
+
+#[test]
+fn composite_coordinates_round_trip() {
+    let file = archive();
+    let coords = file.find_address(0x470).unwrap();
+
+    assert_eq!(coords.to_string().parse::<Coordinates>().unwrap(), coords);
+    assert_eq!("/0/2!0".parse::<Coordinates>().unwrap().depth(), 2);
+    assert!("0/2!0!1/2/3/4".parse::<Coordinates>().is_err());
+
+    let parent = coords.parent().unwrap();
+    assert_eq!(parent.to_string(), "0/2!0");
+    // The root of the mounted file sits between the two layouts
+    let root = parent.parent().unwrap();
+    assert_eq!(root.to_string(), "0/2!");
+    assert_eq!(root.parent().unwrap().to_string(), "0/2");
+    assert!(parent.is_ancestor_of(&coords));
+    assert!(Coordinates::from_ordinals(&[0]).is_ancestor_of(&coords));
+    assert!(!Coordinates::from_ordinals(&[0, 1]).is_ancestor_of(&coords));
+
+    let outer = Coordinates::from_ordinals(&[0, 2]);
+    assert!(outer < coords && coords < Coordinates::from_ordinals(&[0, 3]));
+}
+
+#[test]
+fn label_paths_cross_mount_points() {
+    let file = archive();
+
+    assert_eq!(file.find_address_path(0x470).unwrap(), "archive/b.o!__TEXT/__text");
+    assert_eq!(file.find_address_path(0x170).unwrap(), "archive/1!__TEXT/__text");
+    let coords = file.resolve_label_path("archive/b.o!__TEXT/__text").unwrap();
+    assert_eq!(coords, file.find_address(0x470).unwrap());
+    assert!(file.resolve_label_path("archive/0!__TEXT").is_none());
+}
+
+#[test]
+fn nodes_inside_mounts_can_be_edited() {
+    let mut file = archive();
+    let coords: Coordinates = "0/2!0/0".parse().unwrap();
+
+    assert!(file.set_label_at(&coords, "__stubs"));
+    assert_eq!(file.find_address_path(0x430).unwrap(), "archive/b.o!__TEXT/__stubs");
+    // Copy on write: the other member keeps its own labels
+    assert_eq!(file.find_address_path(0x130).unwrap(), "archive/1!__TEXT/0");
+
+    assert!(!file.set_label_at(&"0/0!0".parse().unwrap(), "nothing mounted"));
+    assert!(!file.mount_at(&Coordinates::new(), member()));
+}
+
+#[test]
+fn bytes_past_the_mounted_file_stay_with_the_mount_point() {
+    let file = archive();
+
+    // 0x650 is 0x250 bytes into member b.o, which is only 0x200 long
+    let lookup = file.locate(0x650).unwrap();
+    assert_eq!(lookup.coordinates.to_string(), "0/2");
+    assert_eq!(lookup.level, NodeLevel::Command);
+    assert!(lookup.is_leaf());
+
+    // Inside the member but outside its slices: the gap is the member's
+    let lookup = file.locate(0x580).unwrap();
+    assert_eq!(lookup.coordinates.to_string(), "0/2!");
+    assert_eq!(lookup.level, NodeLevel::File);
+    assert_eq!(lookup.termination, Termination::AfterLast { previous: 0 });
+    assert!(file.find_address(0x580).is_ok());
+}
+
+#[test]
+fn mounted_nodes_are_not_empty_under_the_strict_policy() {
+    let mut command = janus_array::Commands::default();
+    command.populate_values(0x0, 0x200, 0x0, 0x200, 0);
+    assert!(matches!(
+        command.find_address_with(0x10, LookupPolicy::Strict),
+        Err(OffsetLayoutsError::InconsistentStructure(0x10, _))
+    ));
+
+    let mut builder = LayoutBuilder::new(0x100);
+    builder.push(NodeLevel::Slice, 0x0..0x100, ()).unwrap();
+    builder.push(NodeLevel::Command, 0x20..0x60, ()).unwrap();
+    builder.push(NodeLevel::Element, 0x20..0x40, ()).unwrap();
+    builder.push(NodeLevel::Command, 0x60..0x100, ()).unwrap();
+    command.mount(builder.finish().file);
+    let coords = command.find_address_with(0x30, LookupPolicy::Strict).unwrap();
+    // Relative to the command, which leaves its own ordinal out
+    assert_eq!(coords.to_string(), "!0/0/0");
+
+    // Childless nodes inside the mount are still strict
+    assert!(matches!(
+        command.find_address_with(0x70, LookupPolicy::Strict),
+        Err(OffsetLayoutsError::InconsistentStructure(0x70, r)) if r == (0x60..0x100)
+    ));
+    assert_eq!(command.find_address(0x70).unwrap().to_string(), "!0/1");
+}
+
+#[test]
+fn mounts_nest() {
+    let mut inner = File::with_size(0x100);
+    inner.add_slice({
+        let mut slice = janus_array::Slices::default();
+        slice.populate_values(0x80, 0x100, 0x80, 0x100, 0);
+        slice.set_label("payload");
+        slice
+    });
+    let mut middle = member();
+    assert!(middle.mount_at(&Coordinates::from_ordinals(&[0, 1]), inner));
+    let mut outer = archive();
+    assert!(outer.mount_at(&Coordinates::from_ordinals(&[0, 2]), middle));
+
+    // 0x400 (b.o) + 0x60 (__text) + 0x90 (payload)
+    let coords = outer.find_address(0x4f0).unwrap();
+    assert_eq!(coords.to_string(), "0/2!0/1!0");
+    assert_eq!(coords.to_string().parse::<Coordinates>().unwrap(), coords);
+    assert_eq!(outer.find_address_path(0x4f0).unwrap(), "archive/b.o!__TEXT/__text!payload");
+    assert_eq!(outer.resolve(&coords).unwrap().label(), Some("payload"));
+
+    // Steps 0, 2, !, 0, 1, ! then the missing slice 3
+    let stale: Coordinates = "0/2!0/1!3".parse().unwrap();
+    assert!(matches!(outer.resolve(&stale), Err(OffsetLayoutsError::StaleCoordinates(_, 6))));
+}
+
+#[test]
+fn children_take_precedence_over_a_mount() {
+    let mut file = archive();
+    file.update_slice(0, |slice| {
+        slice.mount(member());
+    });
+
+    assert_eq!(file.find_address(0x470).unwrap().to_string(), "0/2!0/1");
+    assert_eq!(file.find_address(0x900).unwrap().to_string(), "0");
+    assert!(file.get_slice(0).unwrap().mounted().is_some());
+}
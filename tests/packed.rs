@@ -0,0 +1,225 @@
+use janus_array::builder::LayoutBuilder;
+use janus_array::diff::diff;
+use janus_array::lookup::LookupPolicy;
+use janus_array::{
+    AddressRange, CommandElements, Commands, Coordinates, DiskOffsets, File, Labeled, NodeLevel, NodeRef, OffsetLayoutsError, Slices,
+};
+
+// A symbol-table shaped layout: one command with many elements, gaps and
+// zero-length entries, next to a command without elements.
+fn layout() -> File {
+    let mut builder = LayoutBuilder::new(0x10000);
+    builder.push(NodeLevel::Slice, 0x0..0x8000, ()).unwrap();
+    builder.push(NodeLevel::Command, 0x0..0x40, ()).unwrap();
+    builder.push(NodeLevel::Command, 0x100..0x4100, ()).unwrap();
+    for i in 0..0x200 {
+        let start = 0x100 + i * 0x20;
+        match i % 5 {
+            0 => builder.push(NodeLevel::Element, start..start, ()).unwrap(),
+            1 => builder.push(NodeLevel::Element, start..start + 0x20, ()).unwrap(),
+            _ => builder.push(NodeLevel::Element, start + 4..start + 0x18, ()).unwrap(),
+        }
+    }
+    builder.push(NodeLevel::Slice, 0x8000..0x9000, ()).unwrap();
+    builder.push(NodeLevel::Command, 0x8000..0x8800, ()).unwrap();
+    builder.push(NodeLevel::Element, 0x8000..0x8400, ()).unwrap();
+    builder.finish().file
+}
+
+fn packed(file: &File) -> File {
+    let mut packed = file.clone();
+    assert_eq!(packed.pack_elements(), 2);
+    packed
+}
+
+#[test]
+fn lookups_answer_as_before() {
+    let file = layout();
+    let packed = packed(&file);
+    assert!(packed.slices()[0].commands()[1].is_packed());
+    assert_eq!(packed.slices()[0].commands()[1].element_count(), 0x200);
+
+    for address in (0..0x10000).step_by(3).chain([0x100, 0x120, 0x4100, 0x40ff]) {
+        assert_eq!(format!("{:?}", packed.locate(address)), format!("{:?}", file.locate(address)), "{:#x}", address);
+        assert_eq!(
+            format!("{:?}", packed.find_address_with(address, LookupPolicy::Strict)),
+            format!("{:?}", file.find_address_with(address, LookupPolicy::Strict)),
+        );
+        if address < 0x8000 {
+            assert_eq!(format!("{:?}", packed.find_in_slice(0, address)), format!("{:?}", file.find_in_slice(0, address)));
+        }
+    }
+    let addresses: Vec<u64> = (0..0x9000).step_by(7).collect();
+    assert_eq!(format!("{:?}", packed.find_addresses(&addresses)), format!("{:?}", file.find_addresses(&addresses)));
+}
+
+#[test]
+fn packed_elements_resolve_like_nodes() {
+    let file = layout();
+    let packed = packed(&file);
+
+    for ordinal in [0, 1, 2, 0x1ff] {
+        let coords = Coordinates::from_ordinals(&[0, 1, ordinal]);
+        let (a, b) = (file.resolve(&coords).unwrap(), packed.resolve(&coords).unwrap());
+        assert!(matches!(b, NodeRef::PackedElement(_)));
+        assert_eq!((b.level(), b.ordinal(), b.has_children()), (a.level(), a.ordinal(), a.has_children()));
+        assert_eq!((b.absolute_span(), b.relative_span()), (a.absolute_span(), a.relative_span()));
+        let start = a.absolute_span().start();
+        assert_eq!(b.absolute_to_relative(start).ok(), a.absolute_to_relative(start).ok());
+    }
+    assert!(packed.resolve(&Coordinates::from_ordinals(&[0, 1, 0x200])).is_err());
+
+    let command = &packed.slices()[0].commands()[1];
+    let element = command.packed_element(2).unwrap();
+    assert_eq!(element.to_element().get_absolute_range(), 0x144..0x158);
+    assert_eq!(command.packed_elements().count(), 0x200);
+    assert_eq!(element.ordinal(), 2);
+}
+
+#[test]
+fn node_accessors_leave_packed_elements_to_the_packed_ones() {
+    let file = layout();
+    let packed = packed(&file);
+    let (original, command) = (&file.slices()[0].commands()[1], &packed.slices()[0].commands()[1]);
+
+    // Only nodes are borrowed out; packed elements are read from the columns
+    assert!(command.elements().is_empty());
+    assert!(command.get_element(3).is_none());
+    assert_eq!(command.element_count(), original.elements().len());
+    let rebuilt: Vec<CommandElements> = command.packed_elements().map(|e| e.to_element()).collect();
+    assert_eq!(format!("{:?}", rebuilt), format!("{:?}", original.elements()));
+    let element = command.packed_element(3).unwrap().to_element();
+    assert_eq!(format!("{:?}", element), format!("{:?}", original.get_element(3).unwrap()));
+    assert_eq!(element.get_relative_range(), 0x64..0x78);
+    assert!(command.packed_element(0x200).is_none());
+    assert!(command.is_packed(), "reading doesn't unpack");
+}
+
+#[test]
+fn whole_tree_tooling_sees_no_difference() {
+    let file = layout();
+    let packed = packed(&file);
+
+    assert_eq!(packed.stats(), file.stats());
+    assert_eq!(packed.coverage(), file.coverage());
+    assert!(diff(&file, &packed).is_empty());
+
+    let mut unpacked = packed.clone();
+    unpacked.unpack_elements();
+    assert!(!unpacked.slices()[0].commands()[1].is_packed());
+    assert_eq!(unpacked.slices()[0].commands()[1].elements().len(), 0x200);
+    assert!(diff(&file, &unpacked).is_empty());
+}
+
+#[test]
+fn only_plain_elements_are_packed() {
+    fn command(elements: &[(u64, u64, usize)]) -> Commands {
+        let mut command = Commands::default();
+        command.populate_values(0x100, 0x200, 0x100, 0x200, 0);
+        for &(start, end, ordinal) in elements {
+            let mut element = CommandElements::default();
+            element.populate_values(start, end, start - 0x100, end - 0x100, ordinal);
+            command.add_element(element);
+        }
+        command
+    }
+
+    assert!(command(&[(0x100, 0x110, 0), (0x110, 0x120, 1)]).pack_elements());
+    // Nothing to pack
+    assert!(!command(&[]).pack_elements());
+    // Ordinals not in address order
+    assert!(!command(&[(0x100, 0x110, 1), (0x110, 0x120, 0)]).pack_elements());
+
+    // Relative ranges measured from somewhere else
+    let mut shifted = command(&[(0x100, 0x110, 0)]);
+    shifted.update_element(0, |e| e.set_relative_span(AddressRange::new(0x1100, 0x1110).unwrap()));
+    assert!(!shifted.pack_elements());
+
+    // Tags and mounts live on the nodes only
+    let mut labeled = command(&[(0x100, 0x110, 0)]);
+    labeled.update_element(0, |e| e.set_label("_start"));
+    assert!(!labeled.pack_elements());
+    assert_eq!(labeled.get_element(0).unwrap().label(), Some("_start"));
+}
+
+#[test]
+fn changes_to_elements_unpack_them() {
+    let mut packed = packed(&layout());
+    let coords = Coordinates::from_ordinals(&[0, 1, 3]);
+
+    assert!(packed.set_label_at(&coords, "main"));
+    assert!(!packed.slices()[0].commands()[1].is_packed());
+    assert_eq!(packed.find_by_label("main"), Some(coords));
+    assert_eq!(packed.find_address(0x166).unwrap().to_string(), "0/1/3");
+
+    let mut command = packed.slices()[1].commands()[0].clone();
+    assert!(command.is_packed());
+    command.add_element(CommandElements::with_spans(AddressRange::new(0x8400, 0x8500).unwrap(), AddressRange::new(0x400, 0x500).unwrap(), 1));
+    assert!(!command.is_packed());
+    assert_eq!(command.elements().len(), 2);
+    assert_eq!(packed.slices()[1].commands()[0].element_count(), 1, "the original is untouched");
+}
+
+#[test]
+fn edits_keep_moved_commands_packed() {
+    let file = layout();
+    let mut packed = packed(&file);
+    let mut expected = file.clone();
+
+    // Before every command: they all move and stay packed
+    let report = packed.insert_bytes(0x80, 0x10).unwrap();
+    let expected_report = expected.insert_bytes(0x80, 0x10).unwrap();
+    assert_eq!(report.shifted, expected_report.shifted);
+    assert!(packed.slices()[0].commands()[1].is_packed());
+    assert!(diff(&expected, &packed).is_empty());
+    assert_eq!(packed.find_address(0x8010).unwrap(), expected.find_address(0x8010).unwrap());
+    assert_eq!(packed.find_address(0x154).unwrap().to_string(), "0/1/2");
+
+    // After the first packed command, in its slice: it stays in place, the
+    // one in the next slice moves, and neither is unpacked
+    packed.insert_bytes(0x5000, 0x10).unwrap();
+    expected.insert_bytes(0x5000, 0x10).unwrap();
+    assert!(packed.slices()[0].commands()[1].is_packed() && packed.slices()[1].commands()[0].is_packed());
+    assert!(diff(&expected, &packed).is_empty());
+
+    // In the second slice, after its packed command: nothing in the first
+    // slice is touched
+    packed.remove_bytes(0x8a00..0x8a40).unwrap();
+    expected.remove_bytes(0x8a00..0x8a40).unwrap();
+    assert!(packed.slices()[0].commands()[1].is_packed() && packed.slices()[1].commands()[0].is_packed());
+    assert!(diff(&expected, &packed).is_empty());
+
+    // Moving past the end of the address space fails and changes nothing
+    packed.rebase(i64::MAX).unwrap();
+    assert!(packed.slices()[0].commands()[1].is_packed());
+    assert!(matches!(packed.rebase(i64::MAX), Err(OffsetLayoutsError::AddressOverflow(..))));
+    assert!(packed.slices()[0].commands()[1].is_packed());
+    packed.rebase(-i64::MAX).unwrap();
+    assert!(diff(&expected, &packed).is_empty());
+
+    // Inside a packed command: its elements have to be rewritten
+    packed.remove_bytes(0x200..0x240).unwrap();
+    expected.remove_bytes(0x200..0x240).unwrap();
+    assert!(!packed.slices()[0].commands()[1].is_packed());
+    assert!(diff(&expected, &packed).is_empty());
+}
+
+#[test]
+fn packing_reaches_the_end_of_the_address_space() {
+    let mut command = Commands::with_spans(AddressRange::<u16>::to_end_of_space(0xff00), AddressRange::from_bounds(0, 0x100).unwrap(), 0);
+    command.add_element(CommandElements::with_spans(AddressRange::new(0xff00, 0xff80).unwrap(), AddressRange::new(0, 0x80).unwrap(), 0));
+    command.add_element(CommandElements::with_spans(
+        AddressRange::to_end_of_space(0xff80),
+        AddressRange::from_bounds(0x80, 0x100).unwrap(),
+        1,
+    ));
+    let mut slice = Slices::with_spans(AddressRange::to_end_of_space(0xf000), AddressRange::from_bounds(0, 0x1000).unwrap(), 0);
+    slice.add_command(command);
+    let mut file = File::<u16>::with_span(AddressRange::to_end_of_space(0));
+    file.add_slice(slice);
+
+    assert_eq!(file.pack_elements(), 1);
+    let coords = file.find_address(u16::MAX).unwrap();
+    assert_eq!(coords.to_string(), "0/0/1");
+    assert_eq!(file.resolve(&coords).unwrap().absolute_span().end(), None);
+}
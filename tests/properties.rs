@@ -0,0 +1,290 @@
+//! Random valid hierarchies checked against a brute-force oracle.
+//!
+//! Every case is generated from a seed derived from `JANUS_PROPTEST_SEED`
+//! (default fixed) and the case number; a failure prints the seed of the
+//! case, which `JANUS_PROPTEST_SEED` + `JANUS_PROPTEST_CASES=1` replays.
+
+use std::env;
+use std::fmt;
+
+use janus_array::{
+    Address, AddressRange, CommandElements, Commands, Coordinates, DiskOffsets, File, OffsetLayoutsError, Slices,
+};
+
+const DEFAULT_SEED: u64 = 0x6a61_6e75_7321;
+const DEFAULT_CASES: u64 = 256;
+// Node edges probed per case, on top of random addresses
+const MAX_PROBES: usize = 512;
+
+// SplitMix64: tiny, fast and good enough to drive test generation.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, n: u128) -> u128 {
+        if n == 0 {
+            return 0;
+        }
+        ((self.next() as u128) << 64 | self.next() as u128) % n
+    }
+
+    fn chance(&mut self, percent: u64) -> bool {
+        self.next() % 100 < percent
+    }
+}
+
+// Plain description of a node, mirrored into the real structure.
+#[derive(Debug, Clone)]
+struct Node {
+    start: u128,
+    end: u128,
+    ordinal: usize,
+    children: Vec<Node>,
+}
+
+impl Node {
+    fn contains(&self, address: u128) -> bool {
+        (self.start..self.end).contains(&address) || (self.start == self.end && self.start == address)
+    }
+}
+
+// Children of `start..end`: disjoint non-empty ranges in random places, with
+// the odd zero-length node and ordinals in random order. `space_end` is one
+// past the last address.
+fn children(rng: &mut Rng, start: u128, end: u128, space_end: u128, depth: usize) -> Vec<Node> {
+    if depth == 3 || start == end {
+        return Vec::new();
+    }
+    // Wide fan-out only at the top, to keep layouts small
+    let count = match rng.next() % 10 {
+        0 => 0,
+        1 => 1,
+        2 if depth == 0 => 24 + rng.below(40) as usize,
+        _ => 2 + rng.below(6) as usize,
+    };
+    let mut bounds: Vec<u128> = (0..count * 2).map(|_| start + rng.below(end - start + 1)).collect();
+    if rng.chance(20) {
+        // Children touching both ends of the parent
+        bounds.push(start);
+        bounds.push(end);
+    }
+    bounds.sort_unstable();
+    let mut ranges: Vec<(u128, u128)> = bounds.chunks(2).filter(|p| p.len() == 2).map(|p| (p[0], p[1])).collect();
+    // Keep at most one zero-length node per address, and none past the last one
+    ranges.retain(|&(s, e)| s != e || s < space_end);
+    ranges.dedup_by(|a, b| a.0 == a.1 && b.0 == b.1 && a.0 == b.0);
+    for _ in 0..rng.below(3) {
+        let at = start + rng.below(end - start);
+        if !ranges.iter().any(|&(s, e)| s == e && s == at) {
+            ranges.push((at, at));
+        }
+    }
+
+    let mut ordinals: Vec<usize> = (0..ranges.len()).collect();
+    for i in (1..ordinals.len()).rev() {
+        ordinals.swap(i, rng.below(i as u128 + 1) as usize);
+    }
+    ranges
+        .into_iter()
+        .zip(ordinals)
+        .map(|((s, e), ordinal)| Node { start: s, end: e, ordinal, children: children(rng, s, e, space_end, depth + 1) })
+        .collect()
+}
+
+fn span<A: Address>(start: u128, end: u128) -> AddressRange<A> {
+    AddressRange::from_bounds(start, end).expect("generated inside the address space")
+}
+
+fn build<A: Address>(root: &Node, rng: &mut Rng) -> File<A> {
+    let mut file = File::with_span(span(root.start, root.end));
+    // Insert in random order: the containers keep themselves sorted
+    let mut slices = root.children.clone();
+    shuffle(&mut slices, rng);
+    for s in &slices {
+        let mut slice = Slices::with_spans(span(s.start, s.end), span(s.start - root.start, s.end - root.start), s.ordinal);
+        let mut commands = s.children.clone();
+        shuffle(&mut commands, rng);
+        for c in &commands {
+            let mut command = Commands::with_spans(span(c.start, c.end), span(c.start - s.start, c.end - s.start), c.ordinal);
+            let mut elements = c.children.clone();
+            shuffle(&mut elements, rng);
+            for e in &elements {
+                command.add_element(CommandElements::with_spans(
+                    span(e.start, e.end),
+                    span(e.start - c.start, e.end - c.start),
+                    e.ordinal,
+                ));
+            }
+            slice.add_command(command);
+        }
+        file.add_slice(slice);
+    }
+    file
+}
+
+fn shuffle(nodes: &mut [Node], rng: &mut Rng) {
+    for i in (1..nodes.len()).rev() {
+        nodes.swap(i, rng.below(i as u128 + 1) as usize);
+    }
+}
+
+// Linear oracle: at each level a non-empty child containing the address wins,
+// then a zero-length child starting at it; the deepest match is the answer.
+fn oracle(root: &Node, address: u128) -> Option<Option<Coordinates>> {
+    if !root.contains(address) {
+        return None;
+    }
+    let mut path = Vec::new();
+    let mut node = root;
+    loop {
+        let hit = node
+            .children
+            .iter()
+            .find(|c| c.start != c.end && c.contains(address))
+            .or_else(|| node.children.iter().find(|c| c.start == c.end && c.start == address));
+        match hit {
+            Some(child) => {
+                path.push(child.ordinal);
+                node = child;
+            }
+            None if path.is_empty() => return Some(None),
+            None => return Some(Some(Coordinates::from_ordinals(&path))),
+        }
+    }
+}
+
+fn probes(root: &Node, rng: &mut Rng) -> Vec<u128> {
+    fn edges(node: &Node, out: &mut Vec<u128>) {
+        out.extend([node.start.wrapping_sub(1), node.start, node.end.wrapping_sub(1), node.end]);
+        out.push(node.start + (node.end - node.start) / 2);
+        for child in &node.children {
+            edges(child, out);
+        }
+    }
+    let mut out = Vec::new();
+    edges(root, &mut out);
+    for i in (1..out.len()).rev() {
+        out.swap(i, rng.below(i as u128 + 1) as usize);
+    }
+    out.truncate(MAX_PROBES);
+    out.extend((0..32).map(|_| root.start + rng.below(root.end - root.start + 1)));
+    out
+}
+
+fn check_case<A: Address>(seed: u64, space_end: u128) {
+    let mut rng = Rng(seed);
+    // Files anywhere in the space, some of them running to its very end
+    let size = 1 + rng.below(space_end.min(1 << 40));
+    let start = if rng.chance(25) { space_end - size } else { rng.below(space_end - size + 1) };
+    let root = Node { start, end: start + size, ordinal: 0, children: children(&mut rng, start, start + size, space_end, 0) };
+    let file: File<A> = build(&root, &mut rng);
+
+    let addresses: Vec<A> = probes(&root, &mut rng).into_iter().filter_map(A::from_wide).collect();
+    let batch = file.find_addresses(&addresses);
+    for (&address, batched) in addresses.iter().zip(&batch) {
+        let wide = address.to_wide();
+        let found = file.find_address(address);
+        let context = Context { seed, address: wide, root: &root };
+        match oracle(&root, wide) {
+            None => assert!(
+                matches!(found, Err(OffsetLayoutsError::AddressOutsideCurrentScope(a, _)) if a == address),
+                "{}: {:?}",
+                context,
+                found
+            ),
+            Some(None) => assert!(matches!(found, Err(OffsetLayoutsError::NotFound(a)) if a == address), "{}: {:?}", context, found),
+            Some(Some(expected)) => {
+                assert_eq!(found.as_ref().ok(), Some(&expected), "{}", context);
+                let node = file.resolve(&expected).unwrap();
+                assert!(node.absolute_span().contains(address) || node.absolute_span().start() == address, "{}", context);
+                assert_eq!(file.locate(address).unwrap().coordinates, expected, "{}", context);
+
+                let slice = file.get_slice(expected.slice.unwrap()).unwrap();
+                let relative = slice.absolute_to_relative(address).unwrap();
+                assert_eq!(file.find_in_slice(expected.slice.unwrap(), relative).unwrap(), expected, "{}", context);
+            }
+        }
+        assert_eq!(format!("{:?}", batched), format!("{:?}", found), "{}", context);
+    }
+}
+
+// Printed by failing assertions only: layouts can be large.
+struct Context<'a> {
+    seed: u64,
+    address: u128,
+    root: &'a Node,
+}
+
+impl fmt::Display for Context<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "seed {:#x}, address {:#x}, layout {:?}", self.seed, self.address, self.root)
+    }
+}
+
+fn run(space_end: u128, check: fn(u64, u128)) {
+    let seed = env::var("JANUS_PROPTEST_SEED")
+        .ok()
+        .and_then(|s| u64::from_str_radix(s.trim_start_matches("0x"), 16).ok())
+        .unwrap_or(DEFAULT_SEED);
+    let cases = env::var("JANUS_PROPTEST_CASES").ok().and_then(|s| s.parse().ok()).unwrap_or(DEFAULT_CASES);
+    let mut seeds = Rng(seed);
+    for case in 0..cases {
+        // The first case uses the seed itself, so a printed seed replays alone
+        let case_seed = if case == 0 { seed } else { seeds.next() };
+        check(case_seed, space_end);
+    }
+}
+
+#[test]
+fn find_address_matches_the_oracle_u64() {
+    run(1 << 64, check_case::<u64>);
+}
+
+#[test]
+fn find_address_matches_the_oracle_u32() {
+    run(1 << 32, check_case::<u32>);
+}
+
+#[test]
+fn find_address_matches_the_oracle_u16() {
+    // Small spaces make collisions, zero-length nodes and the top of the
+    // space far more likely
+    run(1 << 16, check_case::<u16>);
+}
+
+// Shapes that used to trip the binary search.
+#[test]
+fn binary_search_edge_cases() {
+    fn file(ranges: &[(u64, u64)]) -> File {
+        let mut file = File::with_size(0x1000);
+        for (i, &(start, end)) in ranges.iter().enumerate() {
+            let mut slice = Slices::default();
+            slice.populate_values(start, end, start, end, i);
+            file.add_slice(slice);
+        }
+        file
+    }
+
+    // No children, a single one, and addresses on either side of it
+    assert!(matches!(file(&[]).find_address(0), Err(OffsetLayoutsError::NotFound(0))));
+    let single = file(&[(0x10, 0x20)]);
+    assert!(matches!(single.find_address(0x0), Err(OffsetLayoutsError::NotFound(0x0))));
+    assert!(matches!(single.find_address(0xf), Err(OffsetLayoutsError::NotFound(0xf))));
+    assert_eq!(single.find_address(0x10).unwrap().slice, Some(0));
+    assert_eq!(single.find_address(0x1f).unwrap().slice, Some(0));
+    assert!(matches!(single.find_address(0x20), Err(OffsetLayoutsError::NotFound(0x20))));
+
+    // The first child starting at 0, and the last one ending with the parent
+    let full = file(&[(0x0, 0x1), (0x1, 0x800), (0x800, 0x1000)]);
+    assert_eq!(full.find_address(0x0).unwrap().slice, Some(0));
+    assert_eq!(full.find_address(0x1).unwrap().slice, Some(1));
+    assert_eq!(full.find_address(0xfff).unwrap().slice, Some(2));
+    assert!(full.find_address(0x1000).is_err());
+}
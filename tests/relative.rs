@@ -0,0 +1,93 @@
+use janus_array::builder::LayoutBuilder;
+use janus_array::{
+    AddressRange, Coordinates, DiskOffsets, File, NodeLevel, NodeRef, OffsetLayoutsError, Segmented, Slices,
+};
+
+// Two fat slices; load command offsets are relative to their slice.
+fn fat() -> File {
+    let mut builder = LayoutBuilder::new(0x10000);
+    for base in [0x1000, 0x9000] {
+        builder.push(NodeLevel::Slice, base..base + 0x4000, ()).unwrap();
+        builder.push(NodeLevel::Command, base + 0x20..base + 0x68, ()).unwrap();
+        builder.push(NodeLevel::Command, base + 0x68..base + 0x100, ()).unwrap();
+        builder.push(NodeLevel::Element, base + 0x70..base + 0x80, ()).unwrap();
+    }
+    builder.finish().file
+}
+
+#[test]
+fn find_in_slice_translates_through_the_slice_base() {
+    let file = fat();
+
+    let coords = file.find_in_slice(1, 0x74).unwrap();
+    assert_eq!(coords, Coordinates::from_ordinals(&[1, 1, 0]));
+    assert_eq!(coords, file.find_address(0x9074).unwrap());
+    assert_eq!(file.find_in_slice(0, 0x30).unwrap(), Coordinates::from_ordinals(&[0, 0]));
+
+    let slice = file.get_slice(0).unwrap();
+    assert_eq!(slice.find_relative_address(0x74).unwrap(), file.find_in_slice(0, 0x74).unwrap());
+}
+
+#[test]
+fn relative_lookups_stay_in_their_subtree() {
+    let file = fat();
+
+    // 0x8000 past slice 0 lands in slice 1 absolutely, but not relatively
+    assert!(file.find_address(0x1000 + 0x8020).is_ok());
+    assert!(matches!(
+        file.find_in_slice(0, 0x8020),
+        Err(OffsetLayoutsError::AddressOutsideCurrentScope(0x8020, r)) if r == (0..0x4000)
+    ));
+    assert!(matches!(file.find_in_slice(2, 0), Err(OffsetLayoutsError::StaleCoordinates(_, 0))));
+}
+
+#[test]
+fn conversions_round_trip_at_every_level() {
+    let file = fat();
+    let command = file.get_slice(1).unwrap().get_command(1).unwrap();
+    let element = command.get_element(0).unwrap();
+
+    assert_eq!(file.relative_to_absolute(0x9074).unwrap(), 0x9074);
+    assert_eq!(command.relative_to_absolute(0xc).unwrap(), 0x9074);
+    assert_eq!(element.relative_to_absolute(0x4).unwrap(), 0x9074);
+    assert_eq!(command.absolute_to_relative(0x9074).unwrap(), 0xc);
+    assert_eq!(element.absolute_to_relative(0x9074).unwrap(), 0x4);
+    assert_eq!(command.find_relative_address(0xc).unwrap().element, Some(0));
+
+    assert!(element.relative_to_absolute(0x10).is_err());
+    assert!(matches!(element.absolute_to_relative(0x9000), Err(OffsetLayoutsError::AddressOutsideCurrentScope(0x9000, _))));
+}
+
+#[test]
+fn built_relative_ranges_match_the_conversions() {
+    fn check(node: &NodeRef<'_>) {
+        let start = node.absolute_span().start();
+        for child in node.children() {
+            let child_start = child.absolute_span().start();
+            let relative = child.relative_span().start();
+            assert_eq!(node.absolute_to_relative(child_start).unwrap(), relative, "child of node at {:#x}", start);
+            assert_eq!(node.relative_to_absolute(relative).unwrap(), child_start);
+            check(&child);
+        }
+    }
+    check(&NodeRef::File(&fat()));
+}
+
+#[test]
+fn conversions_never_overflow() {
+    let top = Slices::with_spans(
+        AddressRange::to_end_of_space(u64::MAX - 0xf),
+        AddressRange::new(0, 0x10).unwrap(),
+        0,
+    );
+    assert_eq!(top.relative_to_absolute(0xf).unwrap(), u64::MAX);
+    assert!(top.relative_to_absolute(0x10).is_err());
+    assert!(top.relative_to_absolute(u64::MAX).is_err());
+    assert_eq!(top.absolute_to_relative(u64::MAX).unwrap(), 0xf);
+
+    // Segmented offsets carry into the next segment
+    let span = AddressRange::from_offset_size(Segmented::new(1, 0xffff_fff0), 0x20).unwrap();
+    let slice = Slices::with_spans(span, span, 0);
+    assert_eq!(slice.relative_to_absolute(Segmented::new(0, 0x18)).unwrap(), Segmented::new(2, 0x8));
+    assert_eq!(slice.absolute_to_relative(Segmented::new(2, 0x8)).unwrap(), Segmented::new(0, 0x18));
+}
@@ -0,0 +1,93 @@
+#![cfg(feature = "std")]
+
+use std::thread;
+
+use janus_array::versioned::VersionedFile;
+use janus_array::{CommandElements, Commands, DiskOffsets, File, Slices};
+
+fn slice(ordinal: usize, start: u64, commands: u64) -> Slices {
+    let mut slice = Slices::default();
+    slice.populate_values(start, start + 0x1000, start, start + 0x1000, ordinal);
+    for c in 0..commands {
+        let cmd_start = start + c * 0x100;
+        let mut command = Commands::default();
+        command.populate_values(cmd_start, cmd_start + 0x100, c * 0x100, (c + 1) * 0x100, c as usize);
+        let mut element = CommandElements::default();
+        element.populate_values(cmd_start, cmd_start + 0x10, 0, 0x10, 0);
+        command.add_element(element);
+        slice.add_command(command);
+    }
+    slice
+}
+
+#[test]
+fn clones_share_untouched_subtrees() {
+    let mut original = File::with_size(0x2000);
+    original.add_slice(slice(0, 0, 4));
+    original.add_slice(slice(1, 0x1000, 4));
+
+    let mut edited = original.clone();
+    edited.update_slice(1, |s| {
+        s.update_command(2, |c| {
+            let mut element = CommandElements::default();
+            element.populate_values(0x1280, 0x1290, 0x80, 0x90, 1);
+            c.add_element(element);
+        })
+    });
+
+    // The untouched slice still shares its commands with the original
+    let untouched = |f: &File| f.get_slice(0).unwrap().commands().as_ptr();
+    assert_eq!(untouched(&original), untouched(&edited));
+
+    // The edited path was copied, the original did not change
+    assert_eq!(original.get_slice(1).unwrap().get_command(2).unwrap().elements().len(), 1);
+    assert_eq!(edited.get_slice(1).unwrap().get_command(2).unwrap().elements().len(), 2);
+    assert_eq!(edited.find_address(0x1284).unwrap().element, Some(1));
+    assert_eq!(original.find_address(0x1284).unwrap().element, None);
+}
+
+#[test]
+fn snapshots_stay_stable_while_writers_publish() {
+    let layout = VersionedFile::new(File::with_size(0x10000));
+    layout.update(|f| f.add_slice(slice(0, 0, 16)));
+
+    let pinned = layout.snapshot();
+    thread::scope(|scope| {
+        scope.spawn(|| {
+            for s in 1..16u64 {
+                layout.update(|f| f.add_slice(slice(s as usize, s * 0x1000, 16)));
+            }
+        });
+        for _ in 0..4 {
+            scope.spawn(|| {
+                for _ in 0..200 {
+                    let snapshot = layout.snapshot();
+                    let slices = snapshot.slices().len() as u64;
+                    // Everything a snapshot contains is findable in it
+                    let addr = slices * 0x1000 - 1;
+                    assert_eq!(snapshot.find_address(addr).unwrap().slice, Some(slices as usize - 1));
+                    assert!(pinned.find_address(0x1000).is_err());
+                }
+            });
+        }
+    });
+
+    assert_eq!(pinned.slices().len(), 1);
+    assert_eq!(layout.snapshot().slices().len(), 16);
+    assert_eq!(layout.version(), 16);
+}
+
+#[test]
+fn removal_produces_a_new_version() {
+    let layout = VersionedFile::new(File::with_size(0x2000));
+    layout.update(|f| {
+        f.add_slice(slice(0, 0, 2));
+        f.add_slice(slice(1, 0x1000, 2));
+    });
+    let before = layout.snapshot();
+    let removed = layout.update(|f| f.update_slice(0, |s| s.remove_command(1)).flatten());
+
+    assert!(removed.is_some());
+    assert_eq!(before.get_slice(0).unwrap().commands().len(), 2);
+    assert_eq!(layout.snapshot().get_slice(0).unwrap().commands().len(), 1);
+}
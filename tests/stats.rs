@@ -0,0 +1,83 @@
+use janus_array::builder::LayoutBuilder;
+use janus_array::{Coordinates, File, NodeLevel, SizeHistogram};
+
+// Two slices; the second has an oversized command and a hole at the end.
+fn layout() -> File {
+    let mut builder = LayoutBuilder::new(0x10000);
+    builder.push(NodeLevel::Slice, 0x0..0x1000, ()).unwrap();
+    builder.push(NodeLevel::Command, 0x0..0x20, ()).unwrap();
+    builder.push(NodeLevel::Element, 0x0..0x8, ()).unwrap();
+    builder.push(NodeLevel::Element, 0x8..0x10, ()).unwrap();
+    builder.push(NodeLevel::Element, 0x10..0x18, ()).unwrap();
+    builder.push(NodeLevel::Command, 0x20..0x1000, ()).unwrap();
+    builder.push(NodeLevel::Slice, 0x8000..0xc000, ()).unwrap();
+    builder.push(NodeLevel::Command, 0x8000..0x8010, ()).unwrap();
+    builder.push(NodeLevel::Command, 0x8010..0xa010, ()).unwrap();
+    builder.finish().file
+}
+
+#[test]
+fn counts_and_sizes_per_level() {
+    let stats = layout().stats();
+
+    assert_eq!((stats.slices.count, stats.commands.count, stats.elements.count), (2, 4, 3));
+    assert_eq!(stats.node_count(), 9);
+    assert_eq!(stats.slices.total_size, 0x5000);
+    assert_eq!(stats.commands.total_size, 0x20 + 0xfe0 + 0x10 + 0x2000);
+    assert_eq!(stats.elements.average_size(), 8.0);
+    assert_eq!(stats.level(NodeLevel::Element), Some(&stats.elements));
+    assert!(stats.level(NodeLevel::File).is_none());
+
+    let (coords, range) = stats.commands.largest.clone().unwrap();
+    assert_eq!(coords, Coordinates::from_ordinals(&[1, 1]));
+    assert_eq!(range.to_range(), 0x8010..0xa010);
+    let (coords, _) = stats.elements.smallest.clone().unwrap();
+    assert_eq!(coords, Coordinates::from_ordinals(&[0, 0, 0]), "ties keep the first node");
+}
+
+#[test]
+fn fan_out_and_parent_coverage() {
+    let stats = layout().stats();
+
+    assert_eq!(stats.slices.max_fan_out, 2);
+    assert_eq!(stats.commands.max_fan_out, 2);
+    assert_eq!(stats.elements.max_fan_out, 3);
+    assert_eq!(stats.max_fan_out(), 3);
+
+    assert_eq!((stats.slices.parent_covered, stats.slices.parent_size), (0x5000, 0x10000));
+    // Only the command with elements counts as a parent of elements
+    assert_eq!((stats.elements.parent_covered, stats.elements.parent_size), (0x18, 0x20));
+    assert_eq!(stats.elements.parent_ratio(), 0.75);
+    assert_eq!(stats.coverage, layout().coverage());
+}
+
+#[test]
+fn histogram_buckets_by_power_of_two() {
+    let stats = layout().stats();
+    let buckets: Vec<_> = stats.commands.histogram.iter().collect();
+    // 0x10, 0x20, 0xfe0 and 0x2000 bytes
+    assert_eq!(buckets, vec![(0x10, 1), (0x20, 1), (0x800, 1), (0x2000, 1)]);
+    assert_eq!(stats.elements.histogram.buckets().iter().sum::<usize>(), 3);
+
+    assert_eq!(SizeHistogram::lower_bound(0), 0);
+    assert_eq!(SizeHistogram::lower_bound(1), 1);
+    assert_eq!(SizeHistogram::lower_bound(13), 0x1000);
+}
+
+#[test]
+fn empty_layouts_have_neutral_figures() {
+    let stats = File::with_size(0x100).stats();
+    assert_eq!(stats.node_count(), 0);
+    assert_eq!(stats.commands.average_size(), 0.0);
+    assert_eq!(stats.commands.parent_ratio(), 1.0);
+    assert!(stats.slices.largest.is_none());
+    assert!(stats.to_string().contains("coverage  0.0%"));
+}
+
+#[test]
+fn report_lists_every_level() {
+    let report = layout().stats().to_string();
+    assert!(report.contains("commands         4 nodes"));
+    assert!(report.contains("largest 0x8010..0xa010 at 1/1"));
+    assert!(report.lines().last().unwrap().starts_with("coverage"));
+}
@@ -0,0 +1,101 @@
+use janus_array::builder::LayoutBuilder;
+use janus_array::workspace::{Workspace, WorkspaceError};
+use janus_array::{AddressRange, Coordinates, Coverage, DiskOffsets, File, Labeled, NodeLevel, OffsetLayoutsError, Slices};
+
+// A small binary: one labeled segment at `base` holding two commands with a
+// gap between them, then unclaimed bytes up to `size`.
+fn binary(size: u64, base: u64) -> File {
+    let mut builder = LayoutBuilder::new(size);
+    builder.push(NodeLevel::Slice, base..base + 0x100, "__TEXT").unwrap();
+    builder.push(NodeLevel::Command, base..base + 0x40, "__text").unwrap();
+    builder.push(NodeLevel::Command, base + 0x80..base + 0x100, "__const").unwrap();
+    let built = builder.finish();
+    let mut file = built.file;
+    for (coords, label) in built.payloads {
+        file.set_label_at(&coords, label);
+    }
+    file
+}
+
+fn bundle() -> Workspace {
+    let mut workspace = Workspace::new();
+    workspace.insert("bin/sh".to_string(), binary(0x200, 0x0));
+    workspace.insert("bin/init".to_string(), binary(0x400, 0x100));
+    workspace.insert("lib/empty.so".to_string(), File::with_size(0x80));
+    workspace
+}
+
+#[test]
+fn queries_by_file_and_offset() {
+    let workspace = bundle();
+    assert_eq!(workspace.len(), 3);
+
+    assert_eq!(workspace.find_address("bin/init", 0x190).unwrap(), Coordinates::from_ordinals(&[0, 1]));
+    assert_eq!(workspace.find_address("bin/sh", 0x10).unwrap(), Coordinates::from_ordinals(&[0, 0]));
+    assert!(matches!(workspace.find_address("bin/ls", 0x10), Err(WorkspaceError::UnknownFile)));
+    assert!(matches!(
+        workspace.find_address("bin/sh", 0x1000),
+        Err(WorkspaceError::Lookup(OffsetLayoutsError::AddressOutsideCurrentScope(0x1000, _)))
+    ));
+    assert!(!workspace.locate("bin/init", 0x150).unwrap().is_leaf());
+
+    let results = workspace.find_addresses(&[("bin/sh", 0x90), ("bin/init", 0x90), ("nope", 0)]);
+    assert_eq!(results[0].as_ref().unwrap().command, Some(1));
+    assert!(matches!(results[1], Err(WorkspaceError::Lookup(OffsetLayoutsError::NotFound(0x90)))));
+    assert!(results[2].is_err());
+}
+
+#[test]
+fn label_search_spans_every_file_in_key_order() {
+    let workspace = bundle();
+
+    let hits = workspace.find_by_label("__const");
+    let files: Vec<&str> = hits.iter().map(|h| h.file.as_str()).collect();
+    assert_eq!(files, ["bin/init", "bin/sh"]);
+    assert!(hits.iter().all(|h| h.path == "__TEXT/__const" && h.coordinates == Coordinates::from_ordinals(&[0, 1])));
+    assert!(workspace.find_by_label("__DATA").is_empty());
+}
+
+#[test]
+fn coverage_per_file_and_aggregated() {
+    let workspace = bundle();
+
+    let per_file: Vec<(&str, Coverage)> = workspace.coverage_by_file().into_iter().map(|(k, c)| (k.as_str(), c)).collect();
+    // bin/init: [0x0, 0x100) before, [0x140, 0x180) inside, [0x200, 0x400) after
+    assert_eq!(per_file[0], ("bin/init", Coverage { total: 0x400, covered: 0xc0, gaps: 3, largest_gap: 0x200 }));
+    assert_eq!(per_file[1], ("bin/sh", Coverage { total: 0x200, covered: 0xc0, gaps: 2, largest_gap: 0x100 }));
+    assert_eq!(per_file[2], ("lib/empty.so", Coverage { total: 0x80, covered: 0, gaps: 1, largest_gap: 0x80 }));
+
+    let total = workspace.coverage();
+    assert_eq!(total, Coverage { total: 0x680, covered: 0x180, gaps: 6, largest_gap: 0x200 });
+    assert_eq!(total.unclaimed(), 0x500);
+    assert!((total.ratio() - 0x180 as f64 / 0x680 as f64).abs() < 1e-12);
+}
+
+#[test]
+fn files_can_be_replaced_and_edited() {
+    let mut workspace: Workspace<&str> = [("a", binary(0x100, 0x0)), ("b", binary(0x100, 0x0))].into_iter().collect();
+    assert!(workspace.insert("a", File::with_size(0x10)).is_some());
+    assert_eq!(workspace.get("a").unwrap().coverage().covered, 0);
+
+    workspace.get_mut("b").unwrap().update_slice(0, |s| s.remove_command(1));
+    assert_eq!(workspace.find_address("b", 0x90).unwrap(), Coordinates::from_ordinals(&[0]));
+    assert!(workspace.remove("b").is_some());
+    assert_eq!(workspace.iter().map(|(k, _)| *k).collect::<Vec<_>>(), ["a"]);
+}
+
+#[test]
+fn gaps_ignore_markers_and_reach_the_top() {
+    let mut file = File::with_span(AddressRange::to_end_of_space(u64::MAX - 0xff));
+    let mut slice = Slices::with_spans(AddressRange::new(u64::MAX - 0xff, u64::MAX - 0x7f).unwrap(), AddressRange::new(0, 0x80).unwrap(), 0);
+    slice.set_label("low");
+    file.add_slice(slice);
+    // A zero-length slice doesn't split the gap it sits in
+    file.add_slice(Slices::with_spans(AddressRange::new(u64::MAX - 0x10, u64::MAX - 0x10).unwrap(), AddressRange::default(), 1));
+
+    let gaps = file.gaps();
+    assert_eq!(gaps, vec![AddressRange::to_end_of_space(u64::MAX - 0x7f)]);
+    let coverage = file.coverage();
+    assert_eq!((coverage.total, coverage.covered, coverage.largest_gap), (0x100, 0x80, 0x80));
+    assert!(file.get_slice(0).unwrap().get_max_abs_address() < u64::MAX);
+}
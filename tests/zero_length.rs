@@ -0,0 +1,79 @@
+use janus_array::builder::LayoutBuilder;
+use janus_array::{Commands, DiskOffsets, File, NodeLevel, OffsetLayoutsError, Slices};
+
+fn command(ordinal: usize, offset: u64, size: u64) -> Commands {
+    let mut command = Commands::default();
+    command.populate_offset_size(offset, offset, size, ordinal).unwrap();
+    command
+}
+
+fn file_with(commands: Vec<Commands>) -> File {
+    let mut file = File::with_size(0x1000);
+    let mut slice = Slices::default();
+    slice.populate_offset_size(0, 0, 0x1000, 0).unwrap();
+    for c in commands {
+        slice.add_command(c);
+    }
+    file.add_slice(slice);
+    file
+}
+
+#[test]
+fn zero_length_node_is_found_at_its_start_address() {
+    let file = file_with(vec![command(0, 0x0, 0x100), command(1, 0x200, 0), command(2, 0x300, 0x100)]);
+
+    assert_eq!(file.find_address(0x200).unwrap().command, Some(1));
+    // Nothing owns the bytes around it
+    assert_eq!(file.find_address(0x1ff).unwrap().command, None);
+    assert_eq!(file.find_address(0x201).unwrap().command, None);
+}
+
+#[test]
+fn bytes_belong_to_the_node_owning_them() {
+    // A marker at the start of a non-empty command, and one inside it
+    let file = file_with(vec![command(0, 0x100, 0), command(1, 0x100, 0x100), command(2, 0x180, 0)]);
+
+    assert_eq!(file.find_address(0x100).unwrap().command, Some(1));
+    assert_eq!(file.find_address(0x180).unwrap().command, Some(1));
+    assert_eq!(file.find_address(0x1ff).unwrap().command, Some(1));
+}
+
+#[test]
+fn siblings_are_ordered_deterministically() {
+    let forward = file_with(vec![command(3, 0x100, 0x10), command(1, 0x100, 0), command(2, 0x100, 0)]);
+    let backward = file_with(vec![command(2, 0x100, 0), command(1, 0x100, 0), command(3, 0x100, 0x10)]);
+
+    let order = |f: &File| -> Vec<usize> { f.slices()[0].commands().iter().map(|c| c.get_ordinal()).collect() };
+    assert_eq!(order(&forward), vec![1, 2, 3]);
+    assert_eq!(order(&backward), vec![1, 2, 3]);
+
+    // With only markers at an address, the first one in order answers
+    let markers = file_with(vec![command(7, 0x400, 0), command(5, 0x400, 0)]);
+    assert_eq!(markers.find_address(0x400).unwrap().command, Some(5));
+}
+
+#[test]
+fn offset_size_constructor_rejects_overflow() {
+    let mut command = Commands::default();
+    let err = command.populate_offset_size(u64::MAX - 1, 0, 3, 0).unwrap_err();
+    assert!(matches!(err, OffsetLayoutsError::AddressOverflow(..)));
+
+    let mut builder: LayoutBuilder<()> = LayoutBuilder::new(0x100);
+    assert!(builder.push_sized(NodeLevel::Slice, u64::MAX, 2, ()).is_err());
+    assert!(builder.is_empty());
+}
+
+#[test]
+fn builder_places_zero_length_records() {
+    let mut builder = LayoutBuilder::new(0x1000);
+    builder.push(NodeLevel::Slice, 0x0..0x1000, "slice").unwrap();
+    builder.push_sized(NodeLevel::Command, 0x100, 0x100, "section").unwrap();
+    builder.push_sized(NodeLevel::Command, 0x180, 0, "marker inside section").unwrap();
+    builder.push_sized(NodeLevel::Command, 0x800, 0, "empty section").unwrap();
+    builder.push_sized(NodeLevel::Element, 0x800, 0, "symbol in empty section").unwrap();
+
+    let built = builder.finish();
+    assert!(built.orphans.is_empty(), "{:?}", built.orphans);
+    let coords = built.file.find_address(0x800).unwrap();
+    assert_eq!((coords.command, coords.element), (Some(2), Some(0)));
+}